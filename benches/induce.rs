@@ -0,0 +1,135 @@
+//! Times the induction of a grammar and compares the hashers for rule counting.
+//! Uses the treebank given by PCFG_TOOL_TREEBANK (one tree per line, e.g. from hack/get_corpora.sh)
+//! and falls back to a synthetic treebank otherwise.
+//!
+//! cargo bench --bench induce
+
+use std::{
+    collections::HashMap,
+    hash::{BuildHasher, RandomState},
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use pcfg_tool::{
+    induce::PCFGGrammar,
+    ptb::{Descendants, PTBParser, ParseTree},
+    Grammar,
+};
+use rustc_hash::FxBuildHasher;
+
+const RUNS: u32 = 5;
+
+fn main() {
+    let trees = match std::env::var("PCFG_TOOL_TREEBANK") {
+        Ok(path) => std::fs::read_to_string(&path)
+            .expect("Couldn't read treebank")
+            .lines()
+            .map(|line| PTBParser::parse(line).expect("Treebank should be parsable"))
+            .collect(),
+        Err(_) => synthetic_treebank(40_000),
+    };
+    println!("{} trees", trees.len());
+
+    let induction = time(|| {
+        Grammar::from_parse_trees("ROOT".to_string(), trees.clone())
+            .expect("Treebank should be inducable")
+    });
+    println!("induce: {:?}", induction);
+
+    // Rules consist of a head and a body of strings, so the keys are hashed like Rule
+    let mut keys = Vec::new();
+    for tree in trees.iter() {
+        collect_rule_keys(tree, &mut keys);
+    }
+
+    let sip = time(|| count(&keys, RandomState::new()));
+    let fx = time(|| count(&keys, FxBuildHasher));
+    println!(
+        "count {} rules: SipHash {:?}, FxHash {:?} ({:.2}x)",
+        keys.len(),
+        sip,
+        fx,
+        sip.as_secs_f64() / fx.as_secs_f64()
+    );
+}
+
+/// Returns the mean duration over all runs
+fn time<T, F: FnMut() -> T>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..RUNS {
+        black_box(f());
+    }
+
+    start.elapsed() / RUNS
+}
+
+fn count<S: BuildHasher>(keys: &[(String, Vec<String>)], hasher: S) -> usize {
+    let mut occurences: HashMap<&(String, Vec<String>), u32, S> = HashMap::with_hasher(hasher);
+    for key in keys {
+        *occurences.entry(key).or_insert(0) += 1;
+    }
+
+    occurences.len()
+}
+
+fn collect_rule_keys(tree: &ParseTree<String>, keys: &mut Vec<(String, Vec<String>)>) {
+    match &tree.descendants {
+        Descendants::Atom(atom) => keys.push((tree.root.clone(), vec![atom.clone()])),
+        Descendants::Expressions(trees) => {
+            keys.push((
+                tree.root.clone(),
+                trees.iter().map(|tree| tree.root.clone()).collect(),
+            ));
+            trees.iter().for_each(|tree| collect_rule_keys(tree, keys));
+        }
+    }
+}
+
+/// Builds trees of the shape (ROOT (S (NP (DT w) (NN w)) (VP (VBZ w) (NP ...)))) with varying words and depths
+fn synthetic_treebank(size: usize) -> Vec<ParseTree<String>> {
+    let preterminal = |tag: &str, word: usize| ParseTree {
+        root: tag.to_string(),
+        descendants: Descendants::Atom(format!("{}{}", tag.to_lowercase(), word % 5_000)),
+    };
+    let node = |label: &str, trees: Vec<ParseTree<String>>| ParseTree {
+        root: label.to_string(),
+        descendants: Descendants::Expressions(trees),
+    };
+
+    (0..size)
+        .map(|i| {
+            let mut vp = node(
+                "VP",
+                vec![
+                    preterminal("VBZ", i),
+                    node(
+                        "NP",
+                        vec![preterminal("DT", i * 7), preterminal("NN", i * 13)],
+                    ),
+                ],
+            );
+            for depth in 0..(i % 6) {
+                vp = node(
+                    "VP",
+                    vec![
+                        preterminal("VBZ", i + depth),
+                        node("NP", vec![preterminal("NN", i * depth)]),
+                        vp,
+                    ],
+                );
+            }
+
+            node(
+                "ROOT",
+                vec![node(
+                    "S",
+                    vec![
+                        node("NP", vec![preterminal("DT", i), preterminal("NN", i * 3)]),
+                        vp,
+                    ],
+                )],
+            )
+        })
+        .collect()
+}
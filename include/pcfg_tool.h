@@ -0,0 +1,55 @@
+#ifndef PCFG_TOOL_H
+#define PCFG_TOOL_H
+
+/* Generated with cbindgen --config cbindgen.toml --output include/pcfg_tool.h, don't edit it by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// An induced grammar, opaque to C
+typedef struct PcfgGrammar PcfgGrammar;
+
+// Induces a grammar with initial ROOT from a NUL-terminated treebank, where trees may span multiple lines.
+// Returns NULL if the treebank is malformed
+//
+// # Safety
+// treebank must point to a NUL-terminated string
+struct PcfgGrammar *pcfg_induce(const char *treebank);
+
+// The nonlexical rules of the grammar as HEAD -> BODY PROBABILITY lines
+//
+// # Safety
+// grammar must have been returned by pcfg_induce() and not been freed
+char *pcfg_grammar_rules(const struct PcfgGrammar *grammar);
+
+// The lexical rules of the grammar as HEAD TERMINAL PROBABILITY lines
+//
+// # Safety
+// grammar must have been returned by pcfg_induce() and not been freed
+char *pcfg_grammar_lexicon(const struct PcfgGrammar *grammar);
+
+// The terminals of the grammar, one per line
+//
+// # Safety
+// grammar must have been returned by pcfg_induce() and not been freed
+char *pcfg_grammar_words(const struct PcfgGrammar *grammar);
+
+// Frees a grammar returned by pcfg_induce(). NULL is ignored
+//
+// # Safety
+// grammar must have been returned by pcfg_induce() and not been freed before
+void pcfg_grammar_free(struct PcfgGrammar *grammar);
+
+// Frees a string returned by the library. NULL is ignored
+//
+// # Safety
+// s must have been returned by the library and not been freed before
+void pcfg_string_free(char *s);
+
+// Describes the last failure of the calling thread, or is NULL if nothing failed yet.
+// The string belongs to the library and stays valid until the next failure
+const char *pcfg_last_error(void);
+
+#endif  /* PCFG_TOOL_H */
@@ -1,6 +1,16 @@
-use std::io::{BufWriter, Write};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{BufRead, BufWriter, Write},
+};
 
-use crate::{induce::PCFGGrammar, Grammar, Probability, Rule};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    format::{unknown_part, GrammarFormatReader, GrammarFormatWriter},
+    Body, ExactRules, Grammar, Nonterminal, Occurence, OccurenceRules, Probability,
+    ProbabilityRules, Rule, Terminal,
+};
 
 pub trait BerkeleyFormatWriter {
     fn rules_fmt<F>(&self, f: &mut F) -> std::fmt::Result
@@ -21,25 +31,117 @@ pub trait BerkeleyFormatWriter {
     fn words_io<F>(&self, f: &mut F) -> std::io::Result<()>
     where
         F: std::io::Write;
+    /// Writes the lexicon like the Berkeley parser does: grouped by word in sorted order with one TAG word [PROBABILITY] line per tag.
+    /// If counts are given, the raw occurence of each entry follows the probability (0 for entries that were added by smoothing)
+    fn grouped_lexicon_io<F>(
+        &self,
+        f: &mut F,
+        counts: Option<&OccurenceRules>,
+    ) -> std::io::Result<()>
+    where
+        F: std::io::Write;
+    /// Writes the words like words_io(), each followed by its occurence in the corpus (0 for words added by smoothing)
+    fn counted_words_io<F>(&self, f: &mut F, counts: &OccurenceRules) -> std::io::Result<()>
+    where
+        F: std::io::Write;
 }
 
-pub struct BerkeleyWriter {
-    pub grammar: Grammar,
+/// Writes a borrowed grammar, whose rules are partitioned into the rules and the lexicon only once
+pub struct BerkeleyWriter<'a> {
+    pub grammar: &'a Grammar,
+    nonlexical_rules: Vec<(&'a Rule, Probability)>,
+    lexical_rules: Vec<(&'a Rule, Probability)>,
+    /// Writes the lexicon like grouped_lexicon_io() when used as a GrammarFormatWriter
+    grouped_lexicon: bool,
+    lexicon_counts: Option<&'a OccurenceRules>,
+    word_counts: Option<&'a OccurenceRules>,
+    /// Number of decimals of the probabilities, see ProbabilityDisplay
+    decimals: Option<usize>,
+    /// Exact probabilities that are printed as fractions instead of the decimals
+    fractions: Option<&'a ExactRules>,
 }
 
-impl BerkeleyWriter {
-    pub fn from_grammar(grammar: Grammar) -> Self {
-        Self { grammar }
+impl<'a> BerkeleyWriter<'a> {
+    pub fn from_grammar(grammar: &'a Grammar) -> Self {
+        let (lexical_rules, nonlexical_rules) = grammar
+            .rules
+            .iter()
+            .map(|(rule, probability)| (rule, *probability))
+            .partition(|(rule, _probability)| rule.is_lexical_rule());
+
+        Self {
+            grammar,
+            nonlexical_rules,
+            lexical_rules,
+            grouped_lexicon: false,
+            lexicon_counts: None,
+            word_counts: None,
+            decimals: None,
+            fractions: None,
+        }
+    }
+
+    /// Prints the probabilities as the exact fractions they were normalised to (e.g. 2/3, see normalise_exactly()).
+    /// Rules without a fraction are printed as decimals
+    pub fn fractions(mut self, exact_rules: &'a ExactRules) -> Self {
+        self.fractions = Some(exact_rules);
+        self
+    }
+
+    fn printed(&self, rule: &Rule, probability: Probability) -> PrintedProbability {
+        match self.fractions.and_then(|exact_rules| exact_rules.get(rule)) {
+            Some(fraction) => PrintedProbability::Fraction(*fraction),
+            None => PrintedProbability::Decimal(ProbabilityDisplay(probability, self.decimals)),
+        }
+    }
+
+    /// Prints the probabilities with exactly this many decimals
+    pub fn decimals(mut self, decimals: usize) -> Self {
+        self.decimals = Some(decimals);
+        self
+    }
+
+    /// Groups the lexicon by word like the Berkeley parser, see grouped_lexicon_io()
+    pub fn grouped_lexicon(mut self, counts: Option<&'a OccurenceRules>) -> Self {
+        self.grouped_lexicon = true;
+        self.lexicon_counts = counts;
+        self
+    }
+
+    /// Appends the occurence of each word to the words, see counted_words_io()
+    pub fn word_counts(mut self, counts: &'a OccurenceRules) -> Self {
+        self.word_counts = Some(counts);
+        self
+    }
+
+    /// Every terminal once, in sorted order
+    fn terminals(&self) -> Vec<&'a Terminal> {
+        let mut terminals: Vec<&Terminal> = self
+            .lexical_rules
+            .iter()
+            .filter_map(|(rule, _probability)| match &rule.body {
+                Body::Lexical(terminal) => Some(terminal),
+                Body::NonLexical(_) => None,
+            })
+            .collect();
+        terminals.sort();
+        terminals.dedup();
+
+        terminals
     }
 }
 
-impl BerkeleyFormatWriter for BerkeleyWriter {
+impl BerkeleyFormatWriter for BerkeleyWriter<'_> {
     fn rules_fmt<F>(&self, f: &mut F) -> std::fmt::Result
     where
         F: std::fmt::Write,
     {
-        for (rule, probability) in self.grammar.nonlexical_rules() {
-            rule.print_fmt(f, probability)?;
+        for (rule, probability) in self.nonlexical_rules.iter() {
+            writeln!(
+                f,
+                "{}",
+                RuleWithProbability(rule, self.printed(rule, *probability))
+            )?;
         }
 
         Ok(())
@@ -49,8 +151,12 @@ impl BerkeleyFormatWriter for BerkeleyWriter {
     where
         F: std::fmt::Write,
     {
-        for (rule, probability) in self.grammar.lexical_rules() {
-            rule.print_fmt(f, probability)?;
+        for (rule, probability) in self.lexical_rules.iter() {
+            writeln!(
+                f,
+                "{}",
+                RuleWithProbability(rule, self.printed(rule, *probability))
+            )?;
         }
 
         Ok(())
@@ -60,8 +166,8 @@ impl BerkeleyFormatWriter for BerkeleyWriter {
     where
         F: std::fmt::Write,
     {
-        for terminal in self.grammar.terminals() {
-            writeln!(f, "{}", terminal)?;
+        for terminal in self.terminals() {
+            writeln!(f, "{}", Escaped(terminal))?;
         }
 
         Ok(())
@@ -72,8 +178,12 @@ impl BerkeleyFormatWriter for BerkeleyWriter {
         F: std::io::Write,
     {
         let mut w = BufWriter::new(f);
-        for (rule, probability) in self.grammar.nonlexical_rules() {
-            rule.print_io(&mut w, probability)?;
+        for (rule, probability) in self.nonlexical_rules.iter() {
+            writeln!(
+                w,
+                "{}",
+                RuleWithProbability(rule, self.printed(rule, *probability))
+            )?;
         }
 
         w.flush()?;
@@ -86,8 +196,12 @@ impl BerkeleyFormatWriter for BerkeleyWriter {
         F: std::io::Write,
     {
         let mut w = BufWriter::new(f);
-        for (rule, probability) in self.grammar.lexical_rules() {
-            rule.print_io(&mut w, probability)?;
+        for (rule, probability) in self.lexical_rules.iter() {
+            writeln!(
+                w,
+                "{}",
+                RuleWithProbability(rule, self.printed(rule, *probability))
+            )?;
         }
 
         w.flush()?;
@@ -100,42 +214,211 @@ impl BerkeleyFormatWriter for BerkeleyWriter {
         F: std::io::Write,
     {
         let mut w = BufWriter::new(f);
-        for terminal in self.grammar.terminals() {
-            writeln!(w, "{}", terminal)?;
+        for terminal in self.terminals() {
+            writeln!(w, "{}", Escaped(terminal))?;
         }
 
         w.flush()?;
 
         Ok(())
     }
-}
 
-trait BerkeleyRuleIo {
-    fn print_io<F>(&self, w: &mut F, probability: Probability) -> std::io::Result<()>
+    fn counted_words_io<F>(&self, f: &mut F, counts: &OccurenceRules) -> std::io::Result<()>
     where
-        F: std::io::Write;
-}
+        F: std::io::Write,
+    {
+        let mut word_counts: HashMap<&Terminal, Occurence> = HashMap::new();
+        for (rule, occurence) in counts.iter() {
+            if let Body::Lexical(terminal) = &rule.body {
+                *word_counts.entry(terminal).or_insert(0) += occurence;
+            }
+        }
 
-impl BerkeleyRuleIo for Rule {
-    fn print_io<F>(&self, w: &mut F, probabilty: Probability) -> std::io::Result<()>
+        let mut w = BufWriter::new(f);
+        for terminal in self.terminals() {
+            let count = word_counts.get(terminal).copied().unwrap_or(0);
+            writeln!(w, "{} {}", Escaped(terminal), count)?;
+        }
+
+        w.flush()?;
+
+        Ok(())
+    }
+
+    fn grouped_lexicon_io<F>(
+        &self,
+        f: &mut F,
+        counts: Option<&OccurenceRules>,
+    ) -> std::io::Result<()>
     where
         F: std::io::Write,
     {
-        match &self.body {
-            crate::Body::Lexical(terminal) => {
-                writeln!(w, "{} {} {}", self.head, terminal, probabilty)?;
+        let mut entries: Vec<(&Terminal, &Rule, Probability)> = self
+            .lexical_rules
+            .iter()
+            .filter_map(|(rule, probability)| match &rule.body {
+                Body::Lexical(terminal) => Some((terminal, *rule, *probability)),
+                Body::NonLexical(_) => None,
+            })
+            .collect();
+        entries.sort_by(|(terminal, rule, _), (other_terminal, other_rule, _)| {
+            (terminal, &rule.head).cmp(&(other_terminal, &other_rule.head))
+        });
+
+        let mut w = BufWriter::new(f);
+        for (terminal, rule, probability) in entries {
+            write!(
+                w,
+                "{} {} [{}]",
+                Escaped(&rule.head),
+                Escaped(terminal),
+                self.printed(rule, probability)
+            )?;
+            if let Some(counts) = counts {
+                write!(w, " {}", counts.get(rule).copied().unwrap_or(0))?;
+            }
+            writeln!(w)?;
+        }
+
+        w.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Writes GRAMMAR.rules, GRAMMAR.lexicon and GRAMMAR.words
+impl GrammarFormatWriter for BerkeleyWriter<'_> {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rules", "lexicon", "words"]
+    }
+
+    fn write_part(&self, extension: &str, mut w: &mut dyn Write) -> std::io::Result<()> {
+        match (extension, self.word_counts) {
+            ("rules", _) => self.rules_io(&mut w),
+            ("lexicon", _) if self.grouped_lexicon => {
+                self.grouped_lexicon_io(&mut w, self.lexicon_counts)
             }
-            crate::Body::NonLexical(nonterminals) => {
-                write!(w, "{} -> ", self.head)?;
-                for (index, nonterminal) in nonterminals.iter().enumerate() {
-                    if index != nonterminals.len() - 1 {
-                        write!(w, "{} ", nonterminal)?;
-                    } else {
-                        write!(w, "{}", nonterminal)?;
-                    }
-                }
-
-                writeln!(w, " {}", probabilty)?;
+            ("lexicon", _) => self.lexicon_io(&mut w),
+            ("words", Some(counts)) => self.counted_words_io(&mut w, counts),
+            ("words", None) => self.words_io(&mut w),
+            (extension, _) => Err(unknown_part(extension)),
+        }
+    }
+}
+
+/// Formats a probability in fixed-point notation, with the given number of decimals or as many as it takes to read it back exactly.
+/// Unlike {:e}, f64's Display never switches to scientific notation (e.g. 3.1e-5 is printed as 0.000031)
+pub struct ProbabilityDisplay(pub Probability, pub Option<usize>);
+
+impl fmt::Display for ProbabilityDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.1 {
+            Some(decimals) => write!(f, "{:.*}", decimals, self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+/// An exact probability like 2/3, which is read and printed the same on every platform
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Fraction {
+    pub numerator: u64,
+    pub denominator: u64,
+}
+
+impl Fraction {
+    /// Reduces the fraction, e.g. 2/4 becomes 1/2, which is None if the denominator is 0
+    pub fn new(numerator: u64, denominator: u64) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numerator, denominator);
+        Some(Fraction {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        })
+    }
+
+    pub fn value(&self) -> Probability {
+        self.numerator as Probability / self.denominator as Probability
+    }
+}
+
+impl fmt::Display for Fraction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.numerator, self.denominator)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    match b {
+        0 => a,
+        b => gcd(b, a % b),
+    }
+}
+
+/// A probability as it is printed by the BerkeleyWriter
+enum PrintedProbability {
+    Decimal(ProbabilityDisplay),
+    Fraction(Fraction),
+}
+
+impl fmt::Display for PrintedProbability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrintedProbability::Decimal(decimal) => decimal.fmt(f),
+            PrintedProbability::Fraction(fraction) => fraction.fmt(f),
+        }
+    }
+}
+
+/// Reads a probability printed as a decimal or as a fraction (e.g. 2/3)
+fn parse_probability(token: &str) -> Option<Probability> {
+    match token.split_once('/') {
+        Some((numerator, denominator)) => {
+            let fraction = Fraction::new(numerator.parse().ok()?, denominator.parse().ok()?)?;
+            Some(fraction.value())
+        }
+        None => token.parse().ok(),
+    }
+}
+
+/// Strips the brackets of a score like [0.5] in a grouped lexicon
+fn unbracket(score: &str) -> &str {
+    score
+        .strip_prefix('[')
+        .and_then(|score| score.strip_suffix(']'))
+        .unwrap_or(score)
+}
+
+/// Formats a line of the rules file (HEAD -> BODY PROBABILITY) or the lexicon (HEAD TERMINAL PROBABILITY) without allocating,
+/// where the probability is printed by its own Display, e.g. a ProbabilityDisplay
+pub struct RuleWithProbability<'a, P>(pub &'a Rule, pub P);
+
+impl<P: fmt::Display> fmt::Display for RuleWithProbability<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.0, self.1)
+    }
+}
+
+/// Escapes a symbol so that it is a single token that can't be mistaken for an arrow or a comment:
+/// backslashes become `\\`, whitespace `\s`, `\t`, `\n` or `\r`, `#` becomes `\#` and `->` becomes `\->`
+pub struct Escaped<'a>(pub &'a str);
+
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => f.write_str("\\\\")?,
+                ' ' => f.write_str("\\s")?,
+                '\t' => f.write_str("\\t")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '#' => f.write_str("\\#")?,
+                '-' if chars.peek() == Some(&'>') => f.write_str("\\-")?,
+                c => fmt::Write::write_char(f, c)?,
             }
         }
 
@@ -143,35 +426,433 @@ impl BerkeleyRuleIo for Rule {
     }
 }
 
-trait BerkeleyRuleFmt {
-    fn print_fmt<F>(&self, w: &mut F, probability: Probability) -> std::fmt::Result
-    where
-        F: std::fmt::Write;
+/// Reverts Escaped, which fails on a trailing backslash
+pub fn unescape(token: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        unescaped.push(match chars.next()? {
+            's' => ' ',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            c => c,
+        });
+    }
+
+    Some(unescaped)
 }
 
-impl BerkeleyRuleFmt for Rule {
-    fn print_fmt<F>(&self, w: &mut F, probability: Probability) -> std::fmt::Result
+pub struct BerkeleyReader;
+
+impl BerkeleyReader {
+    /// Reads a grammar from the rules (HEAD -> BODY PROBABILITY) and lexicon (HEAD TERMINAL PROBABILITY) lines.
+    /// The words can be derived from the lexicon, and the initial is not part of the format.
+    /// Symbols are unescaped (see Escaped)
+    pub fn read<R, L>(
+        initial: Nonterminal,
+        rules: R,
+        lexicon: L,
+    ) -> Result<Grammar, Box<dyn std::error::Error>>
     where
-        F: std::fmt::Write,
+        R: BufRead,
+        L: BufRead,
     {
-        match &self.body {
-            crate::Body::Lexical(terminal) => {
-                write!(w, "{} {} {}", self.head, terminal, probability)?;
+        let mut probability_rules: ProbabilityRules = FxHashMap::default();
+
+        for (i, line) in rules.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
             }
-            crate::Body::NonLexical(nonterminals) => {
-                write!(w, "{} -> ", self.head)?;
-                for (index, nonterminal) in nonterminals.iter().enumerate() {
-                    if index != nonterminals.len() - 1 {
-                        write!(w, "{} ", nonterminal)?;
-                    } else {
-                        write!(w, "{}", nonterminal)?;
-                    }
-                }
-
-                writeln!(w, " {}", probability)?;
+
+            let (rule, probability) = Self::parse_rule(&line)
+                .ok_or_else(|| format!("Malformed rule \"{}\" on line {}", line, i + 1))?;
+            probability_rules.insert(rule, probability);
+        }
+
+        for (i, line) in lexicon.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
             }
+
+            let (rule, probability) = Self::parse_lexicon_entry(&line)
+                .ok_or_else(|| format!("Malformed lexicon entry \"{}\" on line {}", line, i + 1))?;
+            probability_rules.insert(rule, probability);
         }
 
-        Ok(())
+        Ok(Grammar {
+            initial,
+            rules: probability_rules,
+        })
+    }
+
+    pub(crate) fn parse_rule(line: &str) -> Option<(Rule, Probability)> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            [head, "->", body @ .., probability] if !body.is_empty() => Some((
+                Rule {
+                    head: unescape(head)?,
+                    body: Body::NonLexical(
+                        body.iter().map(|nt| unescape(nt)).collect::<Option<_>>()?,
+                    ),
+                },
+                parse_probability(probability)?,
+            )),
+            _ => None,
+        }
+    }
+
+    /// Reads HEAD TERMINAL PROBABILITY as well as the entries of a grouped lexicon (HEAD TERMINAL [PROBABILITY] with an optional count),
+    /// whose count is ignored
+    pub(crate) fn parse_lexicon_entry(line: &str) -> Option<(Rule, Probability)> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        let (head, terminal, probability) = match tokens.as_slice() {
+            [head, terminal, probability] => (head, terminal, unbracket(probability)),
+            [head, terminal, score, count] if count.parse::<Occurence>().is_ok() => {
+                (head, terminal, score.strip_prefix('[')?.strip_suffix(']')?)
+            }
+            _ => return None,
+        };
+
+        Some((
+            Rule {
+                head: unescape(head)?,
+                body: Body::Lexical(unescape(terminal)?),
+            },
+            parse_probability(probability)?,
+        ))
+    }
+}
+
+/// Reads GRAMMAR.rules and GRAMMAR.lexicon, with ROOT as the initial if none is given
+impl GrammarFormatReader for BerkeleyReader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["rules", "lexicon"]
+    }
+
+    fn read_parts(
+        &self,
+        parts: Vec<Box<dyn BufRead>>,
+        initial: Option<&str>,
+    ) -> Result<Grammar, Box<dyn std::error::Error>> {
+        let [rules, lexicon]: [Box<dyn BufRead>; 2] = parts
+            .try_into()
+            .map_err(|_| "The Berkeley format consists of the rules and the lexicon")?;
+
+        Self::read(initial.unwrap_or("ROOT").to_string(), rules, lexicon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::induce::normalise_exactly;
+
+    #[test]
+    fn prints_fixed_point_probabilities() {
+        assert_eq!(ProbabilityDisplay(3.1e-5, None).to_string(), "0.000031");
+        assert_eq!(ProbabilityDisplay(3.1e-5, Some(4)).to_string(), "0.0000");
+        assert_eq!(ProbabilityDisplay(1.0, Some(3)).to_string(), "1.000");
+
+        let rule = Rule {
+            head: "NP".to_string(),
+            body: Body::Lexical("Brutus".to_string()),
+        };
+        assert_eq!(
+            RuleWithProbability(&rule, ProbabilityDisplay(2.0 / 3.0, Some(2))).to_string(),
+            "NP Brutus 0.67"
+        );
+    }
+
+    #[test]
+    fn prints_exact_fractions() {
+        let rule = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let counts: OccurenceRules = FxHashMap::from_iter(vec![
+            (rule("NP", "Brutus"), 2),
+            (rule("NP", "Caesar"), 4),
+            (rule("VP", "stabs"), 3),
+        ]);
+        let grammar = Grammar {
+            initial: "NP".to_string(),
+            rules: counts
+                .keys()
+                .map(|rule| (rule.clone(), 0.5))
+                .chain(std::iter::once((rule("VP", "dies"), 0.5)))
+                .collect(),
+        };
+
+        let exact_rules = normalise_exactly(&counts).expect("Every head occurs");
+        let writer = BerkeleyWriter::from_grammar(&grammar).fractions(&exact_rules);
+        let mut lexicon = Vec::new();
+        writer
+            .lexicon_io(&mut lexicon)
+            .expect("Writing to a Vec works");
+        let lexicon = String::from_utf8(lexicon).expect("The lexicon is UTF-8");
+        assert!(lexicon.contains("NP Brutus 1/3\n"));
+        assert!(lexicon.contains("NP Caesar 2/3\n"));
+        assert!(lexicon.contains("VP stabs 1/1\n"));
+        // Rules without a fraction fall back to decimals
+        assert!(lexicon.contains("VP dies 0.5\n"));
+
+        assert_eq!(
+            BerkeleyReader::parse_lexicon_entry("NP Caesar 2/3"),
+            Some((rule("NP", "Caesar"), 2.0 / 3.0))
+        );
+        assert_eq!(BerkeleyReader::parse_lexicon_entry("NP Caesar 2/0"), None);
+    }
+
+    #[test]
+    fn reads_written_grammar() {
+        let grammar = Grammar {
+            initial: "S".to_string(),
+            rules: FxHashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "S".to_string(),
+                        body: Body::NonLexical(vec!["NP".to_string(), "VP".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "NP".to_string(),
+                        body: Body::Lexical("Julius".to_string()),
+                    },
+                    0.25,
+                ),
+                (
+                    Rule {
+                        head: "NP".to_string(),
+                        body: Body::Lexical("Brutus".to_string()),
+                    },
+                    0.75,
+                ),
+                (
+                    Rule {
+                        head: "VP".to_string(),
+                        body: Body::Lexical("stabs".to_string()),
+                    },
+                    1.0,
+                ),
+            ]),
+        };
+
+        let writer = BerkeleyWriter::from_grammar(&grammar);
+        let mut rules = Vec::new();
+        writer.rules_io(&mut rules).expect("Writing to a Vec works");
+        let mut lexicon = Vec::new();
+        writer
+            .lexicon_io(&mut lexicon)
+            .expect("Writing to a Vec works");
+
+        let read = BerkeleyReader::read("S".to_string(), rules.as_slice(), lexicon.as_slice())
+            .expect("This is a valid grammar");
+
+        assert_eq!(read, grammar);
+    }
+
+    #[test]
+    fn writes_every_word_once() {
+        let rules = "S -> A B 1\n";
+        let lexicon = "A w 0.5\nA v 0.5\nB w 1\n";
+        let grammar = BerkeleyReader::read("S".to_string(), rules.as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar");
+
+        let mut words = Vec::new();
+        BerkeleyWriter::from_grammar(&grammar)
+            .words_io(&mut words)
+            .expect("Writing to a Vec works");
+
+        assert_eq!(std::str::from_utf8(&words), Ok("v\nw\n"));
+    }
+
+    #[test]
+    fn fmt_and_io_writers_agree() {
+        let rules = "S -> NP VP 1\n";
+        let lexicon = "NP Julius 1\nVP stabs 1\n";
+        let grammar = BerkeleyReader::read("S".to_string(), rules.as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar");
+        let writer = BerkeleyWriter::from_grammar(&grammar);
+
+        let mut fmt_lexicon = String::new();
+        writer
+            .lexicon_fmt(&mut fmt_lexicon)
+            .expect("Writing to a String works");
+        let mut io_lexicon = Vec::new();
+        writer
+            .lexicon_io(&mut io_lexicon)
+            .expect("Writing to a Vec works");
+        assert_eq!(fmt_lexicon.as_bytes(), io_lexicon.as_slice());
+        assert_eq!(fmt_lexicon.lines().count(), 2);
+
+        let mut fmt_rules = String::new();
+        writer
+            .rules_fmt(&mut fmt_rules)
+            .expect("Writing to a String works");
+        assert_eq!(fmt_rules, rules);
+    }
+
+    #[test]
+    fn groups_lexicon_by_word() {
+        let lexicon = "VB run 0.5\nNN walk 0.25\nNN run 0.75\nVB walk 0.5\n";
+        let grammar = BerkeleyReader::read("S".to_string(), "".as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar");
+
+        let counts = FxHashMap::from_iter(vec![(
+            Rule {
+                head: "NN".to_string(),
+                body: Body::Lexical("run".to_string()),
+            },
+            3,
+        )]);
+
+        let mut grouped = Vec::new();
+        BerkeleyWriter::from_grammar(&grammar)
+            .grouped_lexicon_io(&mut grouped, Some(&counts))
+            .expect("Writing to a Vec works");
+
+        assert_eq!(
+            std::str::from_utf8(&grouped),
+            Ok("NN run [0.75] 3\nVB run [0.5] 0\nNN walk [0.25] 0\nVB walk [0.5] 0\n")
+        );
+    }
+
+    #[test]
+    fn reads_grouped_lexicon() {
+        let lexicon = "VB run 0.5\nNN walk 0.25\nNN run 0.75\nVB walk 0.5\n";
+        let grammar = BerkeleyReader::read("S".to_string(), "".as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar");
+        let counts = OccurenceRules::default();
+
+        for counts in [None, Some(&counts)] {
+            let mut grouped = Vec::new();
+            BerkeleyWriter::from_grammar(&grammar)
+                .grouped_lexicon_io(&mut grouped, counts)
+                .expect("Writing to a Vec works");
+
+            let read = BerkeleyReader::read("S".to_string(), "".as_bytes(), grouped.as_slice())
+                .expect("A grouped lexicon is a valid lexicon");
+            assert_eq!(read, grammar);
+        }
+
+        assert_eq!(BerkeleyReader::parse_lexicon_entry("NN run [0.75] x"), None);
+        assert_eq!(BerkeleyReader::parse_lexicon_entry("NN run [0.75"), None);
+        assert_eq!(BerkeleyReader::parse_lexicon_entry("NN run 0.75 3"), None);
+    }
+
+    #[test]
+    fn writes_sorted_words_with_counts() {
+        let lexicon = "NN walk 0.5\nVB run 0.5\nNN run 0.5\nVB walk 0.5\n";
+        let grammar = BerkeleyReader::read("S".to_string(), "".as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar");
+        let counts = FxHashMap::from_iter(vec![
+            (
+                Rule {
+                    head: "NN".to_string(),
+                    body: Body::Lexical("run".to_string()),
+                },
+                3,
+            ),
+            (
+                Rule {
+                    head: "VB".to_string(),
+                    body: Body::Lexical("run".to_string()),
+                },
+                2,
+            ),
+        ]);
+
+        let mut words = Vec::new();
+        BerkeleyWriter::from_grammar(&grammar)
+            .counted_words_io(&mut words, &counts)
+            .expect("Writing to a Vec works");
+
+        assert_eq!(std::str::from_utf8(&words), Ok("run 5\nwalk 0\n"));
+    }
+
+    #[test]
+    fn counts_words_beyond_32_bits() {
+        let grammar = BerkeleyReader::read("S".to_string(), "".as_bytes(), "NN run 1\n".as_bytes())
+            .expect("This is a valid grammar");
+        let rule = Rule {
+            head: "NN".to_string(),
+            body: Body::Lexical("run".to_string()),
+        };
+        let counts = FxHashMap::from_iter(vec![(rule, u64::from(u32::MAX) + 2)]);
+
+        let mut words = Vec::new();
+        BerkeleyWriter::from_grammar(&grammar)
+            .counted_words_io(&mut words, &counts)
+            .expect("Writing to a Vec works");
+
+        assert_eq!(std::str::from_utf8(&words), Ok("run 4294967297\n"));
+    }
+
+    #[test]
+    fn escapes_special_symbols() {
+        let grammar = Grammar {
+            initial: "S".to_string(),
+            rules: FxHashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "S".to_string(),
+                        body: Body::NonLexical(vec!["->".to_string(), "#".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "->".to_string(),
+                        body: Body::Lexical("New York".to_string()),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "#".to_string(),
+                        body: Body::Lexical("a\\b".to_string()),
+                    },
+                    1.0,
+                ),
+            ]),
+        };
+
+        let writer = BerkeleyWriter::from_grammar(&grammar);
+        let mut rules = Vec::new();
+        writer.rules_io(&mut rules).expect("Writing to a Vec works");
+        let mut lexicon = Vec::new();
+        writer
+            .lexicon_io(&mut lexicon)
+            .expect("Writing to a Vec works");
+        let mut words = Vec::new();
+        writer.words_io(&mut words).expect("Writing to a Vec works");
+
+        assert_eq!(std::str::from_utf8(&rules), Ok("S -> \\-> \\# 1\n"));
+        assert_eq!(std::str::from_utf8(&words), Ok("New\\sYork\na\\\\b\n"));
+
+        let read = BerkeleyReader::read("S".to_string(), rules.as_slice(), lexicon.as_slice())
+            .expect("This is a valid grammar");
+        assert_eq!(read, grammar);
+    }
+
+    #[test]
+    fn rejects_malformed_rules() {
+        let rules = "S -> NP VP 1\nS NP 0.5\n";
+        let err = BerkeleyReader::read("S".to_string(), rules.as_bytes(), "".as_bytes())
+            .expect_err("The second rule is missing its arrow");
+
+        assert_eq!(err.to_string(), "Malformed rule \"S NP 0.5\" on line 2");
     }
 }
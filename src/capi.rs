@@ -0,0 +1,201 @@
+//! C interface for embedding the induction into C/C++ pipelines. The header is include/pcfg_tool.h,
+//! which is generated by `cbindgen --config cbindgen.toml --output include/pcfg_tool.h`.
+//!
+//! Strings returned by the library must be freed with pcfg_string_free() and grammars with pcfg_grammar_free().
+//! Functions that fail return NULL, and pcfg_last_error() describes the failure
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use crate::{
+    berkeley::{BerkeleyFormatWriter, BerkeleyWriter},
+    induce::PCFGGrammar,
+    ptb::PTBParser,
+    Grammar,
+};
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// An induced grammar, opaque to C
+pub struct PcfgGrammar {
+    grammar: Grammar,
+}
+
+fn set_last_error(message: String) {
+    // Interior NUL bytes can't be part of a C string
+    let message = CString::new(message.replace('\0', " ")).expect("NUL bytes were replaced");
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+fn into_c_string(bytes: Vec<u8>) -> *mut c_char {
+    match CString::new(bytes) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Induces a grammar with initial ROOT from a NUL-terminated treebank, where trees may span multiple lines.
+/// Returns NULL if the treebank is malformed
+///
+/// # Safety
+/// treebank must point to a NUL-terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_induce(treebank: *const c_char) -> *mut PcfgGrammar {
+    if treebank.is_null() {
+        set_last_error(String::from("The treebank is NULL"));
+        return ptr::null_mut();
+    }
+
+    let treebank = match unsafe { CStr::from_ptr(treebank) }.to_str() {
+        Ok(treebank) => treebank,
+        Err(e) => {
+            set_last_error(format!("The treebank is no valid UTF-8: {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    let trees = match PTBParser::parse_treebank(treebank, "ROOT") {
+        Ok(trees) => trees,
+        Err(e) => {
+            set_last_error(format!("Malformed treebank at {}", e));
+            return ptr::null_mut();
+        }
+    };
+
+    match Grammar::from_parse_trees(String::from("ROOT"), trees) {
+        Ok(grammar) => Box::into_raw(Box::new(PcfgGrammar { grammar })),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The nonlexical rules of the grammar as HEAD -> BODY PROBABILITY lines
+///
+/// # Safety
+/// grammar must have been returned by pcfg_induce() and not been freed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_grammar_rules(grammar: *const PcfgGrammar) -> *mut c_char {
+    let mut rules = Vec::new();
+    match BerkeleyWriter::from_grammar(unsafe { &(*grammar).grammar }).rules_io(&mut rules) {
+        Ok(()) => into_c_string(rules),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The lexical rules of the grammar as HEAD TERMINAL PROBABILITY lines
+///
+/// # Safety
+/// grammar must have been returned by pcfg_induce() and not been freed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_grammar_lexicon(grammar: *const PcfgGrammar) -> *mut c_char {
+    let mut lexicon = Vec::new();
+    match BerkeleyWriter::from_grammar(unsafe { &(*grammar).grammar }).lexicon_io(&mut lexicon) {
+        Ok(()) => into_c_string(lexicon),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The terminals of the grammar, one per line
+///
+/// # Safety
+/// grammar must have been returned by pcfg_induce() and not been freed
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_grammar_words(grammar: *const PcfgGrammar) -> *mut c_char {
+    let mut words = Vec::new();
+    match BerkeleyWriter::from_grammar(unsafe { &(*grammar).grammar }).words_io(&mut words) {
+        Ok(()) => into_c_string(words),
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a grammar returned by pcfg_induce(). NULL is ignored
+///
+/// # Safety
+/// grammar must have been returned by pcfg_induce() and not been freed before
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_grammar_free(grammar: *mut PcfgGrammar) {
+    if !grammar.is_null() {
+        drop(unsafe { Box::from_raw(grammar) });
+    }
+}
+
+/// Frees a string returned by the library. NULL is ignored
+///
+/// # Safety
+/// s must have been returned by the library and not been freed before
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pcfg_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(unsafe { CString::from_raw(s) });
+    }
+}
+
+/// Describes the last failure of the calling thread, or is NULL if nothing failed yet.
+/// The string belongs to the library and stays valid until the next failure
+#[unsafe(no_mangle)]
+pub extern "C" fn pcfg_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match &*last_error.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn induces_through_c_interface() {
+        let treebank = CString::new("(ROOT (A a))\n( (A b) )\n").expect("No NUL bytes");
+
+        unsafe {
+            let grammar = pcfg_induce(treebank.as_ptr());
+            assert!(!grammar.is_null());
+
+            let rules = pcfg_grammar_rules(grammar);
+            assert_eq!(CStr::from_ptr(rules).to_str(), Ok("ROOT -> A 1\n"));
+            pcfg_string_free(rules);
+
+            let lexicon = pcfg_grammar_lexicon(grammar);
+            let mut lines: Vec<&str> = CStr::from_ptr(lexicon)
+                .to_str()
+                .expect("The lexicon is UTF-8")
+                .lines()
+                .collect();
+            lines.sort();
+            assert_eq!(lines, vec!["A a 0.5", "A b 0.5"]);
+            pcfg_string_free(lexicon);
+
+            pcfg_grammar_free(grammar);
+        }
+    }
+
+    #[test]
+    fn reports_malformed_treebank() {
+        let treebank = CString::new("(ROOT (A a)").expect("No NUL bytes");
+
+        unsafe {
+            assert!(pcfg_induce(treebank.as_ptr()).is_null());
+            assert!(!pcfg_last_error().is_null());
+        }
+    }
+}
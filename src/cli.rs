@@ -1,19 +1,605 @@
 pub use clap::{CommandFactory, Parser};
+pub use clap_complete::Shell;
 
-use clap::Subcommand;
+use clap::{ArgAction, ArgGroup, Args, Subcommand, ValueEnum};
+
+use serde::Deserialize;
+
+use crate::unknown::{SignatureOptions, MAX_SIGNATURE_SUFFIX_LENGTH, UNKNOWN};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Logs more details to the stderr (-v for the duration of each stage, -vv for debug messages)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Only logs errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Prints the probabilities of grammars and queried rules with exactly N decimals instead of as many as needed to read them back exactly
+    #[arg(long, value_name = "N", global = true)]
+    pub decimals: Option<usize>,
+
+    /// Exits on the first malformed tree instead of skipping malformed trees and summarising them in the end
+    #[arg(long, global = true)]
+    pub strict: bool,
+
+    /// Reads the options of the subcommand from a TOML file (e.g. [induce] grammar = "wsj"), where options on the command line take precedence
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<String>,
+
+    /// Format of the log messages on the stderr
+    #[arg(long, value_name = "FORMAT", default_value = "text", global = true)]
+    pub log_format: LogFormat,
 }
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Reads a sequence of contituent trees from the stdin and prints an induced PCFG to the stdout
+    /// Reads a sequence of contituent trees from the stdin (or the given treebanks) and prints an induced PCFG to the stdout
+    #[command(group(ArgGroup::new("smoothed").args(["add_k", "smoothing"])))]
+    #[command(group(ArgGroup::new("signature_classes").args(["signatures", "unknown_signatures"]).multiple(true)))]
     Induce {
         /// If this is set, the induced grammar is written into GRAMMAR.rules , GRAMMAR.lexicon, and GRAMMAR.words files instead of the stdout
         grammar: Option<String>,
+
+        /// Files with one tree per line to read instead of the stdin. Use - as GRAMMAR to print the grammar to the stdout anyway
+        treebanks: Vec<String>,
+
+        /// Reads a treebank like TREEBANKS and multiplies the weights of its trees by WEIGHT, e.g. to interpolate domains (repeatable)
+        #[arg(long, value_name = "FILE:WEIGHT")]
+        source: Vec<Source>,
+
+        /// Reads all .mrg files below DIR (e.g. the PTB's parsed/mrg/wsj) in sorted order, where trees may span multiple lines
+        #[arg(long, value_name = "DIR")]
+        treebank_dir: Option<String>,
+
+        /// Reads the sentences of a TIGER-XML corpus (e.g. the TIGER or NEGRA treebank) as trees (repeatable)
+        #[arg(long, value_name = "FILE")]
+        tiger: Vec<String>,
+
+        /// Orders the children of discontinuous TIGER phrases by their first word or raises them until no branches cross [default: reorder]
+        #[arg(long, value_name = "MODE", requires = "tiger")]
+        crossing_branches: Option<CrossingBranches>,
+
+        #[command(flatten)]
+        preprocess_args: PreprocessArgs,
+
+        /// Merges the lexical rules of words occuring fewer than N times into those of the unknown token while counting, like --unknown-threshold of pipeline
+        #[arg(long, value_name = "N")]
+        vocab_threshold: Option<u32>,
+
+        /// Merges rare words into their signature class (e.g. UNK-INITC-ing) instead of UNK
+        #[arg(long, requires = "vocab_threshold")]
+        signatures: bool,
+
+        #[command(flatten)]
+        signature_args: SignatureArgs,
+
+        /// Also models the words below --vocab-threshold for the lookup of unknown words and writes the model into GRAMMAR.suffixes
+        #[arg(long, value_name = "MODEL", requires_all = ["vocab_threshold", "grammar"])]
+        oov_model: Option<OovModel>,
+
+        /// Longest suffix of the suffix model [default: 10]
+        #[arg(long, value_name = "N", requires = "oov_model", value_parser = clap::value_parser!(u32).range(1..))]
+        oov_suffix_length: Option<u32>,
+
+        #[command(flatten)]
+        smoothing_args: SmoothingArgs,
+
+        /// Adds the rule counts saved in COUNTS (one RULE COUNT line per rule, possibly gzipped) to those of the trees before normalising
+        #[arg(long, value_name = "COUNTS")]
+        resume: Option<String>,
+
+        /// Gzips the grammar files (GRAMMAR.rules.gz etc.) or the stdout. Gzipped trees on the stdin are always detected
+        #[arg(long)]
+        gzip: bool,
+
+        /// Writes the grammar files into DIR, which is created if missing and checked for writability before inducing
+        #[arg(long, value_name = "DIR", requires = "grammar")]
+        output_dir: Option<String>,
+
+        /// Writes the lexicon like the Berkeley parser, i.e. grouped by word with TAG word [PROBABILITY] lines
+        #[arg(long)]
+        berkeley_lexicon: bool,
+
+        /// Appends the raw occurence of each entry to the Berkeley lexicon
+        #[arg(long, requires = "berkeley_lexicon")]
+        lexicon_counts: bool,
+
+        /// Appends the occurence of each word in the treebank to the words file
+        #[arg(long)]
+        word_counts: bool,
+
+        /// Also writes the rule counts the grammar is normalised from into GRAMMAR.counts, which --resume reads
+        #[arg(long, requires = "grammar")]
+        counts: bool,
+
+        /// Writes a single GRAMMAR.pcfg file with the rules, lexicon, words and a header recording the initial, the version and the preprocessing
+        #[arg(long, conflicts_with_all = ["berkeley_lexicon", "word_counts"])]
+        pcfg: bool,
+
+        /// Rounds the probabilities to N significant digits after normalising, which makes the grammar files smaller and stable under diffs
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=17))]
+        significant_digits: Option<u32>,
+
+        /// Normalises the counts into exact fractions of the occurence of each rule and its head and prints them as such (e.g. 2/3), which read back the same on every platform
+        #[arg(long, conflicts_with_all = ["smoothed", "prior", "significant_digits"])]
+        fractions: bool,
+
+        /// Reports the processed trees per second and an ETA on the stderr
+        #[arg(long)]
+        progress: bool,
+    },
+    /// Transforms trees, replaces rare words, induces a PCFG and post-processes it in a single run without intermediate files
+    #[command(group(ArgGroup::new("smoothed").args(["add_k", "smoothing"])))]
+    #[command(group(ArgGroup::new("signature_classes").args(["signatures", "unknown_signatures"]).multiple(true)))]
+    Pipeline {
+        /// If this is set, the resulting grammar is written into GRAMMAR.rules , GRAMMAR.lexicon, and GRAMMAR.words files instead of the stdout
+        grammar: Option<String>,
+
+        /// Files with one tree per line to read instead of the stdin. Use - as GRAMMAR to print the grammar to the stdout anyway
+        treebanks: Vec<String>,
+
+        #[command(flatten)]
+        preprocess_args: PreprocessArgs,
+
+        #[command(flatten)]
+        transform_args: TransformArgs,
+
+        /// Replaces words occuring fewer than N times by the unknown token after transforming the trees, like --vocab-threshold of induce
+        #[arg(long, value_name = "N")]
+        unknown_threshold: Option<u32>,
+
+        /// Replaces rare words by their signature class (e.g. UNK-INITC-ing) instead of UNK
+        #[arg(long, requires = "unknown_threshold")]
+        signatures: bool,
+
+        #[command(flatten)]
+        signature_args: SignatureArgs,
+
+        #[command(flatten)]
+        smoothing_args: SmoothingArgs,
+
+        /// Merges annotated nonterminals (e.g. NP^S) of the induced grammar into their base symbols
+        #[arg(long)]
+        project: bool,
+
+        /// Gzips the grammar files (GRAMMAR.rules.gz etc.) or the stdout. Gzipped trees on the stdin are always detected
+        #[arg(long)]
+        gzip: bool,
+    },
+    /// Reads the grammar GRAMMAR.rules and GRAMMAR.lexicon and merges annotated nonterminals (e.g. NP^S or VP|<NP-PP>) into their base symbols
+    Project {
+        /// Name of the grammar to project
+        grammar: String,
+
+        /// If this is set, the projected grammar is written into PROJECTED.rules , PROJECTED.lexicon, and PROJECTED.words files instead of the stdout
+        projected: Option<String>,
+
+        /// Initial nonterminal of the grammar [default: the one recorded in GRAMMAR.meta, or ROOT]
+        #[arg(long)]
+        initial: Option<String>,
+
+        /// Gzips the projected grammar files or the stdout. Gzipped grammar files (e.g. GRAMMAR.rules.gz) are always detected
+        #[arg(long)]
+        gzip: bool,
+    },
+    /// Reads a sequence of constituent trees from the stdin (or the given treebanks) and prints them transformed, one per line
+    Transform {
+        /// Files with one tree per line to read instead of the stdin
+        treebanks: Vec<String>,
+
+        #[command(flatten)]
+        preprocess_args: PreprocessArgs,
+
+        #[command(flatten)]
+        transform_args: TransformArgs,
+
+        /// Format of the printed trees [default: ptb]
+        #[arg(long, value_name = "FORMAT")]
+        tree_format: Option<TreeFormat>,
+    },
+    /// Reads the grammars GRAMMAR and OTHER (.rules and .lexicon) and prints the KL and Jensen-Shannon divergence of each head's rule distribution and the rule overlap
+    Compare {
+        /// Name of the grammar P
+        grammar: String,
+
+        /// Name of the grammar Q, which P is compared to
+        other: String,
+    },
+    /// Reads the grammar GRAMMAR.rules and GRAMMAR.lexicon and prints the probability (and the count from GRAMMAR.counts) of a rule or all rules of a head
+    Query {
+        /// Name of the grammar to query
+        grammar: String,
+
+        /// Rule to look up, e.g. "NP -> DT NN" or "NN dog"
+        #[arg(required_unless_present_any = ["head", "word"])]
+        rule: Option<String>,
+
+        /// Lists all rules of the head from the most to the least probable one instead
+        #[arg(long, value_name = "HEAD", conflicts_with = "rule")]
+        head: Option<String>,
+
+        /// Lists the lexical rules a parser would scan the word with instead, i.e. those of its signature class if it is unknown,
+        /// weighed by its suffix if the grammar was induced with --oov-model suffix
+        #[arg(long, value_name = "WORD", conflicts_with_all = ["rule", "head"])]
+        word: Option<String>,
+    },
+    /// Reads a sequence of constituent trees from the stdin (or the given treebanks) and prints those meeting all criteria, one per line
+    Filter {
+        /// Files with one tree per line to read instead of the stdin
+        treebanks: Vec<String>,
+
+        /// Selects trees whose root (or its only subtree, e.g. S in (ROOT (S ...))) has this label
+        #[arg(long, value_name = "LABEL")]
+        root: Option<String>,
+
+        /// Selects trees with at most N levels of nodes above the words
+        #[arg(long, value_name = "N")]
+        max_depth: Option<usize>,
+
+        /// Selects trees with at least N words
+        #[arg(long, value_name = "N")]
+        min_length: Option<usize>,
+
+        /// Selects trees with at most N words
+        #[arg(long, value_name = "N")]
+        max_length: Option<usize>,
+
+        /// Selects trees containing a node with this label (repeatable)
+        #[arg(long, value_name = "LABEL")]
+        with: Vec<String>,
+
+        /// Selects trees without any node with this label (repeatable)
+        #[arg(long, value_name = "LABEL")]
+        without: Vec<String>,
+
+        /// Format of the printed trees [default: ptb]
+        #[arg(long, value_name = "FORMAT")]
+        tree_format: Option<TreeFormat>,
     },
+    /// Reads constituent trees from the stdin (or the given treebanks), which may span multiple lines, and reformats them
+    Fmt {
+        /// Files with trees to read instead of the stdin
+        treebanks: Vec<String>,
+
+        /// Prints each tree with one subtree per line, indented by its depth
+        #[arg(long)]
+        pretty: bool,
+
+        /// Number of spaces per level of pretty printed trees [default: 2]
+        #[arg(long, value_name = "N", requires = "pretty")]
+        indent: Option<usize>,
+
+        /// Prints each tree on a single line (the default)
+        #[arg(long, conflicts_with = "pretty")]
+        oneline: bool,
+
+        /// Draws each tree top-down with branches between the nodes, e.g. to inspect trees in the terminal
+        #[arg(long, value_name = "MODE", conflicts_with_all = ["pretty", "oneline"])]
+        render: Option<Render>,
+    },
+    /// Reads constituent trees from the stdin (or the given treebanks), which may span multiple lines, and draws them
+    Viz {
+        /// Files with trees to read instead of the stdin
+        treebanks: Vec<String>,
+
+        /// Draws standalone SVG documents instead of ASCII art
+        #[arg(long)]
+        svg: bool,
+
+        /// Writes the SVG of the n-th tree into DIR/tree-n.svg, which is needed for more than one tree
+        #[arg(long, value_name = "DIR", requires = "svg")]
+        output_dir: Option<String>,
+
+        /// Font family of the SVG labels [default: sans-serif]
+        #[arg(long, value_name = "FAMILY", requires = "svg")]
+        font_family: Option<String>,
+
+        /// Font size of the SVG labels in pixels, which scales the whole drawing [default: 14]
+        #[arg(long, value_name = "PX", value_parser = non_negative, requires = "svg")]
+        font_size: Option<f64>,
+    },
+    /// Reads the grammar GRAMMAR.rules and GRAMMAR.lexicon and keeps only the most probable rules of every head
+    Prune {
+        /// Name of the grammar to prune
+        grammar: String,
+
+        /// If this is set, the pruned grammar is written into PRUNED.rules , PRUNED.lexicon, and PRUNED.words files instead of the stdout
+        pruned: Option<String>,
+
+        /// Keeps the N most probable nonlexical rules of every head and renormalises them
+        #[arg(long, value_name = "N")]
+        top_k: usize,
+
+        /// Initial nonterminal of the grammar [default: the one recorded in GRAMMAR.meta, or ROOT]
+        #[arg(long)]
+        initial: Option<String>,
+
+        /// Gzips the pruned grammar files or the stdout. Gzipped grammar files (e.g. GRAMMAR.rules.gz) are always detected
+        #[arg(long)]
+        gzip: bool,
+    },
+    /// Reads the grammar GRAMMAR.rules and GRAMMAR.lexicon and rescales the rules of every head so that they sum up to one again
+    Renormalize {
+        /// Name of the grammar to renormalise
+        grammar: String,
+
+        /// If this is set, the renormalised grammar is written into RENORMALIZED.rules , RENORMALIZED.lexicon, and RENORMALIZED.words files instead of the stdout
+        renormalized: Option<String>,
+
+        /// Rounds the probabilities to N significant digits after normalising, which makes the grammar files smaller and stable under diffs
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=17))]
+        significant_digits: Option<u32>,
+
+        /// Initial nonterminal of the grammar [default: the one recorded in GRAMMAR.meta, or ROOT]
+        #[arg(long)]
+        initial: Option<String>,
+
+        /// Gzips the renormalised grammar files or the stdout. Gzipped grammar files (e.g. GRAMMAR.rules.gz) are always detected
+        #[arg(long)]
+        gzip: bool,
+    },
+    /// Prints a script that completes the subcommands and options in the shell, e.g. `source <(pcfg_tool completions bash)`
+    Completions {
+        /// Shell to generate the script for
+        shell: Shell,
+    },
+    /// Reads constituent trees (or raw sentences) from the stdin (or the given files) and prints the frequency of every word as TSV, most frequent first
+    Vocab {
+        /// Files with one tree (or sentence) per line to read instead of the stdin
+        treebanks: Vec<String>,
+
+        /// Reads raw text instead of trees, which is split into sentences and tokenized like the PTB (e.g. don't -> do n't)
+        #[arg(long, conflicts_with = "by_preterminal")]
+        sentences: bool,
+
+        /// Counts each word per preterminal, i.e. prints WORD, PRETERMINAL and COUNT columns
+        #[arg(long)]
+        by_preterminal: bool,
+
+        /// Only prints the words occuring at least N times
+        #[arg(long, value_name = "N")]
+        min_count: Option<u64>,
+    },
+    /// Reads the grammar GRAMMAR and a tokenized corpus from the stdin (or the given files) and prints the rates of words the lexicon lacks per sentence length as TSV
+    Oov {
+        /// Name of the grammar whose lexicon is the vocabulary
+        grammar: String,
+
+        /// Files with one tokenized sentence per line to read instead of the stdin
+        corpus: Vec<String>,
+
+        /// Groups the sentences by length into buckets of N lengths, e.g. 1-10 and 11-20
+        #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        bucket_size: u64,
+    },
+    /// Reads the grammar GRAMMAR and a tokenized corpus from the stdin (or the given files) and prints the sentences the grammar can't derive as TSV, together with their words that lack lexical rules
+    Coverage {
+        /// Name of the grammar to check
+        grammar: String,
+
+        /// Files with one tokenized sentence per line to read instead of the stdin
+        corpus: Vec<String>,
+    },
+}
+
+/// Preprocessing of the trees shared by induce, pipeline and transform
+#[derive(Args)]
+pub struct PreprocessArgs {
+    /// Drops trees with more than N words (including punctuation), e.g. 40 like in most parsing papers
+    #[arg(long, value_name = "N")]
+    pub max_length: Option<usize>,
+
+    /// Keeps, removes or raises punctuation preterminals (``, '', ",", ".", ":") to the highest position below the root [default: keep]
+    #[arg(long, value_name = "MODE")]
+    pub punctuation: Option<Punctuation>,
+
+    /// Case-folds all words, which reduces the sparsity of small treebanks
+    #[arg(long)]
+    pub lowercase: bool,
+
+    /// Replaces words consisting of digits, commas and periods (e.g. 1,000.50) by <NUM>
+    #[arg(long)]
+    pub normalize_numbers: bool,
+}
+
+/// Transforms of the trees after their preprocessing shared by transform and pipeline
+#[derive(Args)]
+pub struct TransformArgs {
+    /// Removes the inner node of unaries whose parent and child share their label (e.g. (NP (NP ...)) becomes (NP ...))
+    #[arg(long)]
+    pub remove_x_over_x: bool,
+
+    /// Merges unary chains below the root into single nodes (e.g. (S (VP (VB stop))) becomes (S+VP+VB stop))
+    #[arg(long)]
+    pub collapse_unaries: bool,
+
+    /// Splits collapsed labels (e.g. S+VP+VB) into their unary chains again
+    #[arg(long, conflicts_with = "collapse_unaries")]
+    pub expand_unaries: bool,
+
+    /// Applies the left-corner transform, so that the induced grammar has no left recursion (e.g. (S (NP ...) (VP ...)) becomes (S (DT the) (S/DT ... (S/NP (VP ...)))))
+    #[arg(long)]
+    pub left_corner: bool,
+
+    /// Reverts the left-corner transform, e.g. for the trees of a parser using a left-corner grammar
+    #[arg(long, conflicts_with = "left_corner")]
+    pub undo_left_corner: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Punctuation {
+    /// Leaves the punctuation where it is
+    Keep,
+    /// Removes the punctuation and nodes that are left without words
+    Remove,
+    /// Moves punctuation at the edges of constituents outside of them
+    Raise,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CrossingBranches {
+    /// Keeps the phrases and orders their children by their first word, so that words may appear out of order
+    Reorder,
+    /// Attaches the parts of a phrase that aren't continuous with its head to the parent instead
+    Raise,
+}
+
+/// Smoothing options shared by induce and pipeline, which both declare the "smoothed" group of add_k and smoothing
+#[derive(Args)]
+pub struct SmoothingArgs {
+    /// Adds K to the occurence of every observed rule before normalising (add-k/Laplace smoothing)
+    #[arg(long, value_name = "K", value_parser = non_negative)]
+    pub add_k: Option<f64>,
+
+    /// Smoothes the lexical rules with the given method, which moves probability mass to PRETERMINAL -> UNKNOWN
+    #[arg(long, value_name = "METHOD")]
+    pub smoothing: Option<SmoothingMethod>,
+
+    /// Reserves probability mass for the lexical rule PRETERMINAL -> UNKNOWN of every preterminal (requires --add-k or --smoothing)
+    #[arg(
+        long,
+        value_name = "UNKNOWN",
+        num_args = 0..=1,
+        default_missing_value = UNKNOWN,
+        requires = "smoothed"
+    )]
+    pub reserve_unknown: Option<String>,
+
+    /// Only gives the unknown token to open-class preterminals, i.e. those that occured with words seen exactly once (requires --add-k or --smoothing)
+    #[arg(long, requires = "smoothed")]
+    pub open_class: bool,
+
+    /// Splits the unknown token into signature classes (e.g. NN -> UNK-LC-s) learnt from each preterminal's words seen exactly once (requires --add-k or --smoothing)
+    #[arg(long, requires = "smoothed")]
+    pub unknown_signatures: bool,
+
+    /// Adds the pseudo-counts in PRIOR (one RULE COUNT line per rule, e.g. NP -> NP PP 0.5) to the smoothed counts before normalising
+    #[arg(long, value_name = "PRIOR")]
+    pub prior: Option<String>,
+}
+
+/// Features of the signature classes shared by induce and pipeline, which both declare the "signature_classes" group of signatures and unknown_signatures
+#[derive(Args)]
+pub struct SignatureArgs {
+    /// Leaves the capitalisation (-CAPS, -INITC or -LC) out of the signature classes
+    #[arg(long, requires = "signature_classes")]
+    pub no_signature_capitalisation: bool,
+
+    /// Leaves out whether a word contains a digit (-NUM)
+    #[arg(long, requires = "signature_classes")]
+    pub no_signature_digits: bool,
+
+    /// Leaves out whether a word contains a dash (-DASH)
+    #[arg(long, requires = "signature_classes")]
+    pub no_signature_dash: bool,
+
+    /// Only encodes the common suffixes (-ing, -ion, -ed, -ly, -s) of at most N letters, where 0 encodes none [default: 3]
+    #[arg(long, value_name = "N", requires = "signature_classes")]
+    pub signature_suffix_length: Option<usize>,
+}
+
+impl SignatureArgs {
+    pub fn options(&self) -> SignatureOptions {
+        SignatureOptions {
+            capitalisation: !self.no_signature_capitalisation,
+            digits: !self.no_signature_digits,
+            hyphens: !self.no_signature_dash,
+            suffix_length: self
+                .signature_suffix_length
+                .unwrap_or(MAX_SIGNATURE_SUFFIX_LENGTH),
+        }
+    }
+}
+
+#[derive(Clone, Debug, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmoothingMethod {
+    /// Simple Good-Turing discounting of rare lexical rules
+    GoodTuring,
+    /// Witten-Bell interpolation of the lexical rules with a tag independent word distribution
+    WittenBell,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OovModel {
+    /// Estimates P(tag|suffix) from the suffixes of the rare words (like TnT), which weighs the unknown rules of a word by the tags of its suffix
+    Suffix,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Render {
+    /// Branches made of |, ` and -
+    Ascii,
+    /// Branches made of box-drawing characters
+    Box,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TreeFormat {
+    /// One bracketed tree per line
+    Ptb,
+    /// NEGRA export format (version 3) with one line per word and nonterminal
+    Negra,
+}
+
+/// A treebank whose trees count weight times, given as FILE:WEIGHT (e.g. web.mrg:0.3)
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Source {
+    pub path: String,
+    pub weight: f64,
+}
+
+impl std::str::FromStr for Source {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, weight) = s
+            .rsplit_once(':')
+            .ok_or(format!("{} is not FILE:WEIGHT", s))?;
+        match weight.parse::<f64>() {
+            Ok(weight) if weight.is_finite() && weight > 0.0 => Ok(Source {
+                path: path.to_string(),
+                weight,
+            }),
+            _ => Err(format!("The weight of {} must be positive", path)),
+        }
+    }
+}
+
+impl TryFrom<String> for Source {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum LogFormat {
+    /// One human readable line per message
+    Text,
+    /// One JSON object per message, e.g. for experiment tracking
+    Json,
+}
+
+fn non_negative(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(k) if !k.is_finite() => Err(String::from("must be finite")),
+        Ok(k) if k >= 0.0 => Ok(k),
+        Ok(_) => Err(String::from("must not be negative")),
+        Err(e) => Err(e.to_string()),
+    }
 }
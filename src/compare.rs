@@ -0,0 +1,107 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{Grammar, Nonterminal, Probability, Rule};
+
+/// Divergence between the rule distributions of a head in two grammars (in bits)
+#[derive(Debug, PartialEq)]
+pub struct HeadDivergence {
+    pub head: Nonterminal,
+    /// KL(P || Q), which is infinite if Q lacks a rule of P
+    pub kl: Probability,
+    /// Jensen-Shannon divergence, which is symmetric and at most 1
+    pub js: Probability,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Comparison {
+    /// Divergences of all heads of both grammars in sorted order
+    pub heads: Vec<HeadDivergence>,
+    /// Percentage of rules that are part of both grammars among the rules of either one
+    pub overlap: Probability,
+}
+
+/// Compares the rule distributions of every head of p to those of q, e.g. to quantify how smoothing changed a grammar
+pub fn compare(p: &Grammar, q: &Grammar) -> Comparison {
+    let mut distributions: BTreeMap<&Nonterminal, Vec<(Probability, Probability)>> =
+        BTreeMap::new();
+
+    let rules: HashSet<&Rule> = p.rules.keys().chain(q.rules.keys()).collect();
+    for rule in rules.iter() {
+        let probabilities = (
+            p.rules.get(*rule).copied().unwrap_or(0.0),
+            q.rules.get(*rule).copied().unwrap_or(0.0),
+        );
+        distributions
+            .entry(&rule.head)
+            .or_default()
+            .push(probabilities);
+    }
+
+    let heads = distributions
+        .into_iter()
+        .map(|(head, probabilities)| HeadDivergence {
+            head: head.clone(),
+            kl: kl_divergence(&probabilities),
+            js: js_divergence(&probabilities),
+        })
+        .collect();
+
+    let shared = rules
+        .iter()
+        .filter(|rule| p.rules.contains_key(**rule) && q.rules.contains_key(**rule))
+        .count();
+    let overlap = match rules.len() {
+        0 => 100.0,
+        total => 100.0 * shared as Probability / total as Probability,
+    };
+
+    Comparison { heads, overlap }
+}
+
+fn kl_divergence(probabilities: &[(Probability, Probability)]) -> Probability {
+    probabilities
+        .iter()
+        .filter(|(p, _q)| *p > 0.0)
+        .map(|(p, q)| p * (p / q).log2())
+        .sum()
+}
+
+fn js_divergence(probabilities: &[(Probability, Probability)]) -> Probability {
+    let to_mixture = |select: fn(&(Probability, Probability)) -> Probability| {
+        probabilities
+            .iter()
+            .map(|pair| (select(pair), (pair.0 + pair.1) / 2.0))
+            .collect::<Vec<_>>()
+    };
+
+    (kl_divergence(&to_mixture(|(p, _q)| *p)) + kl_divergence(&to_mixture(|(_p, q)| *q))) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::berkeley::BerkeleyReader;
+
+    fn grammar(rules: &str, lexicon: &str) -> Grammar {
+        BerkeleyReader::read("S".to_string(), rules.as_bytes(), lexicon.as_bytes())
+            .expect("This is a valid grammar")
+    }
+
+    #[test]
+    fn compares_head_distributions() {
+        let p = grammar("S -> A 1\n", "A a 0.5\nA b 0.5\n");
+        let q = grammar("S -> A 1\n", "A a 1\n");
+
+        let comparison = compare(&p, &q);
+        assert_eq!(comparison.heads.len(), 2);
+        assert_eq!(comparison.heads[0].head, "A");
+        assert!(comparison.heads[0].kl.is_infinite());
+        assert!((comparison.heads[0].js - 0.3113).abs() < 1e-4);
+        assert_eq!(comparison.heads[1].kl, 0.0);
+        assert_eq!(comparison.heads[1].js, 0.0);
+        assert!((comparison.overlap - 200.0 / 3.0).abs() < 1e-9);
+
+        let comparison = compare(&q, &p);
+        assert_eq!(comparison.heads[0].kl, 1.0);
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, ErrorKind, Write},
+};
+
+use flate2::{bufread::MultiGzDecoder, write::GzEncoder, Compression};
+
+/// Every gzip stream starts with these bytes
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Transparently decompresses the reader if its content is gzipped
+pub fn decompress<'a, R>(mut reader: R) -> std::io::Result<Box<dyn BufRead + 'a>>
+where
+    R: BufRead + 'a,
+{
+    if reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+/// Opens path, or path.gz if path doesn't exist, and decompresses it if necessary
+pub fn open(path: &str) -> std::io::Result<Box<dyn BufRead>> {
+    let file = match File::open(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            File::open(format!("{}.gz", path)).map_err(|_| e)?
+        }
+        file => file?,
+    };
+
+    decompress(BufReader::new(file))
+}
+
+/// Writes either plainly or gzipped into the inner writer
+pub enum Output<W: Write> {
+    Plain(W),
+    Gzip(GzEncoder<W>),
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(writer: W, gzip: bool) -> Self {
+        if gzip {
+            Output::Gzip(GzEncoder::new(writer, Compression::default()))
+        } else {
+            Output::Plain(writer)
+        }
+    }
+
+    /// Writes the end of the gzip stream, which must happen before the inner writer is dropped
+    pub fn finish(self) -> std::io::Result<W> {
+        match self {
+            Output::Plain(mut writer) => {
+                writer.flush()?;
+                Ok(writer)
+            }
+            Output::Gzip(encoder) => encoder.finish(),
+        }
+    }
+}
+
+impl Output<File> {
+    /// Creates the file at path, or at path.gz if gzip is set
+    pub fn create(path: &str, gzip: bool) -> std::io::Result<Self> {
+        if gzip {
+            Ok(Self::new(File::create(format!("{}.gz", path))?, true))
+        } else {
+            Ok(Self::new(File::create(path)?, false))
+        }
+    }
+}
+
+impl<W: Write> Write for Output<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Output::Plain(writer) => writer.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Output::Plain(writer) => writer.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn decompresses_gzipped_content() {
+        let mut output = Output::new(Vec::new(), true);
+        output
+            .write_all(b"(A a)\n(B b)\n")
+            .expect("Writing to a Vec works");
+        let compressed = output.finish().expect("Writing to a Vec works");
+        assert!(compressed.starts_with(&GZIP_MAGIC));
+
+        let mut content = String::new();
+        decompress(compressed.as_slice())
+            .expect("This is valid gzip")
+            .read_to_string(&mut content)
+            .expect("This is valid gzip");
+        assert_eq!(content, "(A a)\n(B b)\n");
+    }
+
+    #[test]
+    fn passes_through_plain_content() {
+        let mut content = String::new();
+        decompress("(A a)\n".as_bytes())
+            .expect("Reading from a slice works")
+            .read_to_string(&mut content)
+            .expect("Reading from a slice works");
+        assert_eq!(content, "(A a)\n");
+    }
+}
@@ -0,0 +1,1121 @@
+use std::error::Error;
+
+use serde::Deserialize;
+
+use crate::cli::{
+    Commands, CrossingBranches, OovModel, PreprocessArgs, Punctuation, Render, SignatureArgs,
+    SmoothingArgs, SmoothingMethod, Source, TransformArgs, TreeFormat,
+};
+
+/// Experiment settings read from a TOML file with one table per subcommand, e.g.
+///
+/// ```toml
+/// [induce]
+/// grammar = "wsj"
+/// treebank_dir = "corpora/wsj"
+/// smoothing = "witten-bell"
+/// ```
+///
+/// Options given on the command line override those of the file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub induce: InduceConfig,
+    #[serde(default)]
+    pub pipeline: PipelineConfig,
+    #[serde(default)]
+    pub project: ProjectConfig,
+    #[serde(default)]
+    pub transform: TransformConfig,
+    #[serde(default)]
+    pub filter: FilterConfig,
+    #[serde(default)]
+    pub fmt: FmtConfig,
+    #[serde(default)]
+    pub viz: VizConfig,
+    #[serde(default)]
+    pub prune: PruneConfig,
+    #[serde(default)]
+    pub renormalize: RenormalizeConfig,
+    #[serde(default)]
+    pub vocab: VocabConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InduceConfig {
+    pub grammar: Option<String>,
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    #[serde(default)]
+    pub source: Vec<Source>,
+    pub treebank_dir: Option<String>,
+    #[serde(default)]
+    pub tiger: Vec<String>,
+    pub crossing_branches: Option<CrossingBranches>,
+    pub resume: Option<String>,
+    pub output_dir: Option<String>,
+    pub max_length: Option<usize>,
+    pub punctuation: Option<Punctuation>,
+    #[serde(default)]
+    pub lowercase: bool,
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    pub vocab_threshold: Option<u32>,
+    #[serde(default)]
+    pub signatures: bool,
+    #[serde(default)]
+    pub no_signature_capitalisation: bool,
+    #[serde(default)]
+    pub no_signature_digits: bool,
+    #[serde(default)]
+    pub no_signature_dash: bool,
+    pub signature_suffix_length: Option<usize>,
+    pub oov_model: Option<OovModel>,
+    pub oov_suffix_length: Option<u32>,
+    #[serde(default)]
+    pub gzip: bool,
+    #[serde(default)]
+    pub berkeley_lexicon: bool,
+    #[serde(default)]
+    pub lexicon_counts: bool,
+    #[serde(default)]
+    pub word_counts: bool,
+    #[serde(default)]
+    pub counts: bool,
+    #[serde(default)]
+    pub pcfg: bool,
+    pub significant_digits: Option<u32>,
+    #[serde(default)]
+    pub fractions: bool,
+    pub add_k: Option<f64>,
+    pub smoothing: Option<SmoothingMethod>,
+    pub reserve_unknown: Option<String>,
+    #[serde(default)]
+    pub open_class: bool,
+    #[serde(default)]
+    pub unknown_signatures: bool,
+    pub prior: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub grammar: Option<String>,
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    pub max_length: Option<usize>,
+    pub punctuation: Option<Punctuation>,
+    #[serde(default)]
+    pub lowercase: bool,
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    #[serde(default)]
+    pub remove_x_over_x: bool,
+    #[serde(default)]
+    pub collapse_unaries: bool,
+    #[serde(default)]
+    pub expand_unaries: bool,
+    #[serde(default)]
+    pub left_corner: bool,
+    #[serde(default)]
+    pub undo_left_corner: bool,
+    pub unknown_threshold: Option<u32>,
+    #[serde(default)]
+    pub signatures: bool,
+    #[serde(default)]
+    pub no_signature_capitalisation: bool,
+    #[serde(default)]
+    pub no_signature_digits: bool,
+    #[serde(default)]
+    pub no_signature_dash: bool,
+    pub signature_suffix_length: Option<usize>,
+    #[serde(default)]
+    pub project: bool,
+    #[serde(default)]
+    pub gzip: bool,
+    pub add_k: Option<f64>,
+    pub smoothing: Option<SmoothingMethod>,
+    pub reserve_unknown: Option<String>,
+    #[serde(default)]
+    pub open_class: bool,
+    #[serde(default)]
+    pub unknown_signatures: bool,
+    pub prior: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectConfig {
+    pub projected: Option<String>,
+    pub initial: Option<String>,
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformConfig {
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    pub max_length: Option<usize>,
+    pub punctuation: Option<Punctuation>,
+    #[serde(default)]
+    pub lowercase: bool,
+    #[serde(default)]
+    pub normalize_numbers: bool,
+    #[serde(default)]
+    pub remove_x_over_x: bool,
+    #[serde(default)]
+    pub collapse_unaries: bool,
+    #[serde(default)]
+    pub expand_unaries: bool,
+    #[serde(default)]
+    pub left_corner: bool,
+    #[serde(default)]
+    pub undo_left_corner: bool,
+    pub tree_format: Option<TreeFormat>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterConfig {
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    pub root: Option<String>,
+    pub max_depth: Option<usize>,
+    pub min_length: Option<usize>,
+    pub max_length: Option<usize>,
+    #[serde(default)]
+    pub with: Vec<String>,
+    #[serde(default)]
+    pub without: Vec<String>,
+    pub tree_format: Option<TreeFormat>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FmtConfig {
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    #[serde(default)]
+    pub pretty: bool,
+    pub indent: Option<usize>,
+    #[serde(default)]
+    pub oneline: bool,
+    pub render: Option<Render>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VizConfig {
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    #[serde(default)]
+    pub svg: bool,
+    pub output_dir: Option<String>,
+    pub font_family: Option<String>,
+    pub font_size: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PruneConfig {
+    pub pruned: Option<String>,
+    pub initial: Option<String>,
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VocabConfig {
+    #[serde(default)]
+    pub treebanks: Vec<String>,
+    #[serde(default)]
+    pub sentences: bool,
+    #[serde(default)]
+    pub by_preterminal: bool,
+    pub min_count: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RenormalizeConfig {
+    pub renormalized: Option<String>,
+    pub significant_digits: Option<u32>,
+    pub initial: Option<String>,
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+impl Config {
+    pub fn from_toml(s: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Fills in the options of the command that weren't given on the command line.
+    /// Flags can only be switched on by the file, since the command line can't tell an unset flag from a false one
+    pub fn apply(self, command: &mut Commands) {
+        match command {
+            Commands::Induce {
+                grammar,
+                treebanks,
+                source,
+                treebank_dir,
+                tiger,
+                crossing_branches,
+                preprocess_args,
+                vocab_threshold,
+                signatures,
+                signature_args,
+                oov_model,
+                oov_suffix_length,
+                smoothing_args,
+                resume,
+                gzip,
+                output_dir,
+                berkeley_lexicon,
+                lexicon_counts,
+                word_counts,
+                counts,
+                pcfg,
+                significant_digits,
+                fractions,
+                progress: _,
+            } => {
+                let config = self.induce;
+
+                fill(grammar, config.grammar);
+                fill_all(treebanks, config.treebanks);
+                fill_all(source, config.source);
+                fill(treebank_dir, config.treebank_dir);
+                fill_all(tiger, config.tiger);
+                fill(crossing_branches, config.crossing_branches);
+                fill(resume, config.resume);
+                fill(output_dir, config.output_dir);
+                fill(vocab_threshold, config.vocab_threshold);
+                *signatures |= config.signatures;
+                apply_signature_features(
+                    signature_args,
+                    config.no_signature_capitalisation,
+                    config.no_signature_digits,
+                    config.no_signature_dash,
+                    config.signature_suffix_length,
+                );
+                fill(oov_model, config.oov_model);
+                fill(oov_suffix_length, config.oov_suffix_length);
+                apply_preprocessing(
+                    preprocess_args,
+                    config.max_length,
+                    config.punctuation,
+                    config.lowercase,
+                    config.normalize_numbers,
+                );
+                *gzip |= config.gzip;
+                *berkeley_lexicon |= config.berkeley_lexicon;
+                *lexicon_counts |= config.lexicon_counts;
+                *word_counts |= config.word_counts;
+                *counts |= config.counts;
+                *pcfg |= config.pcfg;
+                fill(significant_digits, config.significant_digits);
+                *fractions |= config.fractions;
+                apply_smoothing(
+                    smoothing_args,
+                    config.add_k,
+                    config.smoothing,
+                    config.reserve_unknown,
+                    config.open_class,
+                    config.unknown_signatures,
+                    config.prior,
+                );
+            }
+            Commands::Pipeline {
+                grammar,
+                treebanks,
+                preprocess_args,
+                transform_args,
+                unknown_threshold,
+                signatures,
+                signature_args,
+                smoothing_args,
+                project,
+                gzip,
+            } => {
+                let config = self.pipeline;
+
+                fill(grammar, config.grammar);
+                fill_all(treebanks, config.treebanks);
+                apply_preprocessing(
+                    preprocess_args,
+                    config.max_length,
+                    config.punctuation,
+                    config.lowercase,
+                    config.normalize_numbers,
+                );
+                apply_transforms(
+                    transform_args,
+                    config.remove_x_over_x,
+                    config.collapse_unaries,
+                    config.expand_unaries,
+                    config.left_corner,
+                    config.undo_left_corner,
+                );
+                fill(unknown_threshold, config.unknown_threshold);
+                *signatures |= config.signatures;
+                apply_signature_features(
+                    signature_args,
+                    config.no_signature_capitalisation,
+                    config.no_signature_digits,
+                    config.no_signature_dash,
+                    config.signature_suffix_length,
+                );
+                *project |= config.project;
+                *gzip |= config.gzip;
+                apply_smoothing(
+                    smoothing_args,
+                    config.add_k,
+                    config.smoothing,
+                    config.reserve_unknown,
+                    config.open_class,
+                    config.unknown_signatures,
+                    config.prior,
+                );
+            }
+            Commands::Project {
+                grammar: _,
+                projected,
+                initial,
+                gzip,
+            } => {
+                let config = self.project;
+
+                // The grammar is a required argument, so it is always given
+                fill(projected, config.projected);
+                fill(initial, config.initial);
+                *gzip |= config.gzip;
+            }
+            Commands::Transform {
+                treebanks,
+                preprocess_args,
+                transform_args,
+                tree_format,
+            } => {
+                let config = self.transform;
+
+                fill_all(treebanks, config.treebanks);
+                apply_preprocessing(
+                    preprocess_args,
+                    config.max_length,
+                    config.punctuation,
+                    config.lowercase,
+                    config.normalize_numbers,
+                );
+                apply_transforms(
+                    transform_args,
+                    config.remove_x_over_x,
+                    config.collapse_unaries,
+                    config.expand_unaries,
+                    config.left_corner,
+                    config.undo_left_corner,
+                );
+                fill(tree_format, config.tree_format);
+            }
+            // The grammars are required arguments, and queries only make sense on the command line
+            Commands::Compare { .. }
+            | Commands::Query { .. }
+            | Commands::Completions { .. }
+            | Commands::Oov { .. }
+            | Commands::Coverage { .. } => {}
+            Commands::Filter {
+                treebanks,
+                root,
+                max_depth,
+                min_length,
+                max_length,
+                with,
+                without,
+                tree_format,
+            } => {
+                let config = self.filter;
+
+                fill_all(treebanks, config.treebanks);
+                fill(root, config.root);
+                fill(max_depth, config.max_depth);
+                fill(min_length, config.min_length);
+                fill(max_length, config.max_length);
+                fill_all(with, config.with);
+                fill_all(without, config.without);
+                fill(tree_format, config.tree_format);
+            }
+            Commands::Fmt {
+                treebanks,
+                pretty,
+                indent,
+                oneline,
+                render,
+            } => {
+                let config = self.fmt;
+
+                fill_all(treebanks, config.treebanks);
+                // The modes conflict, so the file can't switch on another one
+                if !*pretty && !*oneline && render.is_none() {
+                    *pretty = config.pretty;
+                    *oneline = config.oneline;
+                    *render = config.render;
+                }
+                fill(indent, config.indent);
+            }
+            Commands::Viz {
+                treebanks,
+                svg,
+                output_dir,
+                font_family,
+                font_size,
+            } => {
+                let config = self.viz;
+
+                fill_all(treebanks, config.treebanks);
+                *svg |= config.svg;
+                fill(output_dir, config.output_dir);
+                fill(font_family, config.font_family);
+                fill(font_size, config.font_size);
+            }
+            Commands::Prune {
+                grammar: _,
+                pruned,
+                top_k: _,
+                initial,
+                gzip,
+            } => {
+                let config = self.prune;
+
+                // The grammar and the number of rules are required on the command line
+                fill(pruned, config.pruned);
+                fill(initial, config.initial);
+                *gzip |= config.gzip;
+            }
+            Commands::Renormalize {
+                grammar: _,
+                renormalized,
+                significant_digits,
+                initial,
+                gzip,
+            } => {
+                let config = self.renormalize;
+
+                // The grammar is a required argument, so it is always given
+                fill(renormalized, config.renormalized);
+                fill(significant_digits, config.significant_digits);
+                fill(initial, config.initial);
+                *gzip |= config.gzip;
+            }
+            Commands::Vocab {
+                treebanks,
+                sentences,
+                by_preterminal,
+                min_count,
+            } => {
+                let config = self.vocab;
+
+                fill_all(treebanks, config.treebanks);
+                // Sentences have no preterminals, so the file can't switch on one of them if the other is given
+                if !*by_preterminal {
+                    *sentences |= config.sentences;
+                }
+                if !*sentences {
+                    *by_preterminal |= config.by_preterminal;
+                }
+                fill(min_count, config.min_count);
+            }
+        }
+    }
+}
+
+fn apply_preprocessing(
+    preprocess_args: &mut PreprocessArgs,
+    max_length: Option<usize>,
+    punctuation: Option<Punctuation>,
+    lowercase: bool,
+    normalize_numbers: bool,
+) {
+    fill(&mut preprocess_args.max_length, max_length);
+    fill(&mut preprocess_args.punctuation, punctuation);
+    preprocess_args.lowercase |= lowercase;
+    preprocess_args.normalize_numbers |= normalize_numbers;
+}
+
+fn apply_transforms(
+    transform_args: &mut TransformArgs,
+    remove_x_over_x: bool,
+    collapse_unaries: bool,
+    expand_unaries: bool,
+    left_corner: bool,
+    undo_left_corner: bool,
+) {
+    transform_args.remove_x_over_x |= remove_x_over_x;
+    transform_args.collapse_unaries |= collapse_unaries;
+    transform_args.expand_unaries |= expand_unaries;
+    transform_args.left_corner |= left_corner;
+    transform_args.undo_left_corner |= undo_left_corner;
+}
+
+/// Checks the options of the command like clap does, which is needed after the config file filled in options
+/// that bypassed the value parsers and the requirements and conflicts between options
+pub fn validate(command: &Commands) -> Result<(), String> {
+    match command {
+        Commands::Induce {
+            grammar,
+            tiger,
+            crossing_branches,
+            vocab_threshold,
+            signatures,
+            signature_args,
+            oov_model,
+            oov_suffix_length,
+            smoothing_args,
+            output_dir,
+            berkeley_lexicon,
+            lexicon_counts,
+            word_counts,
+            counts,
+            pcfg,
+            significant_digits,
+            fractions,
+            ..
+        } => {
+            requires(
+                "--crossing-branches",
+                crossing_branches.is_some(),
+                "--tiger",
+                !tiger.is_empty(),
+            )?;
+            requires(
+                "--signatures",
+                *signatures,
+                "--vocab-threshold",
+                vocab_threshold.is_some(),
+            )?;
+            validate_signature_features(
+                signature_args,
+                *signatures || smoothing_args.unknown_signatures,
+            )?;
+            requires(
+                "--oov-model",
+                oov_model.is_some(),
+                "--vocab-threshold",
+                vocab_threshold.is_some(),
+            )?;
+            requires(
+                "--oov-model",
+                oov_model.is_some(),
+                "GRAMMAR",
+                grammar.is_some(),
+            )?;
+            requires(
+                "--oov-suffix-length",
+                oov_suffix_length.is_some(),
+                "--oov-model",
+                oov_model.is_some(),
+            )?;
+            if *oov_suffix_length == Some(0) {
+                return Err(String::from(
+                    "--oov-suffix-length must be at least 1, not 0",
+                ));
+            }
+            validate_smoothing(smoothing_args)?;
+            requires(
+                "--output-dir",
+                output_dir.is_some(),
+                "GRAMMAR",
+                grammar.is_some(),
+            )?;
+            requires("--counts", *counts, "GRAMMAR", grammar.is_some())?;
+            requires(
+                "--lexicon-counts",
+                *lexicon_counts,
+                "--berkeley-lexicon",
+                *berkeley_lexicon,
+            )?;
+            conflicts("--pcfg", *pcfg, "--berkeley-lexicon", *berkeley_lexicon)?;
+            conflicts("--pcfg", *pcfg, "--word-counts", *word_counts)?;
+            validate_significant_digits(*significant_digits)?;
+
+            let smoothed = smoothing_args.add_k.is_some() || smoothing_args.smoothing.is_some();
+            conflicts(
+                "--fractions",
+                *fractions,
+                "--add-k or --smoothing",
+                smoothed,
+            )?;
+            conflicts(
+                "--fractions",
+                *fractions,
+                "--prior",
+                smoothing_args.prior.is_some(),
+            )?;
+            conflicts(
+                "--fractions",
+                *fractions,
+                "--significant-digits",
+                significant_digits.is_some(),
+            )
+        }
+        Commands::Pipeline {
+            transform_args,
+            unknown_threshold,
+            signatures,
+            signature_args,
+            smoothing_args,
+            ..
+        } => {
+            validate_transforms(transform_args)?;
+            requires(
+                "--signatures",
+                *signatures,
+                "--unknown-threshold",
+                unknown_threshold.is_some(),
+            )?;
+            validate_signature_features(
+                signature_args,
+                *signatures || smoothing_args.unknown_signatures,
+            )?;
+            validate_smoothing(smoothing_args)
+        }
+        Commands::Transform { transform_args, .. } => validate_transforms(transform_args),
+        Commands::Fmt {
+            pretty,
+            indent,
+            oneline,
+            render,
+            ..
+        } => {
+            requires("--indent", indent.is_some(), "--pretty", *pretty)?;
+            conflicts("--oneline", *oneline, "--pretty", *pretty)?;
+            conflicts(
+                "--render",
+                render.is_some(),
+                "--pretty or --oneline",
+                *pretty || *oneline,
+            )
+        }
+        Commands::Viz {
+            svg,
+            output_dir,
+            font_family,
+            font_size,
+            ..
+        } => {
+            requires("--output-dir", output_dir.is_some(), "--svg", *svg)?;
+            requires("--font-family", font_family.is_some(), "--svg", *svg)?;
+            requires("--font-size", font_size.is_some(), "--svg", *svg)?;
+            non_negative("--font-size", *font_size)
+        }
+        Commands::Renormalize {
+            significant_digits, ..
+        } => validate_significant_digits(*significant_digits),
+        Commands::Vocab {
+            sentences,
+            by_preterminal,
+            ..
+        } => conflicts(
+            "--sentences",
+            *sentences,
+            "--by-preterminal",
+            *by_preterminal,
+        ),
+        Commands::Project { .. }
+        | Commands::Filter { .. }
+        | Commands::Compare { .. }
+        | Commands::Query { .. }
+        | Commands::Prune { .. }
+        | Commands::Completions { .. }
+        | Commands::Oov { .. }
+        | Commands::Coverage { .. } => Ok(()),
+    }
+}
+
+fn validate_smoothing(smoothing_args: &SmoothingArgs) -> Result<(), String> {
+    let smoothed = smoothing_args.add_k.is_some() || smoothing_args.smoothing.is_some();
+
+    non_negative("--add-k", smoothing_args.add_k)?;
+    conflicts(
+        "--add-k",
+        smoothing_args.add_k.is_some(),
+        "--smoothing",
+        smoothing_args.smoothing.is_some(),
+    )?;
+    requires(
+        "--reserve-unknown",
+        smoothing_args.reserve_unknown.is_some(),
+        "--add-k or --smoothing",
+        smoothed,
+    )?;
+    requires(
+        "--open-class",
+        smoothing_args.open_class,
+        "--add-k or --smoothing",
+        smoothed,
+    )?;
+    requires(
+        "--unknown-signatures",
+        smoothing_args.unknown_signatures,
+        "--add-k or --smoothing",
+        smoothed,
+    )
+}
+
+fn validate_signature_features(
+    signature_args: &SignatureArgs,
+    signature_classes: bool,
+) -> Result<(), String> {
+    let features = [
+        (
+            "--no-signature-capitalisation",
+            signature_args.no_signature_capitalisation,
+        ),
+        ("--no-signature-digits", signature_args.no_signature_digits),
+        ("--no-signature-dash", signature_args.no_signature_dash),
+        (
+            "--signature-suffix-length",
+            signature_args.signature_suffix_length.is_some(),
+        ),
+    ];
+
+    for (option, given) in features {
+        requires(
+            option,
+            given,
+            "--signatures or --unknown-signatures",
+            signature_classes,
+        )?;
+    }
+
+    Ok(())
+}
+
+fn validate_transforms(transform_args: &TransformArgs) -> Result<(), String> {
+    conflicts(
+        "--expand-unaries",
+        transform_args.expand_unaries,
+        "--collapse-unaries",
+        transform_args.collapse_unaries,
+    )?;
+    conflicts(
+        "--undo-left-corner",
+        transform_args.undo_left_corner,
+        "--left-corner",
+        transform_args.left_corner,
+    )
+}
+
+fn validate_significant_digits(significant_digits: Option<u32>) -> Result<(), String> {
+    match significant_digits {
+        Some(digits) if !(1..=17).contains(&digits) => Err(format!(
+            "--significant-digits must be between 1 and 17, not {}",
+            digits
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn non_negative(option: &str, value: Option<f64>) -> Result<(), String> {
+    match value {
+        Some(value) if !value.is_finite() => {
+            Err(format!("{} must be finite, not {}", option, value))
+        }
+        Some(value) if value < 0.0 => {
+            Err(format!("{} must not be negative, not {}", option, value))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn requires(option: &str, given: bool, requirement: &str, required: bool) -> Result<(), String> {
+    match given && !required {
+        true => Err(format!("{} requires {}", option, requirement)),
+        false => Ok(()),
+    }
+}
+
+fn conflicts(option: &str, given: bool, other: &str, other_given: bool) -> Result<(), String> {
+    match given && other_given {
+        true => Err(format!("{} can't be used with {}", option, other)),
+        false => Ok(()),
+    }
+}
+
+fn apply_signature_features(
+    signature_args: &mut SignatureArgs,
+    no_capitalisation: bool,
+    no_digits: bool,
+    no_dash: bool,
+    suffix_length: Option<usize>,
+) {
+    signature_args.no_signature_capitalisation |= no_capitalisation;
+    signature_args.no_signature_digits |= no_digits;
+    signature_args.no_signature_dash |= no_dash;
+    fill(&mut signature_args.signature_suffix_length, suffix_length);
+}
+
+fn apply_smoothing(
+    smoothing_args: &mut SmoothingArgs,
+    add_k: Option<f64>,
+    smoothing: Option<SmoothingMethod>,
+    reserve_unknown: Option<String>,
+    open_class: bool,
+    unknown_signatures: bool,
+    prior: Option<String>,
+) {
+    // Both select the smoothing, so the file must not add one to a method given on the command line
+    if smoothing_args.add_k.is_none() && smoothing_args.smoothing.is_none() {
+        smoothing_args.add_k = add_k;
+        smoothing_args.smoothing = smoothing;
+    }
+    fill(&mut smoothing_args.reserve_unknown, reserve_unknown);
+    smoothing_args.open_class |= open_class;
+    smoothing_args.unknown_signatures |= unknown_signatures;
+    fill(&mut smoothing_args.prior, prior);
+}
+
+fn fill<T>(option: &mut Option<T>, value: Option<T>) {
+    if option.is_none() {
+        *option = value;
+    }
+}
+
+fn fill_all<T>(values: &mut Vec<T>, defaults: Vec<T>) {
+    if values.is_empty() {
+        *values = defaults;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn induce(grammar: Option<&str>, add_k: Option<f64>) -> Commands {
+        Commands::Induce {
+            grammar: grammar.map(String::from),
+            treebanks: Vec::new(),
+            source: Vec::new(),
+            treebank_dir: None,
+            tiger: Vec::new(),
+            crossing_branches: None,
+            preprocess_args: PreprocessArgs {
+                max_length: None,
+                punctuation: None,
+                lowercase: false,
+                normalize_numbers: false,
+            },
+            vocab_threshold: None,
+            signatures: false,
+            signature_args: SignatureArgs {
+                no_signature_capitalisation: false,
+                no_signature_digits: false,
+                no_signature_dash: false,
+                signature_suffix_length: None,
+            },
+            oov_model: None,
+            oov_suffix_length: None,
+            smoothing_args: SmoothingArgs {
+                add_k,
+                smoothing: None,
+                reserve_unknown: None,
+                open_class: false,
+                unknown_signatures: false,
+                prior: None,
+            },
+            resume: None,
+            gzip: false,
+            output_dir: None,
+            berkeley_lexicon: false,
+            lexicon_counts: false,
+            word_counts: false,
+            counts: false,
+            pcfg: false,
+            significant_digits: None,
+            fractions: false,
+            progress: false,
+        }
+    }
+
+    #[test]
+    fn command_line_overrides_file() {
+        let config = "[induce]\ngrammar = \"wsj\"\ntreebanks = [\"a.mrg\", \"b.mrg\"]\nsmoothing = \"witten-bell\"\ngzip = true\n";
+
+        let mut command = induce(None, None);
+        Config::from_toml(config)
+            .expect("This is a valid config")
+            .apply(&mut command);
+
+        match command {
+            Commands::Induce {
+                grammar,
+                treebanks,
+                smoothing_args,
+                gzip,
+                ..
+            } => {
+                assert_eq!(grammar.as_deref(), Some("wsj"));
+                assert_eq!(treebanks, vec!["a.mrg", "b.mrg"]);
+                assert!(matches!(
+                    smoothing_args.smoothing,
+                    Some(SmoothingMethod::WittenBell)
+                ));
+                assert!(gzip);
+            }
+            _ => unreachable!(),
+        }
+
+        let mut command = induce(Some("ptb"), Some(1.0));
+        Config::from_toml(config)
+            .expect("This is a valid config")
+            .apply(&mut command);
+
+        match command {
+            Commands::Induce {
+                grammar,
+                smoothing_args,
+                ..
+            } => {
+                assert_eq!(grammar.as_deref(), Some("ptb"));
+                assert_eq!(smoothing_args.add_k, Some(1.0));
+                assert!(smoothing_args.smoothing.is_none());
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn validates_options_from_file() {
+        let apply = |config: &str| {
+            let mut command = induce(Some("wsj"), None);
+            Config::from_toml(config)
+                .expect("This is a valid config")
+                .apply(&mut command);
+            validate(&command)
+        };
+
+        assert_eq!(apply("[induce]\nadd_k = 1.0\n"), Ok(()));
+        assert_eq!(
+            apply("[induce]\nadd_k = -0.9\n"),
+            Err(String::from("--add-k must not be negative, not -0.9"))
+        );
+        assert_eq!(
+            apply("[induce]\nadd_k = inf\n"),
+            Err(String::from("--add-k must be finite, not inf"))
+        );
+        assert_eq!(
+            apply("[induce]\nadd_k = 1.0\nfractions = true\n"),
+            Err(String::from(
+                "--fractions can't be used with --add-k or --smoothing"
+            ))
+        );
+        assert_eq!(
+            apply("[induce]\nsignificant_digits = 18\n"),
+            Err(String::from(
+                "--significant-digits must be between 1 and 17, not 18"
+            ))
+        );
+        assert_eq!(
+            apply("[induce]\nlexicon_counts = true\n"),
+            Err(String::from("--lexicon-counts requires --berkeley-lexicon"))
+        );
+        assert_eq!(
+            apply("[induce]\npcfg = true\nword_counts = true\n"),
+            Err(String::from("--pcfg can't be used with --word-counts"))
+        );
+        assert_eq!(
+            apply("[induce]\nsignatures = true\n"),
+            Err(String::from("--signatures requires --vocab-threshold"))
+        );
+        assert_eq!(
+            apply("[induce]\noov_model = \"suffix\"\n"),
+            Err(String::from("--oov-model requires --vocab-threshold"))
+        );
+        assert_eq!(
+            apply("[induce]\nvocab_threshold = 2\noov_model = \"suffix\"\noov_suffix_length = 0\n"),
+            Err(String::from(
+                "--oov-suffix-length must be at least 1, not 0"
+            ))
+        );
+    }
+
+    #[test]
+    fn applies_pipeline_transforms_from_file() {
+        use crate::cli::{Cli, Parser};
+
+        let apply = |config: &str| {
+            let mut cli = Cli::try_parse_from(["pcfg_tool", "pipeline", "--left-corner"])
+                .expect("These are valid options");
+            let mut command = cli.command.take().expect("A subcommand is given");
+            Config::from_toml(config)
+                .expect("This is a valid config")
+                .apply(&mut command);
+            validate(&command).map(|_| command)
+        };
+
+        match apply(
+            "[pipeline]
+lowercase = true
+remove_x_over_x = true
+",
+        ) {
+            Ok(Commands::Pipeline {
+                preprocess_args,
+                transform_args,
+                ..
+            }) => {
+                assert!(preprocess_args.lowercase);
+                assert!(transform_args.remove_x_over_x);
+                assert!(transform_args.left_corner);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            apply(
+                "[pipeline]
+undo_left_corner = true
+"
+            )
+            .err(),
+            Some(String::from(
+                "--undo-left-corner can't be used with --left-corner"
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        Config::from_toml("[induce]\nmarkovisation = 2\n").expect_err("There is no such option");
+    }
+
+    #[test]
+    fn reads_weighted_sources() {
+        let config = Config::from_toml("[induce]\nsource = [\"wsj.mrg:1\", \"c:/web.mrg:0.3\"]\n")
+            .expect("This is a valid config");
+        assert_eq!(
+            config.induce.source,
+            vec![
+                Source {
+                    path: "wsj.mrg".to_string(),
+                    weight: 1.0
+                },
+                Source {
+                    path: "c:/web.mrg".to_string(),
+                    weight: 0.3
+                },
+            ]
+        );
+
+        Config::from_toml("[induce]\nsource = [\"web.mrg\"]\n").expect_err("The weight is missing");
+        Config::from_toml("[induce]\nsource = [\"web.mrg:-1\"]\n")
+            .expect_err("Weights are positive");
+    }
+}
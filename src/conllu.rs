@@ -0,0 +1,91 @@
+use std::io::BufRead;
+
+/// A token of a CoNLL-U sentence
+#[derive(Debug, PartialEq, Clone)]
+pub struct Token {
+    /// The word form (FORM column)
+    pub form: String,
+    /// The universal POS tag (UPOS column), or None if it is unspecified (_)
+    pub upos: Option<String>,
+}
+
+/// Reads the sentences of a CoNLL-U file, where sentences are separated by blank lines.
+/// Comments, multiword token ranges (e.g. 1-2) and empty nodes (e.g. 1.1) are skipped,
+/// so that every token is a syntactic word
+pub fn read_conllu<R: BufRead>(reader: R) -> Result<Vec<Vec<Token>>, Box<dyn std::error::Error>> {
+    let mut sentences = Vec::new();
+    let mut sentence = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            if !sentence.is_empty() {
+                sentences.push(std::mem::take(&mut sentence));
+            }
+            continue;
+        }
+
+        if line.starts_with('#') {
+            continue;
+        }
+
+        let columns: Vec<&str> = line.split('\t').collect();
+        let (id, form, upos) = match columns.as_slice() {
+            [id, form, _lemma, upos, _xpos, _feats, _head, _deprel, _deps, _misc] => {
+                (*id, *form, *upos)
+            }
+            _ => {
+                return Err(format!(
+                    "Line {} has {} instead of 10 tab separated columns",
+                    i + 1,
+                    columns.len()
+                )
+                .into())
+            }
+        };
+
+        if id.contains(['-', '.']) {
+            continue;
+        }
+
+        sentence.push(Token {
+            form: form.to_string(),
+            upos: (upos != "_").then(|| upos.to_string()),
+        });
+    }
+
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+
+    Ok(sentences)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_forms_and_tags() {
+        let conllu = "# sent_id = 1\n# text = Don't stop.\n1-2\tDon't\t_\t_\t_\t_\t_\t_\t_\t_\n1\tDo\tdo\tAUX\tVBP\t_\t3\taux\t_\t_\n2\tn't\tnot\tPART\tRB\t_\t3\tadvmod\t_\t_\n3\tstop\tstop\tVERB\tVB\t_\t0\troot\t_\t_\n3.1\tgo\tgo\tVERB\tVB\t_\t_\t_\t3:conj\t_\n4\t.\t.\t_\t.\t_\t3\tpunct\t_\t_\n\n1\tHi\thi\tINTJ\tUH\t_\t0\troot\t_\t_\n";
+
+        let sentences = read_conllu(conllu.as_bytes()).expect("This is valid CoNLL-U");
+        assert_eq!(sentences.len(), 2);
+        assert_eq!(
+            sentences[0]
+                .iter()
+                .map(|token| token.form.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["Do", "n't", "stop", "."]
+        );
+        assert_eq!(sentences[0][2].upos.as_deref(), Some("VERB"));
+        assert_eq!(sentences[0][3].upos, None);
+
+        let err = read_conllu("1\tHi\thi\n".as_bytes()).expect_err("Columns are missing");
+        assert_eq!(
+            err.to_string(),
+            "Line 1 has 3 instead of 10 tab separated columns"
+        );
+    }
+}
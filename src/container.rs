@@ -0,0 +1,282 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufWriter, Write},
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    berkeley::{unescape, BerkeleyFormatWriter, BerkeleyReader, BerkeleyWriter, Escaped},
+    format::{unknown_part, GrammarFormatReader, GrammarFormatWriter},
+    Grammar, Nonterminal, ProbabilityRules,
+};
+
+/// First line of every container, which identifies the format
+pub const MAGIC: &str = "#pcfg_tool grammar";
+
+/// Header of a .pcfg container, which holds what the Berkeley files don't record
+#[derive(Debug, PartialEq, Clone)]
+pub struct Metadata {
+    /// Version of pcfg_tool that wrote the container
+    pub version: String,
+    pub initial: Nonterminal,
+    /// Preprocessing applied to the treebank before inducing (e.g. lowercase or vocab-threshold=2)
+    pub transforms: Vec<String>,
+}
+
+impl Metadata {
+    pub fn new(initial: &str, transforms: Vec<String>) -> Self {
+        Metadata {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            initial: initial.to_string(),
+            transforms,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Header,
+    Rules,
+    Lexicon,
+    Words,
+}
+
+/// Writes only the header lines (#version, #initial and #transforms), which is also the content of a GRAMMAR.meta sidecar
+pub fn write_metadata<W: Write>(metadata: &Metadata, w: &mut W) -> std::io::Result<()> {
+    writeln!(w, "{}", MAGIC)?;
+    writeln!(w, "#version {}", metadata.version)?;
+    writeln!(w, "#initial {}", Escaped(&metadata.initial))?;
+    write!(w, "#transforms")?;
+    for transform in metadata.transforms.iter() {
+        write!(w, " {}", Escaped(transform))?;
+    }
+    writeln!(w)
+}
+
+/// Writes the grammar as a single file: the header lines followed by the #rules, #lexicon and #words sections in Berkeley format.
+/// Escaped symbols never start with #, so the markers can't be mistaken for rules
+pub fn write_container<W: Write>(
+    berkeley_writer: &BerkeleyWriter,
+    metadata: &Metadata,
+    w: &mut W,
+) -> std::io::Result<()> {
+    let mut w = BufWriter::new(w);
+
+    write_metadata(metadata, &mut w)?;
+    writeln!(w, "#rules")?;
+    berkeley_writer.rules_io(&mut w)?;
+    writeln!(w, "#lexicon")?;
+    berkeley_writer.lexicon_io(&mut w)?;
+    writeln!(w, "#words")?;
+    berkeley_writer.words_io(&mut w)?;
+
+    w.flush()
+}
+
+/// Writes GRAMMAR.pcfg with the sections written like by the Berkeley writer
+pub struct ContainerWriter<'a> {
+    pub writer: BerkeleyWriter<'a>,
+    pub metadata: &'a Metadata,
+}
+
+impl GrammarFormatWriter for ContainerWriter<'_> {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["pcfg"]
+    }
+
+    fn write_part(&self, extension: &str, mut w: &mut dyn Write) -> std::io::Result<()> {
+        match extension {
+            "pcfg" => write_container(&self.writer, self.metadata, &mut w),
+            extension => Err(unknown_part(extension)),
+        }
+    }
+}
+
+/// Reads GRAMMAR.pcfg, whose initial can be overridden
+pub struct ContainerReader;
+
+impl GrammarFormatReader for ContainerReader {
+    fn extensions(&self) -> &'static [&'static str] {
+        &["pcfg"]
+    }
+
+    fn read_parts(
+        &self,
+        parts: Vec<Box<dyn BufRead>>,
+        initial: Option<&str>,
+    ) -> Result<Grammar, Box<dyn Error>> {
+        let [container]: [Box<dyn BufRead>; 1] = parts
+            .try_into()
+            .map_err(|_| "A container is a single file")?;
+
+        let (mut grammar, _metadata) = read_container(container)?;
+        if let Some(initial) = initial {
+            grammar.set_initial(initial.to_string());
+        }
+
+        Ok(grammar)
+    }
+}
+
+/// Reads a container written by write_container(). The words are skipped, since they can be derived from the lexicon
+pub fn read_container<R: BufRead>(reader: R) -> Result<(Grammar, Metadata), Box<dyn Error>> {
+    let mut lines = reader.lines();
+    match lines.next().transpose()? {
+        Some(line) if line.trim_end() == MAGIC => (),
+        _ => return Err(format!("A container has to start with \"{}\"", MAGIC).into()),
+    }
+
+    let mut version = None;
+    let mut initial = None;
+    let mut transforms = Vec::new();
+    let mut rules: ProbabilityRules = FxHashMap::default();
+    let mut section = Section::Header;
+
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        // The magic line isn't enumerated
+        let number = i + 2;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(marker) = line.strip_prefix('#') {
+            let (key, value) = marker.split_once(' ').unwrap_or((marker, ""));
+            let malformed = || format!("Malformed value of #{} on line {}", key, number);
+            match (key, section) {
+                ("version", Section::Header) => version = Some(value.trim().to_string()),
+                ("initial", Section::Header) => {
+                    initial = Some(unescape(value.trim()).ok_or_else(malformed)?)
+                }
+                ("transforms", Section::Header) => {
+                    transforms = value
+                        .split_whitespace()
+                        .map(unescape)
+                        .collect::<Option<_>>()
+                        .ok_or_else(malformed)?
+                }
+                ("rules", _) => section = Section::Rules,
+                ("lexicon", _) => section = Section::Lexicon,
+                ("words", _) => section = Section::Words,
+                _ => return Err(format!("Unexpected #{} on line {}", key, number).into()),
+            }
+            continue;
+        }
+
+        match section {
+            Section::Header => {
+                return Err(format!("Expected a header line on line {}", number).into())
+            }
+            Section::Rules => {
+                let (rule, probability) = BerkeleyReader::parse_rule(&line)
+                    .ok_or_else(|| format!("Malformed rule \"{}\" on line {}", line, number))?;
+                rules.insert(rule, probability);
+            }
+            Section::Lexicon => {
+                let (rule, probability) =
+                    BerkeleyReader::parse_lexicon_entry(&line).ok_or_else(|| {
+                        format!("Malformed lexicon entry \"{}\" on line {}", line, number)
+                    })?;
+                rules.insert(rule, probability);
+            }
+            Section::Words => (),
+        }
+    }
+
+    let metadata = Metadata {
+        version: version.ok_or("The container has no #version")?,
+        initial: initial.ok_or("The container has no #initial")?,
+        transforms,
+    };
+    let grammar = Grammar {
+        initial: metadata.initial.clone(),
+        rules,
+    };
+
+    Ok((grammar, metadata))
+}
+
+/// Reads a GRAMMAR.meta sidecar, i.e. a container without sections
+pub fn read_metadata<R: BufRead>(reader: R) -> Result<Metadata, Box<dyn Error>> {
+    read_container(reader).map(|(_grammar, metadata)| metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Body, Rule};
+
+    #[test]
+    fn reads_written_container() {
+        let grammar = Grammar {
+            initial: "S#1".to_string(),
+            rules: FxHashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "S#1".to_string(),
+                        body: Body::NonLexical(vec!["NP".to_string(), "VP".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "NP".to_string(),
+                        body: Body::Lexical("#rules".to_string()),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "VP".to_string(),
+                        body: Body::Lexical("stabs".to_string()),
+                    },
+                    1.0,
+                ),
+            ]),
+        };
+        let metadata = Metadata::new(
+            "S#1",
+            vec!["lowercase".to_string(), "vocab-threshold=2".to_string()],
+        );
+
+        let mut container = Vec::new();
+        write_container(
+            &BerkeleyWriter::from_grammar(&grammar),
+            &metadata,
+            &mut container,
+        )
+        .expect("Writing to a Vec works");
+        let text = String::from_utf8(container.clone()).expect("The container is UTF-8");
+        assert!(text.starts_with("#pcfg_tool grammar\n#version "));
+        assert!(text.contains("#initial S\\#1\n#transforms lowercase vocab-threshold=2\n#rules\n"));
+        assert!(text.contains("#words\n\\#rules\nstabs\n"));
+
+        let (read, read_metadata) =
+            read_container(container.as_slice()).expect("This is a valid container");
+        assert_eq!(read, grammar);
+        assert_eq!(read_metadata, metadata);
+    }
+
+    #[test]
+    fn rejects_files_without_header() {
+        let error = read_container("S -> NP VP 1.0\n".as_bytes()).expect_err("There is no header");
+        assert!(error.to_string().contains("has to start with"));
+
+        let error = read_container("#pcfg_tool grammar\n#version 0.1.0\n#rules\n".as_bytes())
+            .expect_err("There is no initial");
+        assert_eq!(error.to_string(), "The container has no #initial");
+    }
+
+    #[test]
+    fn reads_written_metadata() {
+        let metadata = Metadata::new("TOP", vec![]);
+        let mut sidecar = Vec::new();
+        write_metadata(&metadata, &mut sidecar).expect("Writing to a Vec works");
+
+        assert_eq!(
+            read_metadata(sidecar.as_slice()).expect("This is a valid sidecar"),
+            metadata
+        );
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufWriter, Write},
+    ops::AddAssign,
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::{Body, OccurenceRules, Probability, Rule, WeightedRules};
+
+/// Writes the counts with one RULE COUNT line per rule, first the nonlexical and then the lexical rules,
+/// each in sorted order so that the files of similar treebanks are easy to diff
+pub fn write_counts<W: Write>(counts: &OccurenceRules, w: &mut W) -> std::io::Result<()> {
+    let mut w = BufWriter::new(w);
+
+    let mut lines: Vec<(bool, String, u64)> = counts
+        .iter()
+        .map(|(rule, count)| {
+            let lexical = matches!(rule.body, Body::Lexical(_));
+            (lexical, rule.to_string(), *count)
+        })
+        .collect();
+    lines.sort_unstable();
+
+    for (_lexical, rule, count) in lines {
+        writeln!(w, "{} {}", rule, count)?;
+    }
+
+    w.flush()
+}
+
+/// Reads rule counts with one RULE COUNT line per rule (e.g. NP -> DT NN 42 or NN dog 3), where symbols are escaped like in the grammar files.
+/// Rules that occur on several lines are summed up
+pub fn read_counts<R: BufRead>(reader: R) -> Result<OccurenceRules, Box<dyn Error>> {
+    read_rule_values(reader, |count| count.parse().ok())
+}
+
+/// Reads pseudo-counts like read_counts(), which may be fractional (e.g. NP -> NP PP 0.5) but not negative
+pub fn read_pseudo_counts<R: BufRead>(reader: R) -> Result<WeightedRules, Box<dyn Error>> {
+    read_rule_values(reader, |count| {
+        count
+            .parse::<Probability>()
+            .ok()
+            .filter(|count| count.is_finite() && *count >= 0.0)
+    })
+}
+
+fn read_rule_values<R: BufRead, T: AddAssign + Default>(
+    reader: R,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<FxHashMap<Rule, T>, Box<dyn Error>> {
+    let mut values: FxHashMap<Rule, T> = FxHashMap::default();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let malformed = || format!("Malformed count \"{}\" on line {}", line, i + 1);
+        let (rule, value) = line.trim_end().rsplit_once(' ').ok_or_else(malformed)?;
+        let rule: Rule = rule.parse().map_err(|_| malformed())?;
+        let value = parse(value).ok_or_else(malformed)?;
+
+        *values.entry(rule).or_default() += value;
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_counts() {
+        let counts = read_counts("S -> NP VP 2\nNP John 1\n\nNP John 2\n".as_bytes())
+            .expect("These are valid counts");
+
+        assert_eq!(
+            counts,
+            FxHashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "S".to_string(),
+                        body: Body::NonLexical(vec!["NP".to_string(), "VP".to_string()]),
+                    },
+                    2
+                ),
+                (
+                    Rule {
+                        head: "NP".to_string(),
+                        body: Body::Lexical("John".to_string()),
+                    },
+                    3
+                ),
+            ])
+        );
+
+        let error = read_counts("S -> NP VP 0.5\n".as_bytes()).expect_err("Counts are whole");
+        assert_eq!(
+            error.to_string(),
+            "Malformed count \"S -> NP VP 0.5\" on line 1"
+        );
+    }
+
+    #[test]
+    fn reads_written_counts() {
+        let counts =
+            read_counts("NP John 1\nS -> NP VP 2\nNP -> John\\s2 1\nNP Bob 4\n".as_bytes())
+                .expect("These are valid counts");
+
+        let mut written = Vec::new();
+        write_counts(&counts, &mut written).expect("Writing to a Vec works");
+        assert_eq!(
+            std::str::from_utf8(&written),
+            Ok("NP -> John\\s2 1\nS -> NP VP 2\nNP Bob 4\nNP John 1\n")
+        );
+        assert_eq!(
+            read_counts(written.as_slice()).expect("Written counts are valid"),
+            counts
+        );
+    }
+
+    #[test]
+    fn reads_fractional_pseudo_counts() {
+        let pseudo_counts = read_pseudo_counts("NP -> NP PP 0.5\nNN dog 2\n".as_bytes())
+            .expect("These are valid pseudo-counts");
+        let rule: Rule = "NP -> NP PP".parse().expect("This is a valid rule");
+        assert_eq!(pseudo_counts.get(&rule), Some(&0.5));
+
+        read_pseudo_counts("NN dog -1\n".as_bytes()).expect_err("Pseudo-counts aren't negative");
+    }
+}
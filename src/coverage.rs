@@ -0,0 +1,222 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    induce::PCFGGrammar,
+    unknown::{lexicon_terminal, SignatureOptions},
+    Body, Grammar, Terminal,
+};
+
+/// Decides whether a grammar derives a sentence with an Earley recognizer, which handles rules of any length
+/// and unary cycles. The probabilities are ignored, so no derivation is built
+pub struct Recognizer<'a> {
+    /// How words without a lexical rule are mapped to the signature classes of the lexicon
+    options: SignatureOptions,
+    /// The nonlexical rules as head and body, where rule 0 is the virtual START -> INITIAL
+    rules: Vec<(&'a str, Vec<&'a str>)>,
+    /// Indices of the rules of each head
+    rules_of: HashMap<&'a str, Vec<usize>>,
+    /// The preterminals of each word
+    tags: HashMap<&'a str, Vec<&'a str>>,
+}
+
+/// A rule with the number of symbols of its body recognised so far, starting at a position of the sentence
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    rule: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl<'a> Recognizer<'a> {
+    /// The options are those the grammar was induced with, see SignatureOptions::from_transforms()
+    pub fn new(grammar: &'a Grammar, options: SignatureOptions) -> Self {
+        let mut rules = vec![("", vec![grammar.initial()])];
+        let mut rules_of: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut tags: HashMap<&str, Vec<&str>> = HashMap::new();
+
+        for (rule, _probability) in grammar.nonlexical_rules() {
+            if let Body::NonLexical(body) = &rule.body {
+                rules_of.entry(&rule.head).or_default().push(rules.len());
+                rules.push((&rule.head, body.iter().map(String::as_str).collect()));
+            }
+        }
+        for (rule, _probability) in grammar.lexical_rules() {
+            if let Body::Lexical(word) = &rule.body {
+                tags.entry(word).or_default().push(&rule.head);
+            }
+        }
+
+        Recognizer {
+            options,
+            rules,
+            rules_of,
+            tags,
+        }
+    }
+
+    /// The terminal of the lexicon a word is scanned as, which is its signature class or UNKNOWN if it has no lexical rule
+    fn terminal(&self, word: &str) -> Option<Terminal> {
+        lexicon_terminal(
+            word,
+            |terminal| self.tags.contains_key(terminal),
+            &self.options,
+        )
+    }
+
+    /// The words that neither have a lexical rule nor a signature class in the lexicon, in the order of the sentence
+    pub fn unknown_words<'w>(&self, words: &[&'w str]) -> Vec<&'w str> {
+        words
+            .iter()
+            .filter(|word| self.terminal(word).is_none())
+            .copied()
+            .collect()
+    }
+
+    /// Whether the initial derives the words. Empty sentences are never derived
+    pub fn recognizes(&self, words: &[&str]) -> bool {
+        let terminals: Option<Vec<Terminal>> =
+            words.iter().map(|word| self.terminal(word)).collect();
+        let terminals = match terminals {
+            Some(terminals) if !terminals.is_empty() => terminals,
+            _ => return false,
+        };
+
+        let start = Item {
+            rule: 0,
+            dot: 0,
+            origin: 0,
+        };
+        let mut chart: Vec<Vec<Item>> = vec![Vec::new(); terminals.len() + 1];
+        let mut seen: Vec<HashSet<Item>> = vec![HashSet::new(); terminals.len() + 1];
+        chart[0].push(start);
+        seen[0].insert(start);
+
+        for position in 0..=terminals.len() {
+            // Items are appended while the position is processed
+            let mut i = 0;
+            while i < chart[position].len() {
+                let item = chart[position][i];
+                i += 1;
+
+                let (head, body) = &self.rules[item.rule];
+                let mut added: Vec<(usize, Item)> = Vec::new();
+                match body.get(item.dot) {
+                    Some(next) => {
+                        // Predict the rules of the next symbol
+                        for rule in self.rules_of.get(next).into_iter().flatten() {
+                            let predicted = Item {
+                                rule: *rule,
+                                dot: 0,
+                                origin: position,
+                            };
+                            added.push((position, predicted));
+                        }
+
+                        // Scan the word if the next symbol is one of its preterminals
+                        let tagged = terminals
+                            .get(position)
+                            .and_then(|terminal| self.tags.get(terminal.as_str()))
+                            .is_some_and(|tags| tags.contains(next));
+                        if tagged {
+                            let scanned = Item {
+                                dot: item.dot + 1,
+                                ..item
+                            };
+                            added.push((position + 1, scanned));
+                        }
+                    }
+                    None => {
+                        // Complete the items that waited for the head, which can't start here since bodies aren't empty
+                        for waiting in chart[item.origin].iter() {
+                            if self.rules[waiting.rule].1.get(waiting.dot) == Some(head) {
+                                let completed = Item {
+                                    dot: waiting.dot + 1,
+                                    ..*waiting
+                                };
+                                added.push((position, completed));
+                            }
+                        }
+                    }
+                }
+
+                for (position, item) in added {
+                    if seen[position].insert(item) {
+                        chart[position].push(item);
+                    }
+                }
+            }
+        }
+
+        seen[terminals.len()].contains(&Item { dot: 1, ..start })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn recognizes_derivable_sentences() {
+        let trees = [
+            "(ROOT (S (NP (NNP John)) (VP (VBZ sees) (NP (NNP Mary)))))",
+            "(ROOT (S (NP (NP (NNP Mary)) (CC and) (NP (NNP John))) (VP (VBZ sleeps))))",
+        ]
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+        let grammar = Grammar::from_parse_trees("ROOT".to_string(), trees)
+            .expect("The trees have the initial as root");
+        let recognizer = Recognizer::new(&grammar, SignatureOptions::default());
+
+        assert!(recognizer.recognizes(&["John", "sleeps"]));
+        assert!(recognizer.recognizes(&["Mary", "and", "John", "and", "Mary", "sees", "John"]));
+        assert!(!recognizer.recognizes(&["sleeps", "John"]));
+        assert!(!recognizer.recognizes(&["John", "Mary"]));
+        assert!(!recognizer.recognizes(&[]));
+
+        assert!(!recognizer.recognizes(&["Bob", "sleeps", "well"]));
+        assert_eq!(
+            recognizer.unknown_words(&["Bob", "sleeps", "well"]),
+            vec!["Bob", "well"]
+        );
+    }
+
+    #[test]
+    fn recognizes_with_unary_cycles() {
+        let tree = PTBParser::parse("(ROOT (A (B (A (C c)))))").expect("This should be parsable");
+        let grammar = Grammar::from_parse_trees("ROOT".to_string(), vec![tree])
+            .expect("The tree has the initial as root");
+
+        assert!(Recognizer::new(&grammar, SignatureOptions::default()).recognizes(&["c"]));
+    }
+
+    #[test]
+    fn recognizes_unknown_words_by_signature() {
+        let trees = [
+            "(ROOT (S (NP (NNP UNK-INITC)) (VP (VBZ sleeps))))",
+            "(ROOT (S (NP (NNP John)) (VP (VBZ UNK-LC-s))))",
+            "(ROOT (S (NP (NNP John)) (VP (VBZ UNK))))",
+        ]
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+        let grammar = Grammar::from_parse_trees("ROOT".to_string(), trees)
+            .expect("The trees have the initial as root");
+        let recognizer = Recognizer::new(&grammar, SignatureOptions::default());
+
+        assert!(recognizer.recognizes(&["Bob", "sleeps"]));
+        assert!(recognizer.recognizes(&["John", "walks"]));
+        // UNK-NUM isn't part of the lexicon, so 1990 falls back to UNK
+        assert!(recognizer.recognizes(&["John", "1990"]));
+        assert!(!recognizer.recognizes(&["Bob", "Mary"]));
+        assert!(recognizer
+            .unknown_words(&["Bob", "walks", "1990"])
+            .is_empty());
+
+        // Without capitalisation Bob only maps to UNK, which isn't a noun
+        let options = SignatureOptions {
+            capitalisation: false,
+            ..SignatureOptions::default()
+        };
+        let recognizer = Recognizer::new(&grammar, options);
+        assert!(!recognizer.recognizes(&["Bob", "sleeps"]));
+        assert!(recognizer.recognizes(&["John", "walks"]));
+    }
+}
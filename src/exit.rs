@@ -0,0 +1,23 @@
+/// Exit codes of pcfg_tool, which let scripts tell failures apart without scraping the stderr.
+/// Panics (e.g. when the stdout is closed) exit with Rust's 101
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitCode {
+    Success = 0,
+    /// Invalid options, config files or queries
+    Usage = 1,
+    /// Malformed trees in a treebank or corpus
+    MalformedTrees = 2,
+    /// Missing or malformed grammar files
+    UnreadableGrammar = 3,
+    /// Other files that can't be read or written, e.g. missing treebanks
+    Io = 4,
+    /// A queried rule or head isn't part of the grammar
+    NotFound = 5,
+    /// Reserved for subcommands that are accepted but not implemented yet
+    NotImplemented = 22,
+}
+
+/// Exits the process with the code
+pub fn exit(code: ExitCode) -> ! {
+    std::process::exit(code as i32)
+}
@@ -0,0 +1,92 @@
+use crate::ptb::{Descendants, ParseTree};
+
+/// Criteria that trees must meet to be selected, where unset criteria match every tree
+#[derive(Debug, Default, Clone)]
+pub struct TreeFilter {
+    /// Label of the root, or of its only subtree for treebanks with an extra root like (ROOT (S ...))
+    pub root: Option<String>,
+    /// Maximum depth() of the tree
+    pub max_depth: Option<usize>,
+    /// Minimum number of words
+    pub min_length: Option<usize>,
+    /// Maximum number of words
+    pub max_length: Option<usize>,
+    /// Labels that must occur somewhere in the tree
+    pub with: Vec<String>,
+    /// Labels that must not occur anywhere in the tree
+    pub without: Vec<String>,
+}
+
+impl TreeFilter {
+    pub fn matches(&self, tree: &ParseTree<String>) -> bool {
+        if let Some(root) = &self.root {
+            let below_root = match &tree.descendants {
+                Descendants::Expressions(trees) if trees.len() == 1 => Some(&trees[0].root),
+                _ => None,
+            };
+
+            if tree.root != *root && below_root != Some(root) {
+                return false;
+            }
+        }
+
+        if self
+            .max_depth
+            .is_some_and(|max_depth| tree.depth() > max_depth)
+        {
+            return false;
+        }
+
+        let length = tree.leaves().len();
+        if self
+            .min_length
+            .is_some_and(|min_length| length < min_length)
+            || self
+                .max_length
+                .is_some_and(|max_length| length > max_length)
+        {
+            return false;
+        }
+
+        let has_label = |label: &String| tree.iter_preorder().any(|node| node.root == *label);
+        self.with.iter().all(has_label) && !self.without.iter().any(has_label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn selects_matching_trees() {
+        let tree = PTBParser::parse("(ROOT (S (NP (NNP Pierre)) (VP (VBD slept) (. .))))")
+            .expect("This should be parsable");
+        let fragment =
+            PTBParser::parse("(ROOT (NP (NNP Pierre)))").expect("This should be parsable");
+
+        let filter = TreeFilter {
+            root: Some(String::from("S")),
+            ..Default::default()
+        };
+        assert!(filter.matches(&tree));
+        assert!(!filter.matches(&fragment));
+
+        let filter = TreeFilter {
+            min_length: Some(2),
+            max_length: Some(3),
+            max_depth: Some(4),
+            ..Default::default()
+        };
+        assert!(filter.matches(&tree));
+        assert!(!filter.matches(&fragment));
+
+        let filter = TreeFilter {
+            with: vec![String::from("NP")],
+            without: vec![String::from("VP")],
+            ..Default::default()
+        };
+        assert!(!filter.matches(&tree));
+        assert!(filter.matches(&fragment));
+    }
+}
@@ -0,0 +1,252 @@
+use std::io::{BufRead, Error, ErrorKind, Write};
+
+use crate::{
+    berkeley::BerkeleyReader,
+    compression::open,
+    container::{read_metadata, ContainerReader, Metadata, MAGIC},
+    Grammar,
+};
+
+/// A way of writing grammars, either split into files with different extensions (e.g. GRAMMAR.rules and GRAMMAR.lexicon)
+/// or as a single stream, e.g. to the stdout
+pub trait GrammarFormatWriter {
+    /// Extensions of the files a named grammar is written into, in the order of the stream
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Writes the part of the grammar that belongs into the file with the extension
+    fn write_part(&self, extension: &str, w: &mut dyn Write) -> std::io::Result<()>;
+
+    /// Writes all parts after each other
+    fn write_stream(&self, w: &mut dyn Write) -> std::io::Result<()> {
+        for extension in self.extensions() {
+            self.write_part(extension, w)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A way of reading grammars from the files with different extensions that a GrammarFormatWriter writes
+pub trait GrammarFormatReader {
+    /// Extensions of the files the grammar is read from, e.g. rules and lexicon but not words
+    fn extensions(&self) -> &'static [&'static str];
+
+    /// Reads the grammar from the parts, which are given in the order of extensions().
+    /// The initial is used if the format doesn't record one and overrides the recorded one otherwise
+    fn read_parts(
+        &self,
+        parts: Vec<Box<dyn BufRead>>,
+        initial: Option<&str>,
+    ) -> Result<Grammar, Box<dyn std::error::Error>>;
+}
+
+/// Reads the grammar from NAME.EXTENSION for all extensions of the format, which may be gzipped.
+/// This is the entry point for all commands that read grammars
+pub fn read_grammar(
+    reader: &dyn GrammarFormatReader,
+    name: &str,
+    initial: Option<&str>,
+) -> Result<Grammar, Box<dyn std::error::Error>> {
+    let parts = reader
+        .extensions()
+        .iter()
+        .map(|extension| {
+            let path = format!("{}.{}", name, extension);
+            open(&path).map_err(|e| format!("Can't open {}: {}", path, e))
+        })
+        .collect::<Result<_, _>>()?;
+
+    reader.read_parts(parts, initial)
+}
+
+/// Where a grammar given by the user is stored
+#[derive(Debug, PartialEq)]
+pub enum GrammarSource<'a> {
+    /// Name of the Berkeley files, i.e. NAME.rules etc.
+    Berkeley(&'a str),
+    /// Path of a container
+    Container(String),
+}
+
+impl<'a> GrammarSource<'a> {
+    /// Recognises the files of a grammar by their extension, which may be followed by .gz,
+    /// e.g. wsj.rules and wsj.lexicon.gz are the Berkeley files named wsj and wsj.pcfg is a container
+    pub fn from_extension(path: &'a str) -> Option<Self> {
+        let without_gz = path.strip_suffix(".gz").unwrap_or(path);
+        if without_gz.ends_with(".pcfg") {
+            return Some(GrammarSource::Container(path.to_string()));
+        }
+
+        ["rules", "lexicon", "words", "meta"]
+            .iter()
+            .find_map(|extension| without_gz.strip_suffix(&format!(".{}", extension)))
+            .map(GrammarSource::Berkeley)
+    }
+
+    /// Like from_extension(), but paths without a known extension are a container
+    /// if the file (or PATH.pcfg) starts with its header and the name of the Berkeley files otherwise
+    pub fn detect(path: &'a str) -> Self {
+        if let Some(source) = Self::from_extension(path) {
+            return source;
+        }
+
+        let starts_with_magic = |path: &str| match open(path) {
+            Ok(reader) => reader
+                .lines()
+                .next()
+                .and_then(Result::ok)
+                .is_some_and(|line| line.trim_end() == MAGIC),
+            Err(_) => false,
+        };
+        let container = format!("{}.pcfg", path);
+        if starts_with_magic(path) {
+            GrammarSource::Container(path.to_string())
+        } else if open(&format!("{}.rules", path)).is_err() && starts_with_magic(&container) {
+            GrammarSource::Container(container)
+        } else {
+            GrammarSource::Berkeley(path)
+        }
+    }
+}
+
+impl Grammar {
+    /// Reads the grammar in the format detected by GrammarSource::detect(). The initial overrides the recorded one,
+    /// which is ROOT for Berkeley files without a NAME.meta sidecar
+    pub fn load(path: &str, initial: Option<&str>) -> Result<Grammar, Box<dyn std::error::Error>> {
+        match GrammarSource::detect(path) {
+            GrammarSource::Berkeley(name) => {
+                let initial = match initial {
+                    Some(initial) => initial.to_string(),
+                    None => recorded_metadata(name)?
+                        .map(|metadata| metadata.initial)
+                        .unwrap_or(String::from("ROOT")),
+                };
+                read_grammar(&BerkeleyReader, name, Some(&initial))
+            }
+            GrammarSource::Container(path) => {
+                let container = open(&path).map_err(|e| format!("Can't open {}: {}", path, e))?;
+                ContainerReader.read_parts(vec![container], initial)
+            }
+        }
+    }
+
+    /// Reads the metadata recorded with the grammar at the path of load(), i.e. the header of a container
+    /// or the NAME.meta sidecar of Berkeley files, which may be missing
+    pub fn load_metadata(path: &str) -> Result<Option<Metadata>, Box<dyn std::error::Error>> {
+        match GrammarSource::detect(path) {
+            GrammarSource::Berkeley(name) => recorded_metadata(name),
+            GrammarSource::Container(path) => {
+                let container = open(&path).map_err(|e| format!("Can't open {}: {}", path, e))?;
+                let metadata =
+                    read_metadata(container).map_err(|e| format!("Malformed {}: {}", path, e))?;
+                Ok(Some(metadata))
+            }
+        }
+    }
+}
+
+/// Reads NAME.meta if there is one
+fn recorded_metadata(name: &str) -> Result<Option<Metadata>, Box<dyn std::error::Error>> {
+    let path = format!("{}.meta", name);
+    let reader = match open(&path) {
+        Ok(reader) => reader,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Can't open {}: {}", path, e).into()),
+    };
+
+    let metadata = read_metadata(reader).map_err(|e| format!("Malformed {}: {}", path, e))?;
+    Ok(Some(metadata))
+}
+
+/// Error for a part that the format doesn't have
+pub fn unknown_part(extension: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!("The format has no .{} files", extension),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        berkeley::{BerkeleyReader, BerkeleyWriter},
+        container::{ContainerReader, ContainerWriter, Metadata},
+        induce::PCFGGrammar,
+        ptb::PTBParser,
+    };
+
+    /// Writes the parts into memory and reads them back
+    fn round_trip(
+        writer: &dyn GrammarFormatWriter,
+        reader: &dyn GrammarFormatReader,
+        initial: Option<&str>,
+    ) -> Grammar {
+        let parts = reader
+            .extensions()
+            .iter()
+            .map(|extension| {
+                let mut part = Vec::new();
+                writer
+                    .write_part(extension, &mut part)
+                    .expect("Writing to a Vec works");
+                Box::new(std::io::Cursor::new(part)) as Box<dyn BufRead>
+            })
+            .collect();
+
+        reader
+            .read_parts(parts, initial)
+            .expect("The written parts are readable")
+    }
+
+    #[test]
+    fn detects_format_by_extension() {
+        assert_eq!(
+            GrammarSource::from_extension("wsj.rules"),
+            Some(GrammarSource::Berkeley("wsj"))
+        );
+        assert_eq!(
+            GrammarSource::from_extension("out/wsj.lexicon.gz"),
+            Some(GrammarSource::Berkeley("out/wsj"))
+        );
+        assert_eq!(
+            GrammarSource::from_extension("wsj.pcfg.gz"),
+            Some(GrammarSource::Container(String::from("wsj.pcfg.gz")))
+        );
+        assert_eq!(GrammarSource::from_extension("wsj"), None);
+        assert_eq!(GrammarSource::from_extension("wsj.gz"), None);
+    }
+
+    #[test]
+    fn reads_what_the_writers_write() {
+        let tree = PTBParser::parse("(ROOT (S (NP (NNP John)) (VP (VBZ sleeps))))")
+            .expect("This should be parsable");
+        let grammar = Grammar::from_parse_trees("ROOT".to_string(), vec![tree])
+            .expect("The tree has the initial as root");
+
+        let berkeley = round_trip(
+            &BerkeleyWriter::from_grammar(&grammar),
+            &BerkeleyReader,
+            None,
+        );
+        assert_eq!(berkeley, grammar);
+
+        let metadata = Metadata::new("ROOT", Vec::new());
+        let container_writer = ContainerWriter {
+            writer: BerkeleyWriter::from_grammar(&grammar),
+            metadata: &metadata,
+        };
+        assert_eq!(
+            round_trip(&container_writer, &ContainerReader, None),
+            grammar
+        );
+        let overridden = round_trip(&container_writer, &ContainerReader, Some("S"));
+        assert_eq!(overridden.initial(), "S");
+
+        let mut part = Vec::new();
+        let error = container_writer
+            .write_part("rules", &mut part)
+            .expect_err("A container has no rules file");
+        assert_eq!(error.to_string(), "The format has no .rules files");
+    }
+}
@@ -1,36 +1,123 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashSet;
+
+use rustc_hash::FxHashMap;
+use tracing::{debug, info_span, warn};
 
 use crate::{
+    berkeley::Fraction,
     ptb::{Descendants, ParseTree},
-    Body, Grammar, Nonterminal, OccurenceRules, Probability, ProbabilityRules, Rule, Terminal,
+    smoothing::Smoothing,
+    Body, ExactRules, Grammar, Nonterminal, Occurence, OccurenceRules, Probability,
+    ProbabilityRules, Rule, Terminal, WeightedRules,
 };
 
 pub trait PCFGGrammar {
     /// Given an initial and parse trees it reuturns a normalised grammar
     fn from_parse_trees(
         initial: Nonterminal,
-        parse_trees: Vec<ParseTree<String>>,
+        parse_trees: impl IntoIterator<Item = ParseTree<String>>,
+    ) -> Result<Self, Box<dyn std::error::Error>>
+    where
+        Self: Sized;
+
+    /// Like from_parse_trees(), but smoothes the rule occurences before normalising
+    fn from_parse_trees_smoothed(
+        initial: Nonterminal,
+        parse_trees: impl IntoIterator<Item = ParseTree<String>>,
+        smoothing: &dyn Smoothing,
     ) -> Result<Self, Box<dyn std::error::Error>>
     where
         Self: Sized;
 
+    /// Normalises rules with (possibly fractional) weights, e.g. from weigh_rules()
+    fn from_weighted_rules(initial: Nonterminal, weighted_rules: WeightedRules) -> Self
+    where
+        Self: Sized;
+
+    /// Takes the probabilities of exactly normalised rules, see normalise_exactly()
+    fn from_exact_rules(initial: Nonterminal, exact_rules: &ExactRules) -> Self
+    where
+        Self: Sized;
+
+    /// Normalises already counted rules, which are smoothed before if a smoothing is given
+    fn from_occurence_rules(
+        initial: Nonterminal,
+        occurence_rules: OccurenceRules,
+        smoothing: Option<&dyn Smoothing>,
+    ) -> Self
+    where
+        Self: Sized;
+
+    /// Rescales the probabilities of every head's rules so that they sum up to one, e.g. after the grammar files were edited
     fn normalise(&mut self);
 
-    fn nonlexical_rules(&self) -> ProbabilityRules;
+    /// Normalises the grammar, rounds the probabilities to the given number of significant digits and renormalises them
+    /// by giving the rounding error of every head to its most probable rule, so that the other rules keep the rounded digits
+    fn quantize(&mut self, significant_digits: u32);
 
-    fn lexical_rules(&self) -> ProbabilityRules;
+    /// Maps every nonterminal with projection (e.g. NP^S -> NP), sums up the probabilities of rules that become equal and renormalises them
+    fn project(&self, projection: fn(&str) -> String) -> Self
+    where
+        Self: Sized;
+
+    /// Iterates over all rules whose body consists of nonterminals
+    fn nonlexical_rules(&self) -> impl Iterator<Item = (&Rule, Probability)>;
+
+    /// Iterates over all rules whose body is a terminal
+    fn lexical_rules(&self) -> impl Iterator<Item = (&Rule, Probability)>;
 
     fn nonterminals(&self) -> Vec<Nonterminal>;
 
     fn terminals(&self) -> Vec<Terminal>;
+
+    /// The probability of the rule, or None if it isn't part of the grammar
+    fn probability(&self, rule: &Rule) -> Option<Probability>;
+
+    /// All rules of the head from the most to the least probable one
+    fn rules_of(&self, head: &str) -> Vec<(&Rule, Probability)>;
+
+    /// The groups of nonterminals that can derive each other via unary rules (e.g. NP -> S and S -> NP, or NP -> NP),
+    /// i.e. the cycles a parser has to stop applying unaries in. Each group and the list are sorted
+    fn unary_cycles(&self) -> Vec<Vec<Nonterminal>>;
+
+    /// Keeps the k most probable nonlexical rules of every head and renormalises them.
+    /// Lexical rules are all kept, since dropping them would leave words without a preterminal
+    fn top_k(&self, k: usize) -> Self
+    where
+        Self: Sized;
+}
+
+/// Rounds the probability to the given number of significant digits
+fn round_significant(probability: Probability, significant_digits: u32) -> Probability {
+    // Formatting rounds correctly, and the parsed value is the shortest one printing these digits
+    format!(
+        "{:.*e}",
+        significant_digits.saturating_sub(1) as usize,
+        probability
+    )
+    .parse()
+    .expect("A formatted float can be parsed")
 }
 
 trait PTBRuleInducer {
+    /// Reads off the rules of all parse trees and counts their occurence, where each tree counts as often as its weight
+    fn count_parse_trees(
+        initial: &str,
+        parse_trees: impl IntoIterator<Item = (ParseTree<String>, Occurence)>,
+    ) -> Result<OccurenceRules, Box<dyn std::error::Error>>;
+
     /// Normalises a given ruleset with occurences into a ruleset with probabilites
     fn normalise_rules(occurence_rules: OccurenceRules) -> ProbabilityRules;
 
-    /// Accumulates rules into occurence_rules thereby counting their occurence
-    fn count_rule_occurence(occurence_rules: &mut OccurenceRules, rules: Vec<Rule>);
+    /// Normalises a given ruleset with (possibly fractional) weights into a ruleset with probabilites
+    fn normalise_weighted_rules(weighted_rules: WeightedRules) -> ProbabilityRules;
+
+    /// Accumulates rules into occurence_rules thereby counting their occurence weight times
+    fn count_rule_occurence(
+        occurence_rules: &mut OccurenceRules,
+        rules: Vec<Rule>,
+        weight: Occurence,
+    );
 
     /// Traverses the parse tree breadth-first until we have read all rules starting at the subtree given by initial_subtree()
     fn read_rules(
@@ -43,47 +130,131 @@ trait PTBRuleInducer {
 impl PCFGGrammar for Grammar {
     fn from_parse_trees(
         initial: Nonterminal,
-        parse_trees: Vec<ParseTree<String>>,
+        parse_trees: impl IntoIterator<Item = ParseTree<String>>,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let mut occurence_rules: OccurenceRules = HashMap::new();
+        let occurence_rules = count_rules(&initial, parse_trees)?;
 
-        for parse_tree in parse_trees {
-            let tree_rules = Self::read_rules(
-                &initial,
-                parse_tree,
-                // Assume starting at the first tree root for now
-                |_initial: &str, parse_tree: ParseTree<String>| -> Option<ParseTree<String>> {
-                    Some(parse_tree)
-                },
-            );
+        Ok(Self::from_occurence_rules(initial, occurence_rules, None))
+    }
 
-            match tree_rules {
-                Some(rules) => {
-                    Self::count_rule_occurence(&mut occurence_rules, rules);
-                }
-                None => {
-                    return Err(("There are no rules to read").into());
-                }
+    fn from_parse_trees_smoothed(
+        initial: Nonterminal,
+        parse_trees: impl IntoIterator<Item = ParseTree<String>>,
+        smoothing: &dyn Smoothing,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let occurence_rules = count_rules(&initial, parse_trees)?;
+
+        Ok(Self::from_occurence_rules(
+            initial,
+            occurence_rules,
+            Some(smoothing),
+        ))
+    }
+
+    fn from_occurence_rules(
+        initial: Nonterminal,
+        occurence_rules: OccurenceRules,
+        smoothing: Option<&dyn Smoothing>,
+    ) -> Self {
+        let probability_rules = match smoothing {
+            Some(smoothing) => {
+                let weighted_rules =
+                    info_span!("smoothing").in_scope(|| smoothing.smooth(occurence_rules));
+                info_span!("normalising")
+                    .in_scope(|| Self::normalise_weighted_rules(weighted_rules))
             }
+            None => info_span!("normalising").in_scope(|| Self::normalise_rules(occurence_rules)),
+        };
+
+        Grammar {
+            initial,
+            rules: probability_rules,
         }
+    }
 
-        let probability_rules = Self::normalise_rules(occurence_rules);
+    fn from_weighted_rules(initial: Nonterminal, weighted_rules: WeightedRules) -> Self {
+        Grammar {
+            initial,
+            rules: info_span!("normalising")
+                .in_scope(|| Self::normalise_weighted_rules(weighted_rules)),
+        }
+    }
 
-        Ok(Grammar {
+    fn from_exact_rules(initial: Nonterminal, exact_rules: &ExactRules) -> Self {
+        Grammar {
             initial,
-            rules: probability_rules,
-        })
+            rules: exact_rules
+                .iter()
+                .map(|(rule, fraction)| (rule.clone(), fraction.value()))
+                .collect(),
+        }
     }
 
     fn normalise(&mut self) {
-        let mut occurence_rules = HashMap::new();
+        self.rules = Self::normalise_weighted_rules(std::mem::take(&mut self.rules));
+    }
 
-        Self::count_rule_occurence(
-            &mut occurence_rules,
-            self.rules.clone().into_keys().collect(),
-        );
+    fn quantize(&mut self, significant_digits: u32) {
+        self.normalise();
+
+        for probability in self.rules.values_mut() {
+            *probability = round_significant(*probability, significant_digits);
+        }
+
+        // Ties are broken by the printed rule, so that the same rule takes the error on every run
+        let mut sums: FxHashMap<&str, Probability> = FxHashMap::default();
+        let mut most_probable: FxHashMap<&str, (&Rule, Probability)> = FxHashMap::default();
+        for (rule, probability) in self.rules.iter() {
+            *sums.entry(&rule.head).or_insert(0.0) += probability;
+
+            let (current, current_probability) = most_probable
+                .entry(&rule.head)
+                .or_insert((rule, *probability));
+            let more_probable = match probability.total_cmp(current_probability) {
+                std::cmp::Ordering::Equal => rule.to_string() < current.to_string(),
+                ordering => ordering.is_gt(),
+            };
+            if more_probable {
+                (*current, *current_probability) = (rule, *probability);
+            }
+        }
+
+        let renormalised: Vec<(Rule, Probability)> = most_probable
+            .into_iter()
+            .map(|(head, (rule, probability))| {
+                let remainder = 1.0 - (sums[head] - probability);
+                // Only strips the noise of the subtraction (e.g. 0.33399999999999996 becomes 0.334)
+                let remainder = round_significant(remainder, significant_digits.max(15));
+                (rule.clone(), remainder)
+            })
+            .collect();
+        self.rules.extend(renormalised);
+    }
+
+    fn project(&self, projection: fn(&str) -> String) -> Self {
+        let mut weighted_rules: WeightedRules = FxHashMap::default();
+
+        for (rule, probability) in self.rules.iter() {
+            let projected_rule = Rule {
+                head: projection(&rule.head),
+                body: match &rule.body {
+                    Body::Lexical(terminal) => Body::Lexical(terminal.clone()),
+                    Body::NonLexical(nonterminals) => Body::NonLexical(
+                        nonterminals
+                            .iter()
+                            .map(|nonterminal| projection(nonterminal))
+                            .collect(),
+                    ),
+                },
+            };
+
+            *weighted_rules.entry(projected_rule).or_insert(0.0) += probability;
+        }
 
-        self.rules = Self::normalise_rules(occurence_rules);
+        Grammar {
+            initial: projection(&self.initial),
+            rules: Self::normalise_weighted_rules(weighted_rules),
+        }
     }
 
     fn nonterminals(&self) -> Vec<Nonterminal> {
@@ -123,68 +294,249 @@ impl PCFGGrammar for Grammar {
         terminals.into_iter().collect()
     }
 
-    fn nonlexical_rules(&self) -> ProbabilityRules {
-        let mut rules = self.rules.clone();
+    fn nonlexical_rules(&self) -> impl Iterator<Item = (&Rule, Probability)> {
+        self.rules
+            .iter()
+            .filter(|(rule, _probability)| !rule.is_lexical_rule())
+            .map(|(rule, probability)| (rule, *probability))
+    }
 
-        for (rule, _probability) in self.rules.iter() {
-            if rule.is_lexical_rule() {
-                rules.remove(rule);
+    fn lexical_rules(&self) -> impl Iterator<Item = (&Rule, Probability)> {
+        self.rules
+            .iter()
+            .filter(|(rule, _probability)| rule.is_lexical_rule())
+            .map(|(rule, probability)| (rule, *probability))
+    }
+
+    fn probability(&self, rule: &Rule) -> Option<Probability> {
+        self.rules.get(rule).copied()
+    }
+
+    fn rules_of(&self, head: &str) -> Vec<(&Rule, Probability)> {
+        let mut rules: Vec<(&Rule, Probability)> = self
+            .rules
+            .iter()
+            .filter(|(rule, _probability)| rule.head == head)
+            .map(|(rule, probability)| (rule, *probability))
+            .collect();
+        // Equally probable rules are ordered by their text to keep the output stable
+        rules.sort_by(|(rule, probability), (other_rule, other_probability)| {
+            other_probability
+                .total_cmp(probability)
+                .then_with(|| rule.to_string().cmp(&other_rule.to_string()))
+        });
+
+        rules
+    }
+
+    fn unary_cycles(&self) -> Vec<Vec<Nonterminal>> {
+        let mut unaries: FxHashMap<&str, Vec<&str>> = FxHashMap::default();
+        for rule in self.rules.keys() {
+            match rule.body {
+                Body::NonLexical(ref body) if body.len() == 1 => {
+                    unaries.entry(&rule.head).or_default().push(&body[0])
+                }
+                _ => {}
             }
         }
 
-        rules
+        let mut components = UnaryComponents {
+            unaries: &unaries,
+            indices: FxHashMap::default(),
+            stack: Vec::new(),
+            cycles: Vec::new(),
+        };
+        let mut heads: Vec<&str> = unaries.keys().copied().collect();
+        heads.sort();
+        for head in heads {
+            if !components.indices.contains_key(head) {
+                components.visit(head);
+            }
+        }
+
+        let mut cycles = components.cycles;
+        cycles.sort();
+        cycles
     }
 
-    fn lexical_rules(&self) -> ProbabilityRules {
-        let mut rules = self.rules.clone();
+    fn top_k(&self, k: usize) -> Self {
+        let mut heads: FxHashMap<&Nonterminal, Vec<(&Rule, Probability)>> = FxHashMap::default();
+        let mut weighted_rules: WeightedRules = FxHashMap::default();
 
-        for (rule, _probability) in self.rules.iter() {
-            if !rule.is_lexical_rule() {
-                rules.remove(rule);
+        for (rule, probability) in self.rules.iter() {
+            match rule.body {
+                Body::Lexical(_) => {
+                    weighted_rules.insert(rule.clone(), *probability);
+                }
+                Body::NonLexical(_) => heads
+                    .entry(&rule.head)
+                    .or_default()
+                    .push((rule, *probability)),
             }
         }
 
-        rules
+        for mut rules in heads.into_values() {
+            // Like in rules_of(), ties are broken by the text of the rules to keep the result stable
+            rules.sort_by(|(rule, probability), (other_rule, other_probability)| {
+                other_probability
+                    .total_cmp(probability)
+                    .then_with(|| rule.to_string().cmp(&other_rule.to_string()))
+            });
+            weighted_rules.extend(
+                rules
+                    .into_iter()
+                    .take(k)
+                    .map(|(rule, probability)| (rule.clone(), probability)),
+            );
+        }
+
+        Grammar {
+            initial: self.initial.clone(),
+            rules: Self::normalise_weighted_rules(weighted_rules),
+        }
+    }
+}
+
+/// Finds the strongly connected components of the unary rules (Tarjan's algorithm)
+struct UnaryComponents<'a> {
+    unaries: &'a FxHashMap<&'a str, Vec<&'a str>>,
+    /// Visiting order and lowest reachable index of every visited nonterminal, where None marks finished components
+    indices: FxHashMap<&'a str, (usize, Option<usize>)>,
+    stack: Vec<&'a str>,
+    cycles: Vec<Vec<Nonterminal>>,
+}
+
+impl<'a> UnaryComponents<'a> {
+    /// Returns the lowest index reachable from the nonterminal
+    fn visit(&mut self, nonterminal: &'a str) -> usize {
+        let index = self.indices.len();
+        self.indices.insert(nonterminal, (index, Some(index)));
+        self.stack.push(nonterminal);
+
+        let mut low = index;
+        let mut self_loop = false;
+        for &child in self.unaries.get(nonterminal).into_iter().flatten() {
+            self_loop |= child == nonterminal;
+            low = match self.indices.get(child) {
+                None => low.min(self.visit(child)),
+                Some((_, Some(child_low))) => low.min(*child_low),
+                // Finished components can't reach back
+                Some((_, None)) => low,
+            };
+        }
+        self.indices.insert(nonterminal, (index, Some(low)));
+
+        if low == index {
+            let start = self
+                .stack
+                .iter()
+                .rposition(|other| *other == nonterminal)
+                .expect("The nonterminal is on the stack");
+            let mut component: Vec<Nonterminal> = self
+                .stack
+                .drain(start..)
+                .map(|member| {
+                    self.indices
+                        .entry(member)
+                        .and_modify(|entry| entry.1 = None);
+                    member.to_string()
+                })
+                .collect();
+
+            if component.len() > 1 || self_loop {
+                component.sort();
+                self.cycles.push(component);
+            }
+        }
+
+        low
     }
 }
 
 impl PTBRuleInducer for Grammar {
+    fn count_parse_trees(
+        initial: &str,
+        parse_trees: impl IntoIterator<Item = (ParseTree<String>, Occurence)>,
+    ) -> Result<OccurenceRules, Box<dyn std::error::Error>> {
+        let mut occurence_rules: OccurenceRules = FxHashMap::default();
+
+        for (parse_tree, weight) in parse_trees {
+            let tree_rules = Self::read_rules(
+                initial,
+                parse_tree,
+                // Assume starting at the first tree root for now
+                |_initial: &str, parse_tree: ParseTree<String>| -> Option<ParseTree<String>> {
+                    Some(parse_tree)
+                },
+            );
+
+            match tree_rules {
+                Some(rules) => {
+                    Self::count_rule_occurence(&mut occurence_rules, rules, weight);
+                }
+                None => {
+                    return Err(("There are no rules to read").into());
+                }
+            }
+        }
+
+        debug!("Counted {} distinct rules", occurence_rules.len());
+        Ok(occurence_rules)
+    }
+
     fn normalise_rules(occurence_rules: OccurenceRules) -> ProbabilityRules {
+        Self::normalise_weighted_rules(
+            occurence_rules
+                .into_iter()
+                .map(|(rule, occurence)| (rule, occurence as Probability))
+                .collect(),
+        )
+    }
+
+    fn normalise_weighted_rules(weighted_rules: WeightedRules) -> ProbabilityRules {
         // Sort rules for their head (e.g. NP -> DT NN has head NP)
         let sorted_rules =
-            occurence_rules
+            weighted_rules
                 .into_iter()
-                .fold(HashMap::new(), |mut acc, (rule, occurence)| {
-                    let entry = acc
-                        .entry(rule.head.to_string())
-                        .or_insert(Vec::<(Rule, u32)>::new());
-                    entry.push((rule, occurence));
+                .fold(FxHashMap::default(), |mut acc, (rule, weight)| {
+                    let entry =
+                        acc.entry(rule.head.to_string())
+                            .or_insert(Vec::<(Rule, Probability)>::new());
+                    entry.push((rule, weight));
                     acc
                 });
 
         // Calculate probabilites on the sorted rules
-        // rule_probability = rule_occurence / sum(rule_occurence_with_same_head)
+        // rule_probability = rule_weight / sum(rule_weight_with_same_head)
         sorted_rules
             .into_iter()
-            .fold(HashMap::new(), |mut acc, (_head, occurence_rules)| {
-                let total_head_occurences: Probability = occurence_rules
-                    .iter()
-                    .map(|(_head, occurence)| *occurence as f64)
-                    .sum();
-
-                occurence_rules.into_iter().for_each(|(rule, occurence)| {
-                    acc.insert(
-                        rule,
-                        occurence as Probability / total_head_occurences as Probability,
+            .fold(FxHashMap::default(), |mut acc, (head, weighted_rules)| {
+                let total_head_weight: Probability =
+                    weighted_rules.iter().map(|(_rule, weight)| *weight).sum();
+
+                // Hand-edited grammars may have heads whose rules all have probability 0, which would become NaN
+                if total_head_weight <= 0.0 {
+                    warn!(
+                        "Dropping the rules of {}, whose weights sum up to {}",
+                        head, total_head_weight
                     );
+                    return acc;
+                }
+
+                weighted_rules.into_iter().for_each(|(rule, weight)| {
+                    acc.insert(rule, weight / total_head_weight);
                 });
                 acc
             })
     }
 
-    fn count_rule_occurence(occurence_rules: &mut OccurenceRules, rules: Vec<Rule>) {
+    fn count_rule_occurence(
+        occurence_rules: &mut OccurenceRules,
+        rules: Vec<Rule>,
+        weight: Occurence,
+    ) {
         rules.into_iter().for_each(|rule| {
-            *occurence_rules.entry(rule).or_insert(0) += 1;
+            *occurence_rules.entry(rule).or_insert(0) += weight;
         })
     }
 
@@ -195,72 +547,115 @@ impl PTBRuleInducer for Grammar {
     ) -> Option<Vec<Rule>> {
         let subtree = inital_subtree(initial, parse_tree)?;
 
-        let mut rules = Vec::<Rule>::new();
-        let mut queue: VecDeque<&ParseTree<String>> = VecDeque::new();
-
-        queue.push_front(&subtree);
-
-        while let Some(tree) = queue.pop_front() {
-            rules.push(Rule {
+        let rules = subtree
+            .iter_preorder()
+            .map(|tree| Rule {
                 head: tree.root.clone(),
                 body: match &tree.descendants {
                     Descendants::Atom(atom) => Body::Lexical(atom.to_string()),
                     Descendants::Expressions(parse_trees) => {
-                        parse_trees.iter().for_each(|tree| queue.push_front(&tree));
-
-                        Body::NonLexical(
-                            parse_trees
-                                .into_iter()
-                                .map(|tree| tree.root.clone())
-                                .collect(),
-                        )
+                        Body::NonLexical(parse_trees.iter().map(|tree| tree.root.clone()).collect())
                     }
                 },
             })
-        }
+            .collect();
         Some(rules)
     }
 }
 
-// NOTE: Currently unused
-/// Traverses the tree breadth-first until we hit the first subtree root that matches the intial
-#[allow(unused)]
-fn first_matching_subtree(
+/// Reads off the rules of all parse trees and counts their occurence, e.g. to keep the counts next to the induced grammar
+pub fn count_rules(
     initial: &str,
-    parse_tree: ParseTree<String>,
-) -> Option<ParseTree<String>> {
-    let mut search_queue: VecDeque<&ParseTree<String>> = VecDeque::new();
-    search_queue.push_front(&parse_tree);
+    parse_trees: impl IntoIterator<Item = ParseTree<String>>,
+) -> Result<OccurenceRules, Box<dyn std::error::Error>> {
+    count_weighted_rules(initial, parse_trees.into_iter().map(|tree| (tree, 1)))
+}
 
-    let subtree: Option<ParseTree<String>> = loop {
-        if search_queue.is_empty() {
-            break None;
+/// Like count_rules(), but each tree counts as often as its weight, e.g. for treebanks of distinct trees and their counts
+pub fn count_weighted_rules(
+    initial: &str,
+    parse_trees: impl IntoIterator<Item = (ParseTree<String>, Occurence)>,
+) -> Result<OccurenceRules, Box<dyn std::error::Error>> {
+    info_span!("counting").in_scope(|| Grammar::count_parse_trees(initial, parse_trees))
+}
+
+/// Sums up the fractional weights of the rules of all parse trees (e.g. expected counts from EM),
+/// which normalise like occurences but can't be smoothed
+pub fn weigh_rules(
+    initial: &str,
+    parse_trees: impl IntoIterator<Item = (ParseTree<String>, Probability)>,
+) -> Result<WeightedRules, Box<dyn std::error::Error>> {
+    let _span = info_span!("counting").entered();
+    let mut weighted_rules: WeightedRules = FxHashMap::default();
+
+    for (parse_tree, weight) in parse_trees {
+        let rules =
+            Grammar::read_rules(initial, parse_tree, |_initial, parse_tree| Some(parse_tree))
+                .ok_or("There are no rules to read")?;
+
+        for rule in rules {
+            *weighted_rules.entry(rule).or_insert(0.0) += weight;
         }
+    }
 
-        let tree = search_queue
-            .pop_front()
-            .expect("queue was checked to be not empty");
+    debug!("Weighed {} distinct rules", weighted_rules.len());
+    Ok(weighted_rules)
+}
 
-        if tree.root == initial {
-            break Some(tree.clone());
-        }
+/// Normalises counted rules into exact fractions of the occurence of each rule and of its head (e.g. 2/3),
+/// which unlike floating-point probabilities are the same on every platform.
+/// Fails for a head that occurs 0 times, e.g. because of resumed counts of 0
+pub fn normalise_exactly(occurence_rules: &OccurenceRules) -> Result<ExactRules, String> {
+    let _span = info_span!("normalising").entered();
 
-        match &tree.descendants {
-            Descendants::Atom(_) => {}
-            Descendants::Expressions(parse_trees) => parse_trees
-                .iter()
-                .for_each(|tree| search_queue.push_front(&tree)),
-        }
-    };
+    let mut heads: FxHashMap<&str, Occurence> = FxHashMap::default();
+    for (rule, occurence) in occurence_rules.iter() {
+        *heads.entry(&rule.head).or_insert(0) += occurence;
+    }
 
-    subtree
+    occurence_rules
+        .iter()
+        .map(
+            |(rule, occurence)| match Fraction::new(*occurence, heads[rule.head.as_str()]) {
+                Some(fraction) => Ok((rule.clone(), fraction)),
+                None => Err(format!(
+                    "{} occurs 0 times, so its rules have no fractions",
+                    rule.head
+                )),
+            },
+        )
+        .collect()
+}
+
+/// Projects an annotated nonterminal to its base symbol by removing parent annotations and markovization contexts
+/// (e.g. NP^S -> NP, VP|<NP-PP> -> VP)
+pub fn strip_annotations(nonterminal: &str) -> String {
+    match nonterminal.find(['^', '|']) {
+        // Nonterminals must not become empty
+        Some(index) if index > 0 => nonterminal[..index].to_string(),
+        _ => nonterminal.to_string(),
+    }
+}
+
+// NOTE: Currently unused
+/// Traverses the tree breadth-first until we hit the first subtree root that matches the intial
+#[allow(unused)]
+fn first_matching_subtree(
+    initial: &str,
+    parse_tree: ParseTree<String>,
+) -> Option<ParseTree<String>> {
+    parse_tree
+        .iter_subtrees()
+        .find(|tree| tree.root == initial)
+        .cloned()
 }
 
 #[cfg(test)]
 mod tests {
     use core::f64;
+    use std::collections::HashMap;
 
-    use crate::ptb::PTBParser;
+    use crate::{ptb::PTBParser, smoothing::AddK};
 
     use super::*;
     #[test]
@@ -329,8 +724,8 @@ mod tests {
 
         assert!(rules.is_some());
 
-        let mut occurence_rules: OccurenceRules = HashMap::new();
-        Grammar::count_rule_occurence(&mut occurence_rules, rules.expect("This is some"));
+        let mut occurence_rules: OccurenceRules = FxHashMap::default();
+        Grammar::count_rule_occurence(&mut occurence_rules, rules.expect("This is some"), 1);
 
         assert_eq!(
             occurence_rules,
@@ -387,8 +782,8 @@ mod tests {
 
         assert!(rules.is_some());
 
-        let mut occurence_rules: OccurenceRules = HashMap::new();
-        Grammar::count_rule_occurence(&mut occurence_rules, rules.expect("This is some"));
+        let mut occurence_rules: OccurenceRules = FxHashMap::default();
+        Grammar::count_rule_occurence(&mut occurence_rules, rules.expect("This is some"), 1);
 
         let normalised_rules = Grammar::normalise_rules(occurence_rules);
         assert_eq!(
@@ -403,28 +798,134 @@ mod tests {
                             "NP".to_string()
                         ])
                     },
-                    1.0 as f64
+                    1.0
+                ),
+                (
+                    Rule {
+                        head: "NP".to_string(),
+                        body: Body::NonLexical(vec!["VP".to_string()])
+                    },
+                    1.0
+                ),
+                (
+                    Rule {
+                        head: "VP".to_string(),
+                        body: Body::Lexical("some".to_string())
+                    },
+                    2.0 / 3.0
+                ),
+                (
+                    Rule {
+                        head: "VP".to_string(),
+                        body: Body::Lexical("other".to_string())
+                    },
+                    1.0 / 3.0
+                )
+            ])
+        )
+    }
+
+    #[test]
+    fn drops_heads_without_weight() {
+        let rule = |rule: &str| rule.parse::<Rule>().expect("This is a valid rule");
+        let weighted_rules: WeightedRules = FxHashMap::from_iter(vec![
+            (rule("S -> NP VP"), 1.0),
+            (rule("NP John"), 0.0),
+            (rule("NP Mary"), 0.0),
+            (rule("VP sleeps"), 0.5),
+        ]);
+
+        let normalised_rules = Grammar::normalise_weighted_rules(weighted_rules);
+        assert_eq!(
+            normalised_rules,
+            HashMap::from_iter(vec![(rule("S -> NP VP"), 1.0), (rule("VP sleeps"), 1.0)])
+        );
+    }
+
+    #[test]
+    fn normalises_rules_exactly() {
+        let rule = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let occurence_rules: OccurenceRules = FxHashMap::from_iter(vec![
+            (rule("VP", "some"), 2),
+            (rule("VP", "other"), 4),
+            (rule("NP", "him"), 3),
+        ]);
+
+        let exact_rules = normalise_exactly(&occurence_rules).expect("Every head occurs");
+        assert_eq!(Some(exact_rules[&rule("VP", "some")]), Fraction::new(1, 3));
+        assert_eq!(Some(exact_rules[&rule("VP", "other")]), Fraction::new(2, 3));
+        assert_eq!(Some(exact_rules[&rule("NP", "him")]), Fraction::new(1, 1));
+
+        let grammar = Grammar::from_exact_rules("S".to_string(), &exact_rules);
+        assert_eq!(grammar.rules[&rule("VP", "some")], 1.0 / 3.0);
+
+        // Resumed counts may be 0, which leaves a head without a denominator
+        let occurence_rules: OccurenceRules = FxHashMap::from_iter(vec![(rule("NP", "him"), 0)]);
+        assert_eq!(
+            normalise_exactly(&occurence_rules),
+            Err(String::from(
+                "NP occurs 0 times, so its rules have no fractions"
+            ))
+        );
+    }
+
+    #[test]
+    fn adds_k_with_unknown_rules() {
+        let input = "(S (NP (VP some)) (NP (VP some)) (NP (VP other)))";
+        let output = PTBParser::parse(input).expect("This should be parsable");
+
+        let initial = String::from("S");
+        let add_k = AddK {
+            k: 1.0,
+            unknown: Some("UNK".to_string()),
+        };
+        let grammar = Grammar::from_parse_trees_smoothed(initial, vec![output], &add_k)
+            .expect("This is a valid initial");
+
+        assert_eq!(
+            grammar.rules,
+            HashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "S".to_string(),
+                        body: Body::NonLexical(vec![
+                            "NP".to_string(),
+                            "NP".to_string(),
+                            "NP".to_string()
+                        ])
+                    },
+                    1.0
                 ),
                 (
                     Rule {
                         head: "NP".to_string(),
                         body: Body::NonLexical(vec!["VP".to_string()])
                     },
-                    1.0 as f64
+                    1.0
                 ),
                 (
                     Rule {
                         head: "VP".to_string(),
                         body: Body::Lexical("some".to_string())
                     },
-                    2.0 / 3.0 as f64
+                    3.0 / 6.0
                 ),
                 (
                     Rule {
                         head: "VP".to_string(),
                         body: Body::Lexical("other".to_string())
                     },
-                    1.0 / 3.0 as f64
+                    2.0 / 6.0
+                ),
+                (
+                    Rule {
+                        head: "VP".to_string(),
+                        body: Body::Lexical("UNK".to_string())
+                    },
+                    1.0 / 6.0
                 )
             ])
         )
@@ -579,4 +1080,256 @@ mod tests {
             assert!(approx_equal(*total, 1.0));
         }
     }
+
+    #[test]
+    fn strips_annotations() {
+        assert_eq!(strip_annotations("NP^S"), "NP");
+        assert_eq!(strip_annotations("VP|<NP-PP>"), "VP");
+        assert_eq!(strip_annotations("NP-SBJ"), "NP-SBJ");
+        assert_eq!(strip_annotations("|"), "|");
+    }
+
+    #[test]
+    fn projects_annotated_grammar() {
+        let grammar = Grammar {
+            initial: "ROOT".to_string(),
+            rules: HashMap::from_iter(vec![
+                (
+                    Rule {
+                        head: "ROOT".to_string(),
+                        body: Body::NonLexical(vec!["NP^ROOT".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "NP^ROOT".to_string(),
+                        body: Body::NonLexical(vec!["NN".to_string(), "PP^NP".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "PP^NP".to_string(),
+                        body: Body::NonLexical(vec!["IN".to_string(), "NP^PP".to_string()]),
+                    },
+                    1.0,
+                ),
+                (
+                    Rule {
+                        head: "NP^PP".to_string(),
+                        body: Body::NonLexical(vec!["NN".to_string(), "PP^NP".to_string()]),
+                    },
+                    0.5,
+                ),
+                (
+                    Rule {
+                        head: "NP^PP".to_string(),
+                        body: Body::NonLexical(vec!["NN".to_string()]),
+                    },
+                    0.5,
+                ),
+            ]),
+        };
+
+        let projected = grammar.project(strip_annotations);
+
+        assert_eq!(
+            projected,
+            Grammar {
+                initial: "ROOT".to_string(),
+                rules: HashMap::from_iter(vec![
+                    (
+                        Rule {
+                            head: "ROOT".to_string(),
+                            body: Body::NonLexical(vec!["NP".to_string()]),
+                        },
+                        1.0,
+                    ),
+                    (
+                        Rule {
+                            head: "NP".to_string(),
+                            body: Body::NonLexical(vec!["NN".to_string(), "PP".to_string()]),
+                        },
+                        0.75,
+                    ),
+                    (
+                        Rule {
+                            head: "NP".to_string(),
+                            body: Body::NonLexical(vec!["NN".to_string()]),
+                        },
+                        0.25,
+                    ),
+                    (
+                        Rule {
+                            head: "PP".to_string(),
+                            body: Body::NonLexical(vec!["IN".to_string(), "NP".to_string()]),
+                        },
+                        1.0,
+                    ),
+                ]),
+            }
+        )
+    }
+
+    #[test]
+    fn partitions_lexical_and_nonlexical_rules() {
+        let input = "(S (NP (NNP Julius)) (VP (VB stabs) (NP (NN him))))";
+        let output = PTBParser::parse(input).expect("This should be parsable");
+
+        let grammar = Grammar::from_parse_trees("S".to_string(), vec![output])
+            .expect("This is a valid initial");
+
+        assert!(grammar
+            .lexical_rules()
+            .all(|(rule, _probability)| rule.is_lexical_rule()));
+        assert!(grammar
+            .nonlexical_rules()
+            .all(|(rule, _probability)| !rule.is_lexical_rule()));
+        assert_eq!(grammar.lexical_rules().count(), 3);
+        assert_eq!(grammar.nonlexical_rules().count(), 4);
+    }
+
+    #[test]
+    fn looks_up_rules() {
+        let input = "(S (NP (NNP Julius)) (VP (VB stabs) (NP (NN him))))";
+        let output = PTBParser::parse(input).expect("This should be parsable");
+
+        let grammar = Grammar::from_parse_trees("S".to_string(), vec![output])
+            .expect("This is a valid initial");
+
+        let rule: Rule = "NP -> NNP".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(0.5));
+        let rule: Rule = "NN him".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(1.0));
+        let rule: Rule = "NP -> DT NN".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), None);
+
+        assert_eq!(
+            grammar
+                .rules_of("NP")
+                .into_iter()
+                .map(|(rule, probability)| format!("{} {}", rule, probability))
+                .collect::<Vec<String>>(),
+            vec!["NP -> NN 0.5", "NP -> NNP 0.5"]
+        );
+        "NP ->".parse::<Rule>().expect_err("The body is missing");
+    }
+
+    #[test]
+    fn renormalises_edited_rules() {
+        let mut grammar = crate::berkeley::BerkeleyReader::read(
+            "S".to_string(),
+            "S -> A 0.2\nS -> B 0.6\n".as_bytes(),
+            "A a 1\nB b 0.5\n".as_bytes(),
+        )
+        .expect("This is a valid grammar");
+
+        grammar.normalise();
+        let rule: Rule = "S -> A".parse().expect("This is a valid rule");
+        assert!((grammar.probability(&rule).expect("The rule is kept") - 0.25).abs() < 1e-9);
+        let rule: Rule = "B b".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(1.0));
+
+        grammar.quantize(2);
+        let rule: Rule = "S -> B".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(0.75));
+
+        let mut grammar = crate::berkeley::BerkeleyReader::read(
+            "S".to_string(),
+            "S -> A 2\nS -> B 1\n".as_bytes(),
+            "A a 1\nB b 1\n".as_bytes(),
+        )
+        .expect("This is a valid grammar");
+        grammar.quantize(3);
+        let rule: Rule = "S -> A".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(0.667));
+
+        let mut grammar = crate::berkeley::BerkeleyReader::read(
+            "S".to_string(),
+            "S -> A 1\nS -> B 1\nS -> C 1\n".as_bytes(),
+            "A a 1\nB b 1\nC c 0.25\nC d 0.25\nC e 0.25\nC f 0.25\nC g 0.25\nC h 0.25\n".as_bytes(),
+        )
+        .expect("This is a valid grammar");
+        grammar.quantize(3);
+        let rule: Rule = "S -> A".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(0.334));
+        let rule: Rule = "S -> C".parse().expect("This is a valid rule");
+        assert_eq!(grammar.probability(&rule), Some(0.333));
+
+        for head in ["S", "C"] {
+            let sum: Probability = grammar
+                .rules_of(head)
+                .iter()
+                .map(|(_rule, probability)| probability)
+                .sum();
+            assert!((sum - 1.0).abs() <= 1e-12);
+        }
+    }
+
+    #[test]
+    fn finds_unary_cycles() {
+        let grammar = crate::berkeley::BerkeleyReader::read(
+            "ROOT".to_string(),
+            "ROOT -> S 1\nS -> VP 0.5\nS -> NP VP 0.5\nVP -> S 0.5\nVP -> V 0.5\nNP -> NP 0.5\nNP -> N 0.5\n"
+                .as_bytes(),
+            "V runs 1\nN dogs 1\n".as_bytes(),
+        )
+        .expect("This is a valid grammar");
+
+        assert_eq!(
+            grammar.unary_cycles(),
+            vec![
+                vec!["NP".to_string()],
+                vec!["S".to_string(), "VP".to_string()]
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_top_k_rules_per_head() {
+        let trees = [
+            "(S (NP (DT the) (NN dog)) (VP (VB barks)))",
+            "(S (NP (DT a) (NN cat)) (VP (VB sees) (NP (NN it))))",
+            "(S (NP (NN Bob)) (VP (VB runs) (ADVP (RB fast))))",
+        ]
+        .into_iter()
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+        let grammar =
+            Grammar::from_parse_trees("S".to_string(), trees).expect("This is a valid initial");
+
+        let pruned = grammar.top_k(1);
+        let rules = |head: &str| {
+            pruned
+                .rules_of(head)
+                .into_iter()
+                .map(|(rule, probability)| format!("{} {}", rule, probability))
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(rules("NP"), vec!["NP -> DT NN 1"]);
+        assert_eq!(rules("VP"), vec!["VP -> VB 1"]);
+        assert_eq!(rules("DT"), vec!["DT a 0.5", "DT the 0.5"]);
+    }
+
+    #[test]
+    fn multiplies_counts_by_tree_weights() {
+        let tree = |tree| PTBParser::parse(tree).expect("This should be parsable");
+        let rule = Rule {
+            head: "A".to_string(),
+            body: Body::Lexical("a".to_string()),
+        };
+
+        let counts =
+            count_weighted_rules("S", vec![(tree("(S (A a))"), 3), (tree("(S (A a))"), 2)])
+                .expect("The trees have rules");
+        assert_eq!(counts.get(&rule), Some(&5));
+
+        let weights = weigh_rules(
+            "S",
+            vec![(tree("(S (A a))"), 0.25), (tree("(S (A b))"), 0.75)],
+        )
+        .expect("The trees have rules");
+        let grammar = Grammar::from_weighted_rules("S".to_string(), weights);
+        assert_eq!(grammar.probability(&rule), Some(0.25));
+    }
 }
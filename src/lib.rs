@@ -1,13 +1,40 @@
 use core::fmt;
-use std::collections::HashMap;
+
+use rustc_hash::FxHashMap;
 
 use crate::berkeley::BerkeleyFormatWriter;
-use berkeley::BerkeleyWriter;
+use berkeley::{unescape, BerkeleyWriter, Escaped};
 
 pub mod berkeley;
+#[cfg(feature = "capi")]
+pub mod capi;
 pub mod cli;
+pub mod compare;
+pub mod compression;
+pub mod config;
+pub mod conllu;
+pub mod container;
+pub mod counts;
+pub mod coverage;
+pub mod exit;
+pub mod filter;
+pub mod format;
 pub mod induce;
+pub mod logging;
+pub mod negra;
+pub mod oov;
+pub mod progress;
 pub mod ptb;
+pub mod smoothing;
+pub mod summary;
+pub mod svg;
+pub mod tiger;
+pub mod tokenize;
+pub mod transform;
+pub mod unknown;
+pub mod vocab;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 type Nonterminal = String;
 type Terminal = String;
@@ -33,18 +60,15 @@ impl Rule {
     }
 }
 
+/// Prints rules like the Berkeley format without probability, i.e. HEAD -> BODY or HEAD TERMINAL with escaped symbols
 impl fmt::Display for Rule {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.body {
-            Body::Lexical(terminal) => write!(f, "{} -> {}", self.head, terminal),
+            Body::Lexical(terminal) => write!(f, "{} {}", Escaped(&self.head), Escaped(terminal)),
             Body::NonLexical(nonterminals) => {
-                write!(f, "{} -> ", self.head)?;
-                for (index, nonterminal) in nonterminals.iter().enumerate() {
-                    if index != nonterminals.len() - 1 {
-                        write!(f, "{} ", nonterminal)?;
-                    } else {
-                        write!(f, "{}", nonterminal)?;
-                    }
+                write!(f, "{} ->", Escaped(&self.head))?;
+                for nonterminal in nonterminals {
+                    write!(f, " {}", Escaped(nonterminal))?;
                 }
                 Ok(())
             }
@@ -52,11 +76,44 @@ impl fmt::Display for Rule {
     }
 }
 
+/// Parses rules like they are printed, i.e. HEAD -> BODY or HEAD TERMINAL with escaped symbols
+impl std::str::FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let unescape = |token: &str| unescape(token).ok_or(format!("Malformed symbol {}", token));
+        let tokens: Vec<&str> = s.split_whitespace().collect();
+
+        match tokens.as_slice() {
+            [head, "->", body @ ..] if !body.is_empty() => Ok(Rule {
+                head: unescape(head)?,
+                body: Body::NonLexical(
+                    body.iter()
+                        .map(|nonterminal| unescape(nonterminal))
+                        .collect::<Result<_, _>>()?,
+                ),
+            }),
+            // A terminal -> would have been escaped
+            [head, terminal] if *terminal != "->" => Ok(Rule {
+                head: unescape(head)?,
+                body: Body::Lexical(unescape(terminal)?),
+            }),
+            _ => Err(format!(
+                "\"{}\" is neither HEAD -> BODY nor HEAD TERMINAL",
+                s
+            )),
+        }
+    }
+}
+
 type Probability = f64;
-type Occurence = u32;
+type Occurence = u64;
 
-type ProbabilityRules = HashMap<Rule, Probability>;
-type OccurenceRules = HashMap<Rule, Occurence>;
+// Rules are hashed very often during induction, so the maps use the faster (non-DoS-resistant) FxHash
+type ProbabilityRules = FxHashMap<Rule, Probability>;
+pub type OccurenceRules = FxHashMap<Rule, Occurence>;
+pub type WeightedRules = FxHashMap<Rule, Probability>;
+pub type ExactRules = FxHashMap<Rule, berkeley::Fraction>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Grammar {
@@ -64,9 +121,20 @@ pub struct Grammar {
     rules: ProbabilityRules,
 }
 
+impl Grammar {
+    pub fn initial(&self) -> &str {
+        &self.initial
+    }
+
+    /// Replaces the initial, e.g. when it is given on the command line instead of being read with the grammar
+    pub fn set_initial(&mut self, initial: Nonterminal) {
+        self.initial = initial;
+    }
+}
+
 impl fmt::Display for Grammar {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let berkeley_writer = BerkeleyWriter::from_grammar((*self).clone());
+        let berkeley_writer = BerkeleyWriter::from_grammar(self);
         berkeley_writer.rules_fmt(f)?;
         berkeley_writer.lexicon_fmt(f)?;
         berkeley_writer.words_fmt(f)?;
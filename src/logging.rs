@@ -0,0 +1,47 @@
+use std::io::IsTerminal;
+
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+use crate::cli::LogFormat;
+
+/// Maps --quiet and the number of -v flags onto the most verbose level that is logged
+pub fn max_level(verbosity: u8, quiet: bool) -> Level {
+    match (quiet, verbosity) {
+        (true, _) => Level::ERROR,
+        (false, 0) => Level::WARN,
+        (false, 1) => Level::INFO,
+        (false, 2) => Level::DEBUG,
+        (false, _) => Level::TRACE,
+    }
+}
+
+/// Logs to the stderr, so that the stdout stays free for grammars and trees.
+/// Closing stage spans (reading, counting, ...) are logged with their duration at the info level
+pub fn init(verbosity: u8, quiet: bool, format: &LogFormat) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::stderr().is_terminal())
+        .with_max_level(max_level(verbosity, quiet))
+        .with_span_events(FmtSpan::CLOSE)
+        .with_target(false);
+
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_verbosity_to_levels() {
+        assert_eq!(max_level(0, false), Level::WARN);
+        assert_eq!(max_level(1, false), Level::INFO);
+        assert_eq!(max_level(2, false), Level::DEBUG);
+        assert_eq!(max_level(5, false), Level::TRACE);
+        assert_eq!(max_level(0, true), Level::ERROR);
+    }
+}
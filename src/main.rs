@@ -1,110 +1,717 @@
-use std::{fs::File, io::Write, process::exit};
+use std::{
+    io::{BufRead, Read, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use tracing::{error, info, info_span, warn};
 
 use pcfg_tool::{
-    berkeley::{BerkeleyFormatWriter, BerkeleyWriter},
-    cli::{Cli, CommandFactory, Commands, Parser},
-    induce::PCFGGrammar,
-    ptb::PTBParser,
-    Grammar,
+    berkeley::{BerkeleyWriter, Escaped, ProbabilityDisplay, RuleWithProbability},
+    cli::{
+        Cli, CommandFactory, Commands, CrossingBranches, OovModel, Parser, PreprocessArgs,
+        Punctuation, Render, SmoothingArgs, SmoothingMethod, Source, TransformArgs, TreeFormat,
+    },
+    compare::compare,
+    compression::{decompress, open, Output},
+    config::{validate, Config},
+    container::{write_metadata, ContainerWriter, Metadata},
+    counts::{read_counts, read_pseudo_counts, write_counts},
+    coverage::Recognizer,
+    exit::{exit, ExitCode},
+    filter::TreeFilter,
+    format::{GrammarFormatWriter, GrammarSource},
+    induce::{
+        count_weighted_rules, normalise_exactly, strip_annotations, weigh_rules, PCFGGrammar,
+    },
+    logging, negra,
+    oov::OovReport,
+    progress::{Progress, ProgressIterator},
+    ptb::{PTBParser, ParseError, ParseTree, ASCII_BRANCHES, BOX_BRANCHES},
+    smoothing::{AddK, GoodTuring, OpenClassOnly, Prior, SignatureClasses, Smoothing, WittenBell},
+    summary::InduceSummary,
+    svg::SvgOptions,
+    tiger::read_tiger,
+    tokenize::{split_sentences, tokenize},
+    transform,
+    unknown::{
+        self, count_suffixes, fold_rare_words, replace_rare_words, SignatureOptions, SuffixModel,
+        MAX_OOV_SUFFIX_LENGTH, UNKNOWN,
+    },
+    vocab::Vocabulary,
+    ExactRules, Grammar, OccurenceRules, Rule, WeightedRules,
 };
 
 fn main() {
-    let cli = Cli::parse();
+    // clap would exit with 2 on invalid options
+    let mut cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            e.print().expect("Unable to write to stderr");
+            exit(match e.use_stderr() {
+                true => ExitCode::Usage,
+                false => ExitCode::Success,
+            });
+        }
+    };
+    logging::init(cli.verbose, cli.quiet, &cli.log_format);
+
+    if let (Some(path), Some(command)) = (&cli.config, &mut cli.command) {
+        match Config::read(path) {
+            Ok(config) => config.apply(command),
+            Err(e) => {
+                error!("Error while reading config {}: {}", path, e);
+                exit(ExitCode::Usage);
+            }
+        }
+
+        if let Err(e) = validate(command) {
+            error!("Invalid options with config {}: {}", path, e);
+            exit(ExitCode::Usage);
+        }
+    }
 
     match &cli.command {
-        Some(Commands::Induce { grammar }) => {
-            if let Some(gname) = grammar {
-                let mut trees = Vec::new();
-                for (i, line) in std::io::stdin().lines().enumerate() {
-                    match line {
-                        Ok(line) => match PTBParser::parse(&line) {
-                            Ok(tree) => trees.push(tree),
-                            Err(e) => {
-                                eprintln!("Error while parsing tree {} at line {}: {}", line, i, e);
-                                exit(1);
-                            }
-                        },
+        Some(Commands::Induce {
+            grammar,
+            treebanks,
+            source,
+            treebank_dir,
+            tiger,
+            crossing_branches,
+            preprocess_args,
+            vocab_threshold,
+            signatures,
+            signature_args,
+            oov_model,
+            oov_suffix_length,
+            smoothing_args,
+            resume,
+            gzip,
+            output_dir,
+            berkeley_lexicon,
+            lexicon_counts,
+            word_counts,
+            counts,
+            pcfg,
+            significant_digits,
+            fractions,
+            progress,
+        }) => {
+            // - keeps the stdout as output when treebanks are given
+            let grammar = grammar.as_deref().filter(|gname| *gname != "-");
+            let grammar = match (grammar, output_dir) {
+                (Some(gname), Some(dir)) => {
+                    if let Err(e) = prepare_output_dir(dir) {
+                        error!("Can't write into output directory {}: {}", dir, e);
+                        exit(ExitCode::Io);
+                    }
+
+                    Some(Path::new(dir).join(gname).display().to_string())
+                }
+                (gname, _) => gname.map(String::from),
+            };
+
+            // Before the trees, so that a missing file doesn't waste a long reading
+            let resumed = resume.as_deref().map(|path| {
+                let counts = read_saved_counts(path);
+                info!("Resuming from {} counted rules", counts.len());
+                counts
+            });
+
+            let start = Instant::now();
+            let mut malformed = MalformedLines::new(cli.strict);
+            let mut trees = info_span!("reading").in_scope(|| {
+                let mut reading = Progress::new("Reading", "trees", None, *progress);
+                let skip_stdin = treebank_dir.is_some() || !tiger.is_empty();
+                let mut trees =
+                    read_trees(treebanks, source, skip_stdin, &mut malformed, &mut reading);
+                // Only the one-tree-per-line treebanks can be weighted
+                let mut unweighted = Vec::new();
+                if let Some(dir) = treebank_dir {
+                    read_treebank_dir(dir, &mut unweighted, &mut reading);
+                }
+                let crossing_branches = crossing_branches.unwrap_or(CrossingBranches::Reorder);
+                for corpus in tiger {
+                    read_tiger_corpus(corpus, crossing_branches, &mut unweighted, &mut reading);
+                }
+                trees.extend(unweighted.into_iter().map(|tree| (tree, 1.0)));
+                reading.finish();
+
+                trees
+            });
+            info!("Read {} trees", trees.len());
+            let trees_read = trees.len();
+            preprocess(&mut trees, preprocess_args);
+            let trees_skipped = malformed.count + trees_read - trees.len();
+            let reading_time = start.elapsed();
+
+            let start = Instant::now();
+            let fractional = trees.iter().any(|(_tree, weight)| weight.fract() != 0.0);
+            // u64::MAX itself isn't representable, so its f64 is the first weight out of range
+            let too_large = trees
+                .iter()
+                .find(|(_tree, weight)| *weight >= u64::MAX as f64);
+            if let (false, Some((_tree, weight))) = (fractional, too_large) {
+                error!(
+                    "The whole weight {} exceeds the largest count {}",
+                    weight,
+                    u64::MAX
+                );
+                exit(ExitCode::Usage);
+            }
+            let trees = trees.into_iter().progress("Counting", "trees", *progress);
+
+            let options = signature_args.options();
+            let vocabulary =
+                vocab_threshold.map(|threshold| (threshold, signatures.then_some(&options)));
+            let suffix_length = oov_model.map(|OovModel::Suffix| {
+                oov_suffix_length.map_or(MAX_OOV_SUFFIX_LENGTH, |length| length as usize)
+            });
+            let keep_counts = *lexicon_counts || *word_counts || *counts || *fractions;
+            let (mut g, rule_counts, suffix_counts) = if fractional {
+                let smoothed = smoothing_args.add_k.is_some() || smoothing_args.smoothing.is_some();
+                if vocabulary.is_some() || smoothed || keep_counts {
+                    error!("Trees with fractional weights can't be induced with --vocab-threshold, smoothing or counts");
+                    exit(ExitCode::Usage);
+                }
+
+                let prior = smoothing_args.prior.as_deref();
+                (induce_fractional(trees, resumed, prior), None, None)
+            } else {
+                // Whole weights are counted exactly
+                let trees = trees.map(|(tree, weight)| (tree, weight as u64));
+                induce(
+                    trees,
+                    resumed,
+                    vocabulary,
+                    suffix_length,
+                    smoothing_args,
+                    &options,
+                    keep_counts,
+                )
+            };
+            if let Some(digits) = significant_digits {
+                info_span!("quantizing").in_scope(|| g.quantize(*digits));
+            }
+            // The grammar takes the probabilities of the fractions it's printed with
+            let exact_rules = match (*fractions, &rule_counts) {
+                (true, Some(counts)) => {
+                    let exact_rules = match normalise_exactly(counts) {
+                        Ok(exact_rules) => exact_rules,
                         Err(e) => {
-                            eprintln!("Error on line {}: {}", i, e);
-                            exit(1);
+                            error!("Error while normalising the counts into fractions: {}", e);
+                            exit(ExitCode::UnreadableGrammar);
                         }
-                    }
+                    };
+                    g = Grammar::from_exact_rules(g.initial().to_string(), &exact_rules);
+                    Some(exact_rules)
                 }
+                _ => None,
+            };
+            let signature_classes = *signatures || smoothing_args.unknown_signatures;
+            let metadata = Metadata::new(
+                g.initial(),
+                applied_transforms(
+                    preprocess_args,
+                    *vocab_threshold,
+                    *signatures,
+                    signature_classes.then_some(&options),
+                    *oov_model,
+                ),
+            );
+            let format = OutputFormat {
+                grouped_lexicon: *berkeley_lexicon,
+                lexicon_counts: rule_counts.as_ref().filter(|_| *lexicon_counts),
+                word_counts: rule_counts.as_ref().filter(|_| *word_counts),
+                rule_counts: rule_counts.as_ref().filter(|_| *counts),
+                suffix_counts: suffix_counts.as_ref(),
+                metadata: Some(&metadata),
+                container: *pcfg,
+                decimals: cli.decimals,
+                fractions: exact_rules.as_ref(),
+            };
+            let mut summary = InduceSummary::new(trees_read, trees_skipped, &g);
+            summary.phases.push(("Reading", reading_time));
+            summary.phases.push(("Counting", start.elapsed()));
 
-                let initial = "ROOT";
-                let grammar = Grammar::from_parse_trees(initial.to_string(), trees);
+            let start = Instant::now();
+            write_grammar(g, grammar.as_deref(), *gzip, format);
+            summary.phases.push(("Writing", start.elapsed()));
 
-                match grammar {
-                    Ok(g) => {
-                        let berkeley_writer = BerkeleyWriter::from_grammar(g);
+            if !cli.quiet {
+                eprint!("{}", summary);
+            }
+        }
+        Some(Commands::Pipeline {
+            grammar,
+            treebanks,
+            preprocess_args,
+            transform_args,
+            unknown_threshold,
+            signatures,
+            signature_args,
+            smoothing_args,
+            project,
+            gzip,
+        }) => {
+            let mut trees = info_span!("reading").in_scope(|| {
+                unweighted(read_trees(
+                    treebanks,
+                    &[],
+                    false,
+                    &mut MalformedLines::new(cli.strict),
+                    &mut Progress::new("Reading", "trees", None, false),
+                ))
+            });
+            info!("Read {} trees", trees.len());
+            preprocess(&mut trees, preprocess_args);
+            transform_trees(&mut trees, transform_args);
 
-                        let mut rules = File::create(format!("{}.rules", gname))
-                            .expect("Error while creating rules file");
-                        berkeley_writer
-                            .rules_io(&mut rules)
-                            .expect("Couldn't write rules file");
+            let options = signature_args.options();
+            if let Some(threshold) = unknown_threshold {
+                info_span!("replacing unknown words").in_scope(|| {
+                    replace_rare_words(
+                        &mut trees,
+                        u64::from(*threshold),
+                        signatures.then_some(&options),
+                    )
+                });
+            }
 
-                        let mut lexicon = File::create(format!("{}.lexicon", gname))
-                            .expect("Error while creating lexicon file");
-                        berkeley_writer
-                            .lexicon_io(&mut lexicon)
-                            .expect("Couldn't write lexicon file");
+            let trees = trees.into_iter().map(|tree| (tree, 1));
+            let (mut g, _counts, _suffix_counts) =
+                induce(trees, None, None, None, smoothing_args, &options, false);
 
-                        let mut words = File::create(format!("{}.words", gname))
-                            .expect("Error while creating words file");
-                        berkeley_writer
-                            .words_io(&mut words)
-                            .expect("Couldn't write words file");
-                    }
-                    Err(e) => {
-                        eprintln!("Error while creating PCFG from trees: {}", e);
-                        exit(1);
+            if *project {
+                g = info_span!("projecting").in_scope(|| g.project(strip_annotations));
+            }
+
+            // Lookups of unknown words have to find the same signature classes
+            let signature_classes = *signatures || smoothing_args.unknown_signatures;
+            let transforms = applied_transforms(
+                preprocess_args,
+                *unknown_threshold,
+                *signatures,
+                signature_classes.then_some(&options),
+                None,
+            );
+            let metadata = Metadata::new(g.initial(), transforms);
+            write_grammar(
+                g,
+                grammar.as_deref().filter(|gname| *gname != "-"),
+                *gzip,
+                OutputFormat {
+                    metadata: Some(&metadata),
+                    decimals: cli.decimals,
+                    ..Default::default()
+                },
+            );
+        }
+        Some(Commands::Project {
+            grammar,
+            projected,
+            initial,
+            gzip,
+        }) => {
+            let g = info_span!("reading").in_scope(|| read_grammar(grammar, initial.as_deref()));
+            let g = info_span!("projecting").in_scope(|| g.project(strip_annotations));
+
+            let metadata = Metadata::new(g.initial(), recorded_transforms(grammar));
+            write_grammar(
+                g,
+                projected.as_deref(),
+                *gzip,
+                OutputFormat {
+                    metadata: Some(&metadata),
+                    container: is_container(grammar),
+                    decimals: cli.decimals,
+                    ..Default::default()
+                },
+            );
+        }
+        Some(Commands::Transform {
+            treebanks,
+            preprocess_args,
+            transform_args,
+            tree_format,
+        }) => {
+            let mut trees = info_span!("reading").in_scope(|| {
+                unweighted(read_trees(
+                    treebanks,
+                    &[],
+                    false,
+                    &mut MalformedLines::new(cli.strict),
+                    &mut Progress::new("Reading", "trees", None, false),
+                ))
+            });
+            info!("Read {} trees", trees.len());
+            preprocess(&mut trees, preprocess_args);
+            transform_trees(&mut trees, transform_args);
+
+            let _span = info_span!("writing").entered();
+            write_trees(&trees, tree_format.unwrap_or(TreeFormat::Ptb));
+        }
+        Some(Commands::Compare { grammar, other }) => {
+            let (p, q) = info_span!("reading")
+                .in_scope(|| (read_grammar(grammar, None), read_grammar(other, None)));
+            let comparison = info_span!("comparing").in_scope(|| compare(&p, &q));
+
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            writeln!(stdout, "HEAD KL JS").expect("Unable to write to stdout");
+            for head in comparison.heads {
+                writeln!(stdout, "{} {} {}", Escaped(&head.head), head.kl, head.js)
+                    .expect("Unable to write to stdout");
+            }
+            writeln!(stdout, "Rule overlap: {:.2}%", comparison.overlap)
+                .expect("Unable to write to stdout");
+            stdout.flush().expect("Unable to write to stdout");
+        }
+        Some(Commands::Query {
+            grammar,
+            rule,
+            head,
+            word,
+        }) => {
+            let g = info_span!("reading").in_scope(|| read_grammar(grammar, None));
+
+            let rules: Vec<(Rule, f64)> = match (rule, head, word) {
+                (_, _, Some(word)) => scanned_rules(&g, grammar, word),
+                (_, Some(head), None) => {
+                    let rules = g.rules_of(head);
+                    if rules.is_empty() {
+                        error!("{} has no rules in grammar {}", Escaped(head), grammar);
+                        exit(ExitCode::NotFound);
                     }
+
+                    rules
+                        .into_iter()
+                        .map(|(rule, probability)| (rule.clone(), probability))
+                        .collect()
                 }
-            } else {
-                let mut trees = Vec::new();
-                for (i, line) in std::io::stdin().lines().enumerate() {
-                    match line {
-                        Ok(line) => match PTBParser::parse(&line) {
-                            Ok(tree) => trees.push(tree),
-                            Err(e) => {
-                                eprintln!("Error while parsing tree {} at line {}: {}", line, i, e);
-                                exit(1);
-                            }
-                        },
+                (Some(rule), None, None) => {
+                    let rule: Rule = match rule.parse() {
+                        Ok(rule) => rule,
                         Err(e) => {
-                            eprintln!("Error on line {}: {}", i, e);
-                            exit(1);
+                            error!("Invalid query: {}", e);
+                            exit(ExitCode::Usage);
+                        }
+                    };
+
+                    match g.probability(&rule) {
+                        Some(probability) => vec![(rule, probability)],
+                        None => {
+                            error!("{} is not part of grammar {}", rule, grammar);
+                            exit(ExitCode::NotFound);
                         }
                     }
                 }
+                (None, None, None) => unreachable!("clap requires a rule, a head or a word"),
+            };
+
+            // The grammar files only store probabilities, so the counts come from induce --counts
+            let counts_path = sidecar(grammar, "counts");
+            let counts = match open(&counts_path) {
+                Ok(_) => Some(read_saved_counts(&counts_path)),
+                Err(_) => {
+                    warn!(
+                        "Counts are unavailable, since grammar {} has no {} (see induce --counts)",
+                        grammar, counts_path
+                    );
+                    None
+                }
+            };
+
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            for (rule, probability) in rules {
+                let probability = ProbabilityDisplay(probability, cli.decimals);
+                write!(stdout, "{}", RuleWithProbability(&rule, probability))
+                    .expect("Unable to write to stdout");
+                // Rules that were never observed (e.g. added by smoothing) have no count
+                if let Some(counts) = &counts {
+                    let count = counts.get(&rule).copied().unwrap_or(0);
+                    write!(stdout, " {}", count).expect("Unable to write to stdout");
+                }
+                writeln!(stdout).expect("Unable to write to stdout");
+            }
+            stdout.flush().expect("Unable to write to stdout");
+        }
+        Some(Commands::Filter {
+            treebanks,
+            root,
+            max_depth,
+            min_length,
+            max_length,
+            with,
+            without,
+            tree_format,
+        }) => {
+            let trees = info_span!("reading").in_scope(|| {
+                unweighted(read_trees(
+                    treebanks,
+                    &[],
+                    false,
+                    &mut MalformedLines::new(cli.strict),
+                    &mut Progress::new("Reading", "trees", None, false),
+                ))
+            });
+            info!("Read {} trees", trees.len());
+
+            let filter = TreeFilter {
+                root: root.clone(),
+                max_depth: *max_depth,
+                min_length: *min_length,
+                max_length: *max_length,
+                with: with.clone(),
+                without: without.clone(),
+            };
+
+            let _span = info_span!("writing").entered();
+            let selected: Vec<&ParseTree<String>> =
+                trees.iter().filter(|tree| filter.matches(tree)).collect();
+            write_trees(
+                selected.iter().copied(),
+                tree_format.unwrap_or(TreeFormat::Ptb),
+            );
+            info!("Selected {} of {} trees", selected.len(), trees.len());
+        }
+        Some(Commands::Fmt {
+            treebanks,
+            pretty,
+            indent,
+            oneline: _,
+            render,
+        }) => {
+            let trees = info_span!("reading").in_scope(|| {
+                read_multiline_trees(
+                    treebanks,
+                    &mut Progress::new("Reading", "trees", None, false),
+                )
+            });
+            info!("Read {} trees", trees.len());
+
+            let _span = info_span!("writing").entered();
+            let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+            for tree in trees {
+                match (render, pretty) {
+                    (Some(Render::Ascii), _) => {
+                        writeln!(stdout, "{}", tree.render(&ASCII_BRANCHES))
+                    }
+                    (Some(Render::Box), _) => writeln!(stdout, "{}", tree.render(&BOX_BRANCHES)),
+                    (None, true) => writeln!(stdout, "{}", tree.pretty(indent.unwrap_or(2))),
+                    (None, false) => writeln!(stdout, "{}", tree),
+                }
+                .expect("Unable to write to stdout");
+            }
+            stdout.flush().expect("Unable to write to stdout");
+        }
+        Some(Commands::Viz {
+            treebanks,
+            svg,
+            output_dir,
+            font_family,
+            font_size,
+        }) => {
+            let trees = info_span!("reading").in_scope(|| {
+                read_multiline_trees(
+                    treebanks,
+                    &mut Progress::new("Reading", "trees", None, false),
+                )
+            });
+            info!("Read {} trees", trees.len());
 
-                let initial = "ROOT";
-                let grammar = Grammar::from_parse_trees(initial.to_string(), trees);
+            let _span = info_span!("writing").entered();
+            if !*svg {
+                let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+                for tree in trees {
+                    writeln!(stdout, "{}\n", tree.render(&ASCII_BRANCHES))
+                        .expect("Unable to write to stdout");
+                }
+                stdout.flush().expect("Unable to write to stdout");
+                return;
+            }
 
-                match grammar {
-                    Ok(g) => {
-                        let berkeley_writer = BerkeleyWriter::from_grammar(g);
+            let defaults = SvgOptions::default();
+            let options = SvgOptions {
+                font_family: font_family.clone().unwrap_or(defaults.font_family),
+                font_size: font_size.unwrap_or(defaults.font_size),
+            };
 
-                        let mut stdout = std::io::stdout();
-                        berkeley_writer
-                            .rules_io(&mut stdout)
-                            .expect("Unable to write to stdout");
-                        berkeley_writer
-                            .lexicon_io(&mut stdout)
-                            .expect("Unable to write to stdout");
-                        berkeley_writer
-                            .words_io(&mut stdout)
-                            .expect("Unable to write to stdout");
+            match output_dir {
+                Some(dir) => {
+                    if let Err(e) = prepare_output_dir(dir) {
+                        error!("Can't write into output directory {}: {}", dir, e);
+                        exit(ExitCode::Io);
+                    }
 
-                        stdout.flush().expect("works");
+                    for (i, tree) in trees.iter().enumerate() {
+                        let path = Path::new(dir).join(format!("tree-{}.svg", i + 1));
+                        if let Err(e) = std::fs::write(&path, tree.to_svg(&options)) {
+                            error!("Error while writing {}: {}", path.display(), e);
+                            exit(ExitCode::Io);
+                        }
+                    }
+                }
+                None => match trees.as_slice() {
+                    [tree] => print!("{}", tree.to_svg(&options)),
+                    _ => {
+                        error!(
+                            "Only a single tree can be drawn to the stdout, use --output-dir for {} trees",
+                            trees.len()
+                        );
+                        exit(ExitCode::Usage);
                     }
-                    Err(e) => {
-                        eprintln!("Error while creating PCFG from trees: {}", e);
-                        exit(1);
+                },
+            }
+        }
+        Some(Commands::Prune {
+            grammar,
+            pruned,
+            top_k,
+            initial,
+            gzip,
+        }) => {
+            let g = info_span!("reading").in_scope(|| read_grammar(grammar, initial.as_deref()));
+            let g = info_span!("pruning").in_scope(|| g.top_k(*top_k));
+
+            let metadata = Metadata::new(g.initial(), recorded_transforms(grammar));
+            write_grammar(
+                g,
+                pruned.as_deref(),
+                *gzip,
+                OutputFormat {
+                    metadata: Some(&metadata),
+                    container: is_container(grammar),
+                    decimals: cli.decimals,
+                    ..Default::default()
+                },
+            );
+        }
+        Some(Commands::Renormalize {
+            grammar,
+            renormalized,
+            significant_digits,
+            initial,
+            gzip,
+        }) => {
+            let mut g =
+                info_span!("reading").in_scope(|| read_grammar(grammar, initial.as_deref()));
+            match significant_digits {
+                Some(digits) => info_span!("quantizing").in_scope(|| g.quantize(*digits)),
+                None => info_span!("normalising").in_scope(|| g.normalise()),
+            }
+
+            let metadata = Metadata::new(g.initial(), recorded_transforms(grammar));
+            write_grammar(
+                g,
+                renormalized.as_deref(),
+                *gzip,
+                OutputFormat {
+                    metadata: Some(&metadata),
+                    container: is_container(grammar),
+                    decimals: cli.decimals,
+                    ..Default::default()
+                },
+            );
+        }
+        Some(Commands::Completions { shell }) => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(*shell, &mut command, name, &mut std::io::stdout());
+        }
+        Some(Commands::Oov {
+            grammar,
+            corpus,
+            bucket_size,
+        }) => {
+            let g = info_span!("reading").in_scope(|| read_grammar(grammar, None));
+
+            let mut report = OovReport::new(&g, *bucket_size as usize);
+            for line in read_lines(corpus) {
+                report.add_sentence(&line.split_whitespace().collect::<Vec<&str>>());
+            }
+
+            print!("{}", report);
+        }
+        Some(Commands::Coverage { grammar, corpus }) => {
+            let g = info_span!("reading").in_scope(|| read_grammar(grammar, None));
+            // Unknown words are scanned as the signature classes the grammar was induced with
+            let options = SignatureOptions::from_transforms(&recorded_transforms(grammar));
+            let recognizer = Recognizer::new(&g, options);
+
+            let mut stdout = std::io::stdout().lock();
+            writeln!(stdout, "line\tunknown_words\tsentence").expect("Unable to write to stdout");
+
+            let (mut sentences, mut derived) = (0, 0);
+            let mut unknown = std::collections::BTreeSet::new();
+            let lines = read_lines(corpus);
+            for (i, line) in lines.iter().enumerate() {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                if words.is_empty() {
+                    continue;
+                }
+
+                sentences += 1;
+                if recognizer.recognizes(&words) {
+                    derived += 1;
+                    continue;
+                }
+
+                let unknown_words = recognizer.unknown_words(&words);
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}",
+                    i + 1,
+                    unknown_words.join(" "),
+                    words.join(" ")
+                )
+                .expect("Unable to write to stdout");
+                unknown.extend(unknown_words);
+            }
+
+            info!(
+                "Derived {} of {} sentences, {} distinct words lack lexical rules and signature classes",
+                derived,
+                sentences,
+                unknown.len()
+            );
+        }
+        Some(Commands::Vocab {
+            treebanks,
+            sentences,
+            by_preterminal,
+            min_count,
+        }) => {
+            let mut vocabulary = Vocabulary::new(*by_preterminal);
+            if *sentences {
+                for line in read_lines(treebanks) {
+                    // Periods are only split off at the end of a sentence
+                    for sentence in split_sentences(&line) {
+                        vocabulary.add_words(&tokenize(sentence));
                     }
                 }
+            } else {
+                let trees = info_span!("reading").in_scope(|| {
+                    unweighted(read_trees(
+                        treebanks,
+                        &[],
+                        false,
+                        &mut MalformedLines::new(cli.strict),
+                        &mut Progress::new("Reading", "trees", None, false),
+                    ))
+                });
+                trees.iter().for_each(|tree| vocabulary.add_tree(tree));
             }
+            info!(
+                "Counted {} tokens of {} types, {} of which occur once",
+                vocabulary.tokens(),
+                vocabulary.types(),
+                vocabulary.singletons()
+            );
+
+            vocabulary
+                .write_tsv(&mut std::io::stdout().lock(), min_count.unwrap_or(1))
+                .expect("Unable to write to stdout");
         }
         None => {
             Cli::command()
@@ -113,3 +720,829 @@ fn main() {
         }
     }
 }
+
+/// Trees that can be preprocessed, with or without a weight
+trait TreeMut {
+    fn tree(&mut self) -> &mut ParseTree<String>;
+}
+
+impl TreeMut for ParseTree<String> {
+    fn tree(&mut self) -> &mut ParseTree<String> {
+        self
+    }
+}
+
+impl TreeMut for (ParseTree<String>, f64) {
+    fn tree(&mut self) -> &mut ParseTree<String> {
+        &mut self.0
+    }
+}
+
+/// Applies the preprocessing options to the trees. Trees that are left without words are dropped
+fn preprocess<T: TreeMut>(trees: &mut Vec<T>, preprocess_args: &PreprocessArgs) {
+    let _span = info_span!("preprocessing").entered();
+
+    // The length refers to the trees as they were read, before any words are removed
+    if let Some(max_length) = preprocess_args.max_length {
+        let read = trees.len();
+        trees.retain_mut(|tree| tree.tree().leaves().len() <= max_length);
+        info!(
+            "Dropped {} trees with more than {} words",
+            read - trees.len(),
+            max_length
+        );
+    }
+
+    match preprocess_args.punctuation {
+        Some(Punctuation::Remove) => {
+            trees.retain_mut(|tree| transform::remove_punctuation(tree.tree()))
+        }
+        Some(Punctuation::Raise) => trees
+            .iter_mut()
+            .for_each(|tree| transform::raise_punctuation(tree.tree())),
+        Some(Punctuation::Keep) | None => {}
+    }
+
+    if preprocess_args.lowercase {
+        trees
+            .iter_mut()
+            .for_each(|tree| transform::lowercase_terminals(tree.tree()));
+    }
+
+    if preprocess_args.normalize_numbers {
+        trees
+            .iter_mut()
+            .for_each(|tree| transform::normalize_numbers(tree.tree()));
+    }
+}
+
+/// Applies the transforms of transform and pipeline to the preprocessed trees
+fn transform_trees(trees: &mut [ParseTree<String>], transform_args: &TransformArgs) {
+    let _span = info_span!("transforming").entered();
+
+    for tree in trees.iter_mut() {
+        // Left-corner trees have to be restored before anything else
+        if transform_args.undo_left_corner {
+            transform::undo_left_corner(tree);
+        }
+        // Before collapsing, which would otherwise produce labels like NP+NP
+        if transform_args.remove_x_over_x {
+            transform::remove_x_over_x(tree);
+        }
+        if transform_args.collapse_unaries {
+            transform::collapse_unaries(tree);
+        }
+        if transform_args.expand_unaries {
+            transform::expand_unaries(tree);
+        }
+        if transform_args.left_corner {
+            transform::left_corner(tree);
+        }
+    }
+}
+
+/// Induces a grammar with initial ROOT from the trees, smoothed as given by the options, and exits on failure.
+/// Resumed counts are added to those of the trees. If a vocabulary threshold is given, rare words are folded
+/// into the unknown token or their signature class, and the suffixes of up to suffix_length characters of the
+/// rare words are counted beforehand if it is given. The rule counts are returned as well if keep_counts is set
+fn induce(
+    trees: impl IntoIterator<Item = (ParseTree<String>, u64)>,
+    resumed: Option<OccurenceRules>,
+    vocabulary: Option<(u32, Option<&SignatureOptions>)>,
+    suffix_length: Option<usize>,
+    smoothing_args: &SmoothingArgs,
+    signature_options: &SignatureOptions,
+    keep_counts: bool,
+) -> (Grammar, Option<OccurenceRules>, Option<OccurenceRules>) {
+    let initial = "ROOT";
+    let mut occurence_rules = match count_weighted_rules(initial, trees) {
+        Ok(occurence_rules) => occurence_rules,
+        Err(e) => {
+            error!("Error while creating PCFG from trees: {}", e);
+            exit(ExitCode::MalformedTrees);
+        }
+    };
+
+    for (rule, count) in resumed.into_iter().flatten() {
+        *occurence_rules.entry(rule).or_insert(0) += count;
+    }
+
+    let mut suffix_counts = None;
+    if let Some((threshold, options)) = vocabulary {
+        suffix_counts = suffix_length.map(|length| {
+            info_span!("counting suffixes")
+                .in_scope(|| count_suffixes(&occurence_rules, length, u64::from(threshold)))
+        });
+        occurence_rules = info_span!("folding rare words")
+            .in_scope(|| fold_rare_words(occurence_rules, u64::from(threshold), options));
+    }
+
+    let counts = keep_counts.then(|| occurence_rules.clone());
+    let smoothing = smoothing(smoothing_args, signature_options);
+    let g =
+        Grammar::from_occurence_rules(initial.to_string(), occurence_rules, smoothing.as_deref());
+
+    let cycles = g.unary_cycles();
+    if let Some(cycle) = cycles.first() {
+        warn!(
+            "The grammar has {} unary cycles (e.g. among {}), which a parser has to stop applying unaries in",
+            cycles.len(),
+            cycle.join(", ")
+        );
+    }
+
+    (g, counts, suffix_counts)
+}
+
+/// Induces a grammar with initial ROOT from trees with fractional weights, which can't be smoothed, and exits on failure
+fn induce_fractional(
+    trees: impl IntoIterator<Item = (ParseTree<String>, f64)>,
+    resumed: Option<OccurenceRules>,
+    prior: Option<&str>,
+) -> Grammar {
+    let initial = "ROOT";
+    let mut weighted_rules = match weigh_rules(initial, trees) {
+        Ok(weighted_rules) => weighted_rules,
+        Err(e) => {
+            error!("Error while creating PCFG from trees: {}", e);
+            exit(ExitCode::MalformedTrees);
+        }
+    };
+
+    for (rule, count) in resumed.into_iter().flatten() {
+        *weighted_rules.entry(rule).or_insert(0.0) += count as f64;
+    }
+    for (rule, pseudo_count) in prior.map(read_prior).into_iter().flatten() {
+        *weighted_rules.entry(rule).or_insert(0.0) += pseudo_count;
+    }
+
+    Grammar::from_weighted_rules(initial.to_string(), weighted_rules)
+}
+
+/// Reads the pseudo-counts of a prior, and exits if they can't be read
+fn read_prior(path: &str) -> WeightedRules {
+    let reader = match open(path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            error!("Error while opening {}: {}", path, e);
+            exit(ExitCode::Io);
+        }
+    };
+
+    match read_pseudo_counts(reader) {
+        Ok(pseudo_counts) => pseudo_counts,
+        Err(e) => {
+            error!("Error while reading prior {}: {}", path, e);
+            exit(ExitCode::UnreadableGrammar);
+        }
+    }
+}
+
+/// Reads the rule counts (or suffix counts) saved by an earlier induction, and exits if they can't be read
+fn read_saved_counts(path: &str) -> OccurenceRules {
+    let reader = match open(path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            error!("Error while opening {}: {}", path, e);
+            exit(ExitCode::Io);
+        }
+    };
+
+    match read_counts(reader) {
+        Ok(counts) => counts,
+        Err(e) => {
+            error!("Error while reading counts {}: {}", path, e);
+            exit(ExitCode::UnreadableGrammar);
+        }
+    }
+}
+
+fn smoothing(
+    smoothing_args: &SmoothingArgs,
+    signature_options: &SignatureOptions,
+) -> Option<Box<dyn Smoothing>> {
+    let SmoothingArgs {
+        add_k,
+        smoothing,
+        reserve_unknown,
+        open_class,
+        unknown_signatures,
+        prior,
+    } = smoothing_args;
+
+    let smoothing: Option<Box<dyn Smoothing>> = match (add_k, smoothing) {
+        (Some(k), _) => Some(Box::new(AddK {
+            k: *k,
+            unknown: reserve_unknown.clone(),
+        })),
+        (None, Some(SmoothingMethod::GoodTuring)) => Some(Box::new(GoodTuring {
+            unknown: reserve_unknown.clone().unwrap_or(String::from(UNKNOWN)),
+        })),
+        (None, Some(SmoothingMethod::WittenBell)) => Some(Box::new(WittenBell {
+            unknown: reserve_unknown.clone().unwrap_or(String::from(UNKNOWN)),
+        })),
+        (None, None) => None,
+    };
+
+    let smoothing = match smoothing {
+        Some(smoothing) if *open_class => Some(Box::new(OpenClassOnly {
+            smoothing,
+            unknown: reserve_unknown.clone().unwrap_or(String::from(UNKNOWN)),
+        }) as Box<dyn Smoothing>),
+        smoothing => smoothing,
+    };
+
+    let smoothing = match smoothing {
+        Some(smoothing) if *unknown_signatures => Some(Box::new(SignatureClasses {
+            smoothing,
+            unknown: reserve_unknown.clone().unwrap_or(String::from(UNKNOWN)),
+            options: signature_options.clone(),
+        }) as Box<dyn Smoothing>),
+        smoothing => smoothing,
+    };
+
+    match prior {
+        Some(path) => Some(Box::new(Prior {
+            smoothing,
+            pseudo_counts: read_prior(path),
+        })),
+        None => smoothing,
+    }
+}
+
+/// Writes the grammar into GRAMMAR.rules, GRAMMAR.lexicon and GRAMMAR.words if a name is given and to the stdout otherwise.
+/// With gzip, the files get a .gz extension and the stdout is compressed as well
+fn write_grammar(grammar: Grammar, name: Option<&str>, gzip: bool, format: OutputFormat) {
+    let _span = info_span!("writing").entered();
+    let default_metadata;
+    let metadata = match format.metadata {
+        Some(metadata) => metadata,
+        None => {
+            default_metadata = Metadata::new(grammar.initial(), Vec::new());
+            &default_metadata
+        }
+    };
+
+    let mut berkeley_writer = BerkeleyWriter::from_grammar(&grammar);
+    if let Some(decimals) = format.decimals {
+        berkeley_writer = berkeley_writer.decimals(decimals);
+    }
+    if let Some(exact_rules) = format.fractions {
+        berkeley_writer = berkeley_writer.fractions(exact_rules);
+    }
+    let writer: Box<dyn GrammarFormatWriter> = if format.container {
+        Box::new(ContainerWriter {
+            writer: berkeley_writer,
+            metadata,
+        })
+    } else {
+        if format.grouped_lexicon {
+            berkeley_writer = berkeley_writer.grouped_lexicon(format.lexicon_counts);
+        }
+        if let Some(counts) = format.word_counts {
+            berkeley_writer = berkeley_writer.word_counts(counts);
+        }
+        Box::new(berkeley_writer)
+    };
+
+    if let Some(gname) = name {
+        for extension in writer.extensions() {
+            let path = format!("{}.{}", gname, extension);
+            let mut file = match Output::create(&path, gzip) {
+                Ok(file) => file,
+                Err(e) => {
+                    error!("Error while creating {}: {}", path, e);
+                    exit(ExitCode::Io);
+                }
+            };
+            if let Err(e) = writer
+                .write_part(extension, &mut file)
+                .and_then(|_| file.finish().map(|_| ()))
+            {
+                error!("Couldn't write {}: {}", path, e);
+                exit(ExitCode::Io);
+            }
+        }
+
+        if !format.container {
+            // Small enough to never be gzipped, so that it can be read without tools
+            let path = format!("{}.meta", gname);
+            let mut meta = match std::fs::File::create(&path) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    error!("Error while creating {}: {}", path, e);
+                    exit(ExitCode::Io);
+                }
+            };
+            if let Err(e) = write_metadata(metadata, &mut meta) {
+                error!("Couldn't write {}: {}", path, e);
+                exit(ExitCode::Io);
+            }
+        }
+
+        let sidecars = [
+            ("counts", format.rule_counts),
+            ("suffixes", format.suffix_counts),
+        ];
+        for (extension, counts) in sidecars {
+            let counts = match counts {
+                Some(counts) => counts,
+                None => continue,
+            };
+            let path = format!("{}.{}", gname, extension);
+            let written = Output::create(&path, gzip).and_then(|mut file| {
+                write_counts(counts, &mut file)?;
+                file.finish().map(|_| ())
+            });
+            if let Err(e) = written {
+                error!("Couldn't write {}: {}", path, e);
+                exit(ExitCode::Io);
+            }
+        }
+    } else {
+        let mut stdout = Output::new(std::io::stdout(), gzip);
+        writer
+            .write_stream(&mut stdout)
+            .expect("Unable to write to stdout");
+        stdout.finish().expect("Unable to write to stdout");
+    }
+}
+
+/// Writes the trees to the stdout, one per line in PTB format or as numbered sentences in NEGRA export format
+fn write_trees<'a>(trees: impl IntoIterator<Item = &'a ParseTree<String>>, format: TreeFormat) {
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+    for (i, tree) in trees.into_iter().enumerate() {
+        match format {
+            TreeFormat::Ptb => writeln!(stdout, "{}", tree),
+            TreeFormat::Negra => write!(stdout, "{}", negra::to_negra(tree, i + 1)),
+        }
+        .expect("Unable to write to stdout");
+    }
+    stdout.flush().expect("Unable to write to stdout");
+}
+
+/// Format of the grammar files, i.e. the Berkeley files with the given lexicon and words or a container
+#[derive(Default)]
+struct OutputFormat<'a> {
+    /// Groups the lexicon by word like the Berkeley parser
+    grouped_lexicon: bool,
+    /// Counts that are appended to the entries of the grouped lexicon
+    lexicon_counts: Option<&'a OccurenceRules>,
+    /// Counts whose sum per word is appended to the words
+    word_counts: Option<&'a OccurenceRules>,
+    /// Counts that are written into GRAMMAR.counts
+    rule_counts: Option<&'a OccurenceRules>,
+    /// Suffix counts of the rare words (see count_suffixes()) that are written into GRAMMAR.suffixes
+    suffix_counts: Option<&'a OccurenceRules>,
+    /// Header of the GRAMMAR.meta sidecar or the container, which only records the initial and the version if it isn't given
+    metadata: Option<&'a Metadata>,
+    /// Writes a single GRAMMAR.pcfg container instead of the three files and the sidecar
+    container: bool,
+    /// Number of decimals of the probabilities
+    decimals: Option<usize>,
+    /// Exact probabilities that are printed as fractions instead of as decimals
+    fractions: Option<&'a ExactRules>,
+}
+
+/// Creates dir if it is missing and makes sure that files can be created in it,
+/// so that a long induction doesn't fail only when writing the grammar
+fn prepare_output_dir(dir: &str) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+
+    let probe = Path::new(dir).join(format!(".pcfg_tool-{}", std::process::id()));
+    std::fs::File::create(&probe)?;
+    std::fs::remove_file(&probe)
+}
+
+/// Reads the grammar in the format detected by Grammar::load() and exits if it is missing or malformed
+fn read_grammar(name: &str, initial: Option<&str>) -> Grammar {
+    match Grammar::load(name, initial) {
+        Ok(grammar) => grammar,
+        Err(e) => {
+            error!("Error while reading grammar {}: {}", name, e);
+            exit(ExitCode::UnreadableGrammar);
+        }
+    }
+}
+
+/// Reads the transforms recorded in the metadata of the grammar (see Grammar::load_metadata()),
+/// which are empty if it has none, and exits if the metadata is malformed
+fn recorded_transforms(name: &str) -> Vec<String> {
+    match Grammar::load_metadata(name) {
+        Ok(metadata) => metadata
+            .map(|metadata| metadata.transforms)
+            .unwrap_or_default(),
+        Err(e) => {
+            error!("Error while reading grammar {}: {}", name, e);
+            exit(ExitCode::UnreadableGrammar);
+        }
+    }
+}
+
+/// Whether the grammar is read from a container, so that a rewritten grammar is written as one as well
+fn is_container(name: &str) -> bool {
+    matches!(GrammarSource::detect(name), GrammarSource::Container(_))
+}
+
+/// Path of a file that induce writes beside the grammar, e.g. wsj.counts for the Berkeley files wsj.rules etc.
+/// and for the container wsj.pcfg.gz
+fn sidecar(name: &str, extension: &str) -> String {
+    match GrammarSource::detect(name) {
+        GrammarSource::Berkeley(name) => format!("{}.{}", name, extension),
+        GrammarSource::Container(path) => {
+            let without_gz = path.strip_suffix(".gz").unwrap_or(&path);
+            let name = without_gz.strip_suffix(".pcfg").unwrap_or(without_gz);
+            format!("{}.{}", name, extension)
+        }
+    }
+}
+
+/// The lexical rules a word is scanned with (see unknown::scanned_rules()), where the suffix model is read from
+/// GRAMMAR.suffixes if the grammar was induced with --oov-model suffix, and exits if there are none
+fn scanned_rules(grammar: &Grammar, name: &str, word: &str) -> Vec<(Rule, f64)> {
+    let transforms = recorded_transforms(name);
+    let options = SignatureOptions::from_transforms(&transforms);
+    let suffix_model = match transforms.contains(&String::from("oov-model=suffix")) {
+        true => {
+            let suffix_counts = read_saved_counts(&sidecar(name, "suffixes"));
+            Some(SuffixModel::estimate(&suffix_counts))
+        }
+        false => None,
+    };
+
+    match unknown::scanned_rules(grammar, word, &options, suffix_model.as_ref()) {
+        Some(rules) if !rules.is_empty() => rules,
+        _ => {
+            error!(
+                "{} has no lexical rule, signature class or suffix in grammar {}",
+                Escaped(word),
+                name
+            );
+            exit(ExitCode::NotFound);
+        }
+    }
+}
+
+/// Names the preprocessing of induce (or pipeline, whose --unknown-threshold is a vocab-threshold) for the header of a .pcfg container,
+/// e.g. lowercase or vocab-threshold=2.
+/// The signature options are given if any signature classes were used, and only those that differ from the default are named
+fn applied_transforms(
+    preprocess_args: &PreprocessArgs,
+    vocab_threshold: Option<u32>,
+    signatures: bool,
+    signature_options: Option<&SignatureOptions>,
+    oov_model: Option<OovModel>,
+) -> Vec<String> {
+    let PreprocessArgs {
+        max_length,
+        punctuation,
+        lowercase,
+        normalize_numbers,
+    } = preprocess_args;
+
+    let mut transforms = Vec::new();
+    if let Some(n) = max_length {
+        transforms.push(format!("max-length={}", n));
+    }
+    match punctuation {
+        Some(Punctuation::Remove) => transforms.push(String::from("punctuation=remove")),
+        Some(Punctuation::Raise) => transforms.push(String::from("punctuation=raise")),
+        Some(Punctuation::Keep) | None => (),
+    }
+    if *lowercase {
+        transforms.push(String::from("lowercase"));
+    }
+    if *normalize_numbers {
+        transforms.push(String::from("normalize-numbers"));
+    }
+    if let Some(n) = vocab_threshold {
+        transforms.push(format!("vocab-threshold={}", n));
+    }
+    if signatures {
+        transforms.push(String::from("signatures"));
+    }
+    if let Some(options) = signature_options {
+        transforms.extend(options.transforms());
+    }
+    if let Some(OovModel::Suffix) = oov_model {
+        transforms.push(String::from("oov-model=suffix"));
+    }
+
+    transforms
+}
+
+/// Reads one PTB tree per line from the treebank files and sources, or from the stdin if there are none and skip_stdin isn't set.
+/// The input may be gzipped and each tree may be preceded by its weight and a tab, which is 1 otherwise
+/// and multiplied by the weight of its source.
+/// Malformed lines are skipped and summarised in the end, or exited on if strict is set
+fn read_trees(
+    treebanks: &[String],
+    sources: &[Source],
+    skip_stdin: bool,
+    malformed: &mut MalformedLines,
+    progress: &mut Progress,
+) -> Vec<(ParseTree<String>, f64)> {
+    let mut trees = Vec::new();
+
+    if treebanks.is_empty() && sources.is_empty() && !skip_stdin {
+        let stdin = match decompress(std::io::stdin().lock()) {
+            Ok(stdin) => stdin,
+            Err(e) => {
+                error!("Error while reading stdin: {}", e);
+                exit(ExitCode::Io);
+            }
+        };
+
+        read_treebank(stdin, None, &mut trees, malformed, progress);
+    }
+
+    for treebank in treebanks {
+        match open(treebank) {
+            Ok(reader) => read_treebank(reader, Some(treebank), &mut trees, malformed, progress),
+            Err(e) => {
+                error!("Error while opening {}: {}", treebank, e);
+                exit(ExitCode::Io);
+            }
+        }
+    }
+
+    for source in sources {
+        let start = trees.len();
+        match open(&source.path) {
+            Ok(reader) => {
+                read_treebank(reader, Some(&source.path), &mut trees, malformed, progress)
+            }
+            Err(e) => {
+                error!("Error while opening {}: {}", source.path, e);
+                exit(ExitCode::Io);
+            }
+        }
+
+        for (_tree, weight) in trees[start..].iter_mut() {
+            *weight *= source.weight;
+        }
+    }
+
+    if malformed.count > 0 {
+        malformed.summarise(trees.len());
+    }
+
+    trees
+}
+
+/// Reads the lines of the (possibly gzipped) files, or of the stdin if there are none
+fn read_lines(files: &[String]) -> Vec<String> {
+    let readers: Vec<(Box<dyn BufRead>, &str)> = if files.is_empty() {
+        match decompress(std::io::stdin().lock()) {
+            Ok(stdin) => vec![(stdin, "stdin")],
+            Err(e) => {
+                error!("Error while reading stdin: {}", e);
+                exit(ExitCode::Io);
+            }
+        }
+    } else {
+        files
+            .iter()
+            .map(|file| match open(file) {
+                Ok(reader) => (reader, file.as_str()),
+                Err(e) => {
+                    error!("Error while opening {}: {}", file, e);
+                    exit(ExitCode::Io);
+                }
+            })
+            .collect()
+    };
+
+    let mut lines = Vec::new();
+    for (reader, name) in readers {
+        for line in reader.lines() {
+            match line {
+                Ok(line) => lines.push(line),
+                Err(e) => {
+                    error!("Error while reading {}: {}", name, e);
+                    exit(ExitCode::Io);
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// Drops the weights of trees for commands that don't count them, with a warning if there are any
+fn unweighted(trees: Vec<(ParseTree<String>, f64)>) -> Vec<ParseTree<String>> {
+    let weighted = trees
+        .iter()
+        .filter(|(_tree, weight)| *weight != 1.0)
+        .count();
+    if weighted > 0 {
+        warn!(
+            "Ignoring the weights of {} trees, which are only counted by induce",
+            weighted
+        );
+    }
+
+    trees.into_iter().map(|(tree, _weight)| tree).collect()
+}
+
+/// How many of the malformed lines are listed in the summary
+const MALFORMED_EXAMPLES: usize = 5;
+
+/// Keeps track of the skipped malformed lines of the treebanks, whose warnings are replaced by a summary with --quiet
+struct MalformedLines {
+    /// Exits on the first malformed line instead
+    strict: bool,
+    count: usize,
+    /// The first few malformed lines, e.g. "line 3 in wsj.mrg"
+    examples: Vec<String>,
+}
+
+impl MalformedLines {
+    fn new(strict: bool) -> Self {
+        MalformedLines {
+            strict,
+            count: 0,
+            examples: Vec::new(),
+        }
+    }
+
+    /// Warns about the malformed line, or exits if strict is set. The source is empty for the stdin
+    fn add(&mut self, line: &str, number: usize, source: &str, e: ParseError) {
+        if self.strict {
+            error!(
+                "Error while parsing tree {} at line {}, column {}{}: expected {}",
+                line, number, e.column, source, e.expected
+            );
+            exit(ExitCode::MalformedTrees);
+        }
+
+        warn!(
+            "Skipping malformed tree {} at line {}, column {}{}: expected {}",
+            line, number, e.column, source, e.expected
+        );
+
+        self.count += 1;
+        if self.examples.len() < MALFORMED_EXAMPLES {
+            self.examples.push(format!("line {}{}", number, source));
+        }
+    }
+
+    /// Logged as an error, so that it isn't hidden by --quiet
+    fn summarise(&self, trees: usize) {
+        let more = match self.count > self.examples.len() {
+            true => ", ...",
+            false => "",
+        };
+        error!(
+            "Read {} trees and skipped {} malformed lines ({}{})",
+            trees,
+            self.count,
+            self.examples.join(", "),
+            more
+        );
+    }
+}
+
+/// Reads all (possibly gzipped) .mrg files below dir in sorted order.
+/// Their trees may span multiple lines and have an unlabelled outer bracket, which becomes ROOT
+fn read_treebank_dir(dir: &str, trees: &mut Vec<ParseTree<String>>, progress: &mut Progress) {
+    let mut files = Vec::new();
+    if let Err(e) = collect_mrg_files(Path::new(dir), &mut files) {
+        error!("Error while reading directory {}: {}", dir, e);
+        exit(ExitCode::Io);
+    }
+    files.sort();
+
+    for file in files {
+        let name = file.display().to_string();
+
+        let mut content = String::new();
+        if let Err(e) = open(&name).and_then(|mut reader| reader.read_to_string(&mut content)) {
+            error!("Error while reading {}: {}", name, e);
+            exit(ExitCode::Io);
+        }
+
+        parse_treebank(&content, &name, trees, progress);
+    }
+}
+
+/// Reads whole (possibly gzipped) treebanks from the files, or from the stdin if there are none,
+/// where trees may span multiple lines like those of read_treebank_dir()
+fn read_multiline_trees(treebanks: &[String], progress: &mut Progress) -> Vec<ParseTree<String>> {
+    let mut trees = Vec::new();
+
+    if treebanks.is_empty() {
+        let mut content = String::new();
+        if let Err(e) = decompress(std::io::stdin().lock())
+            .and_then(|mut stdin| stdin.read_to_string(&mut content))
+        {
+            error!("Error while reading stdin: {}", e);
+            exit(ExitCode::Io);
+        }
+
+        parse_treebank(&content, "stdin", &mut trees, progress);
+    }
+
+    for treebank in treebanks {
+        let mut content = String::new();
+        if let Err(e) = open(treebank).and_then(|mut reader| reader.read_to_string(&mut content)) {
+            error!("Error while reading {}: {}", treebank, e);
+            exit(ExitCode::Io);
+        }
+
+        parse_treebank(&content, treebank, &mut trees, progress);
+    }
+
+    trees
+}
+
+/// Parses the trees of a whole treebank and exits with the line of the first malformed tree
+fn parse_treebank(
+    content: &str,
+    name: &str,
+    trees: &mut Vec<ParseTree<String>>,
+    progress: &mut Progress,
+) {
+    match PTBParser::parse_treebank(content, "ROOT") {
+        Ok(parsed) => {
+            progress.add(parsed.len());
+            trees.extend(parsed);
+        }
+        Err(e) => {
+            error!("Error while parsing trees in {} at {}", name, e);
+            exit(ExitCode::MalformedTrees);
+        }
+    }
+}
+
+/// Reads the sentences of a (possibly gzipped) TIGER-XML corpus and exits if it is malformed
+fn read_tiger_corpus(
+    corpus: &str,
+    crossing_branches: CrossingBranches,
+    trees: &mut Vec<ParseTree<String>>,
+    progress: &mut Progress,
+) {
+    let mut content = String::new();
+    if let Err(e) = open(corpus).and_then(|mut reader| reader.read_to_string(&mut content)) {
+        error!("Error while reading {}: {}", corpus, e);
+        exit(ExitCode::Io);
+    }
+
+    match read_tiger(&content, crossing_branches) {
+        Ok(parsed) => {
+            progress.add(parsed.len());
+            trees.extend(parsed);
+        }
+        Err(e) => {
+            error!("Error while reading TIGER-XML corpus {}: {}", corpus, e);
+            exit(ExitCode::MalformedTrees);
+        }
+    }
+}
+
+fn collect_mrg_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            collect_mrg_files(&path, files)?;
+        } else if path
+            .to_str()
+            .is_some_and(|name| name.ends_with(".mrg") || name.ends_with(".mrg.gz"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_treebank(
+    reader: Box<dyn BufRead>,
+    name: Option<&str>,
+    trees: &mut Vec<(ParseTree<String>, f64)>,
+    malformed: &mut MalformedLines,
+    progress: &mut Progress,
+) {
+    // Errors on the stdin don't need a source
+    let source = match name {
+        Some(name) => format!(" in {}", name),
+        None => String::new(),
+    };
+
+    for (i, line) in BufRead::lines(reader).enumerate() {
+        match line {
+            Ok(line) => match PTBParser::parse_weighted(&line) {
+                Ok(weighted_tree) => {
+                    trees.push(weighted_tree);
+                    progress.inc();
+                }
+                Err(e) => malformed.add(&line, i + 1, &source, e),
+            },
+            Err(e) => {
+                error!("Error{} on line {}: {}", source, i, e);
+                exit(ExitCode::Io);
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::fmt::Write;
+
+use crate::ptb::{Descendants, ParseTree};
+
+/// Number of the first nonterminal node, since smaller numbers refer to the words
+const FIRST_NODE: usize = 500;
+
+/// Writes the tree as a sentence of the NEGRA export format (version 3), i.e. one word TAG MORPH EDGE PARENT line per word
+/// followed by one #NODE LABEL MORPH EDGE PARENT line per nonterminal, enclosed by #BOS id and #EOS id.
+/// The nonterminals are numbered from 500 in postorder, the root is attached to the virtual root 0,
+/// and morphology and edge labels are unknown (--)
+pub fn to_negra(tree: &ParseTree<String>, id: usize) -> String {
+    let mut words = Vec::new();
+    let mut nodes = Vec::new();
+    number_nodes(tree, 0, &mut words, &mut nodes);
+
+    let mut negra = format!("#BOS {}\n", id);
+    for (word, tag, parent) in words {
+        writeln!(negra, "{}\t{}\t--\t--\t{}", word, tag, parent)
+            .expect("Writing to a String works");
+    }
+    for (number, (label, parent)) in nodes.into_iter().enumerate() {
+        writeln!(
+            negra,
+            "#{}\t{}\t--\t--\t{}",
+            FIRST_NODE + number,
+            label,
+            parent
+        )
+        .expect("Writing to a String works");
+    }
+    writeln!(negra, "#EOS {}", id).expect("Writing to a String works");
+
+    negra
+}
+
+/// Placeholder parent of the subtrees of a node that isn't numbered yet
+const UNNUMBERED: usize = usize::MAX;
+
+/// Collects the (word, tag, parent) of the words and the (label, parent) of the nonterminals.
+/// A node's number is only known once its subtrees were numbered, so they are collected with the placeholder parent first
+fn number_nodes<'a>(
+    tree: &'a ParseTree<String>,
+    parent: usize,
+    words: &mut Vec<(&'a str, &'a str, usize)>,
+    nodes: &mut Vec<(&'a str, usize)>,
+) {
+    match &tree.descendants {
+        Descendants::Atom(word) => words.push((word, &tree.root, parent)),
+        Descendants::Expressions(trees) => {
+            let (first_word, first_node) = (words.len(), nodes.len());
+            for tree in trees {
+                number_nodes(tree, UNNUMBERED, words, nodes);
+            }
+
+            // Deeper entries were already patched by their own parents, so only the direct children are left
+            let number = FIRST_NODE + nodes.len();
+            for (_word, _tag, word_parent) in words[first_word..].iter_mut() {
+                if *word_parent == UNNUMBERED {
+                    *word_parent = number;
+                }
+            }
+            for (_label, node_parent) in nodes[first_node..].iter_mut() {
+                if *node_parent == UNNUMBERED {
+                    *node_parent = number;
+                }
+            }
+
+            nodes.push((&tree.root, parent));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn writes_export_format() {
+        let tree = PTBParser::parse("(ROOT (S (NP (NNP Pierre)) (VP (VBD slept))))")
+            .expect("This should be parsable");
+
+        assert_eq!(
+            to_negra(&tree, 7),
+            "#BOS 7\nPierre\tNNP\t--\t--\t500\nslept\tVBD\t--\t--\t501\n#500\tNP\t--\t--\t502\n#501\tVP\t--\t--\t502\n#502\tS\t--\t--\t503\n#503\tROOT\t--\t--\t0\n#EOS 7\n"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+};
+
+use crate::{induce::PCFGGrammar, Grammar, Probability, Terminal};
+
+/// Out-of-vocabulary words among some sentences, i.e. words that the lexicon of a grammar lacks
+#[derive(Debug, Default, PartialEq)]
+pub struct OovCounts {
+    pub sentences: usize,
+    pub tokens: usize,
+    pub oov_tokens: usize,
+    types: HashSet<String>,
+    oov_types: HashSet<String>,
+}
+
+impl OovCounts {
+    fn add(&mut self, words: &[&str], known: &HashSet<Terminal>) {
+        self.sentences += 1;
+        self.tokens += words.len();
+
+        for word in words {
+            self.types.insert(word.to_string());
+            if !known.contains(*word) {
+                self.oov_tokens += 1;
+                self.oov_types.insert(word.to_string());
+            }
+        }
+    }
+
+    pub fn types(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn oov_types(&self) -> usize {
+        self.oov_types.len()
+    }
+
+    /// Share of the tokens that are out of vocabulary, which is 0 without tokens
+    pub fn token_rate(&self) -> Probability {
+        rate(self.oov_tokens, self.tokens)
+    }
+
+    /// Share of the distinct words that are out of vocabulary, which is 0 without tokens
+    pub fn type_rate(&self) -> Probability {
+        rate(self.oov_types(), self.types())
+    }
+}
+
+fn rate(part: usize, whole: usize) -> Probability {
+    match whole {
+        0 => 0.0,
+        whole => part as Probability / whole as Probability,
+    }
+}
+
+/// OOV rates of a corpus overall and per bucket of sentence lengths (e.g. 1-10, 11-20)
+pub struct OovReport {
+    known: HashSet<Terminal>,
+    bucket_size: usize,
+    pub total: OovCounts,
+    /// The counts of the sentences with (bucket - 1) * bucket_size + 1 to bucket * bucket_size words
+    pub buckets: BTreeMap<usize, OovCounts>,
+}
+
+impl OovReport {
+    /// The vocabulary are the terminals of the grammar's lexicon
+    pub fn new(grammar: &Grammar, bucket_size: usize) -> Self {
+        OovReport {
+            known: grammar.terminals().into_iter().collect(),
+            bucket_size: bucket_size.max(1),
+            total: OovCounts::default(),
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    /// Counts the words of a tokenized sentence. Empty sentences are skipped
+    pub fn add_sentence(&mut self, words: &[&str]) {
+        if words.is_empty() {
+            return;
+        }
+
+        let bucket = (words.len() - 1) / self.bucket_size + 1;
+        self.total.add(words, &self.known);
+        self.buckets
+            .entry(bucket)
+            .or_default()
+            .add(words, &self.known);
+    }
+}
+
+/// Prints a TSV table with one row per bucket of sentence lengths and a last row for all sentences
+impl fmt::Display for OovReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "length\tsentences\ttokens\toov_tokens\ttoken_rate\ttypes\toov_types\ttype_rate"
+        )?;
+
+        let row = |f: &mut fmt::Formatter<'_>, length: &str, counts: &OovCounts| {
+            writeln!(
+                f,
+                "{}\t{}\t{}\t{}\t{:.4}\t{}\t{}\t{:.4}",
+                length,
+                counts.sentences,
+                counts.tokens,
+                counts.oov_tokens,
+                counts.token_rate(),
+                counts.types(),
+                counts.oov_types(),
+                counts.type_rate()
+            )
+        };
+        for (bucket, counts) in self.buckets.iter() {
+            let length = format!(
+                "{}-{}",
+                (bucket - 1) * self.bucket_size + 1,
+                bucket * self.bucket_size
+            );
+            row(f, &length, counts)?;
+        }
+
+        row(f, "all", &self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn reports_oov_rates_per_length() {
+        let tree = PTBParser::parse("(S (NP (NNP John)) (VP (VBZ sleeps)))")
+            .expect("This should be parsable");
+        let grammar =
+            Grammar::from_parse_trees("S".to_string(), vec![tree]).expect("S is the root");
+
+        let mut report = OovReport::new(&grammar, 2);
+        report.add_sentence(&["John", "sleeps"]);
+        report.add_sentence(&["Mary", "sleeps", "well"]);
+        report.add_sentence(&[]);
+
+        assert_eq!(report.total.sentences, 2);
+        assert_eq!((report.total.tokens, report.total.oov_tokens), (5, 2));
+        assert_eq!((report.total.types(), report.total.oov_types()), (4, 2));
+        assert_eq!(report.buckets[&2].token_rate(), 2.0 / 3.0);
+
+        assert_eq!(
+            report.to_string(),
+            "length\tsentences\ttokens\toov_tokens\ttoken_rate\ttypes\toov_types\ttype_rate\n\
+             1-2\t1\t2\t0\t0.0000\t2\t0\t0.0000\n\
+             3-4\t1\t3\t2\t0.6667\t3\t2\t0.6667\n\
+             all\t2\t5\t2\t0.4000\t4\t2\t0.5000\n"
+        );
+    }
+}
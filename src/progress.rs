@@ -0,0 +1,191 @@
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+/// Minimum time between two reports, so that the stderr isn't flooded
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reports the number of processed items, their rate and (if the total is known) an ETA on the stderr
+pub struct Progress {
+    stage: String,
+    unit: String,
+    total: Option<usize>,
+    processed: usize,
+    visible: bool,
+    start: Instant,
+    last_report: Instant,
+}
+
+impl Progress {
+    /// Nothing is printed unless visible is set
+    pub fn new(stage: &str, unit: &str, total: Option<usize>, visible: bool) -> Self {
+        let now = Instant::now();
+
+        Self {
+            stage: stage.to_string(),
+            unit: unit.to_string(),
+            total,
+            processed: 0,
+            visible,
+            start: now,
+            last_report: now,
+        }
+    }
+
+    pub fn inc(&mut self) {
+        self.add(1);
+    }
+
+    pub fn add(&mut self, processed: usize) {
+        self.processed += processed;
+
+        if self.visible && self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.last_report = Instant::now();
+            self.report();
+        }
+    }
+
+    /// Prints the final report and ends its line
+    pub fn finish(&mut self) {
+        if self.visible {
+            self.report();
+            eprintln!();
+            // Reports of the next stage must not overwrite this one
+            self.visible = false;
+        }
+    }
+
+    /// Overwrites the current line of the stderr
+    fn report(&self) {
+        let mut stderr = std::io::stderr().lock();
+        // Progress is only informational, so a closed stderr must not abort the run
+        let _ = write!(
+            stderr,
+            "\r{}",
+            self.line(self.start.elapsed().as_secs_f64())
+        );
+        let _ = stderr.flush();
+    }
+
+    fn line(&self, elapsed: f64) -> String {
+        let rate = if elapsed > 0.0 {
+            self.processed as f64 / elapsed
+        } else {
+            0.0
+        };
+
+        let mut line = match self.total {
+            Some(total) => format!(
+                "{}: {}/{} {} ({:.0} {}/s",
+                self.stage, self.processed, total, self.unit, rate, self.unit
+            ),
+            None => format!(
+                "{}: {} {} ({:.0} {}/s",
+                self.stage, self.processed, self.unit, rate, self.unit
+            ),
+        };
+
+        if let (Some(total), true) = (self.total, rate > 0.0) {
+            let remaining = total.saturating_sub(self.processed) as f64 / rate;
+            line.push_str(&format!(", ETA {}", format_duration(remaining)));
+        }
+        line.push(')');
+
+        line
+    }
+}
+
+/// Reports the progress while the inner iterator is consumed
+pub struct ProgressIter<I> {
+    inner: I,
+    progress: Progress,
+}
+
+impl<I: Iterator> Iterator for ProgressIter<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.inner.next() {
+            Some(item) => {
+                self.progress.inc();
+                Some(item)
+            }
+            None => {
+                self.progress.finish();
+                None
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+pub trait ProgressIterator: Iterator + Sized {
+    /// Reports the progress of the iteration, where the total is taken from an exact size hint
+    fn progress(self, stage: &str, unit: &str, visible: bool) -> ProgressIter<Self> {
+        let total = match self.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        };
+
+        ProgressIter {
+            inner: self,
+            progress: Progress::new(stage, unit, total, visible),
+        }
+    }
+}
+
+impl<I: Iterator> ProgressIterator for I {}
+
+/// Formats seconds as h:mm:ss or m:ss
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(0.2), "0:00");
+        assert_eq!(format_duration(75.0), "1:15");
+        assert_eq!(format_duration(2.0 * 3600.0 + 61.0), "2:01:01");
+    }
+
+    #[test]
+    fn reports_rate_and_eta() {
+        let mut progress = Progress::new("Counting", "trees", Some(1000), false);
+        progress.add(250);
+
+        assert_eq!(
+            progress.line(10.0),
+            "Counting: 250/1000 trees (25 trees/s, ETA 0:30)"
+        );
+
+        let mut progress = Progress::new("Reading", "trees", None, false);
+        progress.add(250);
+
+        assert_eq!(progress.line(10.0), "Reading: 250 trees (25 trees/s)");
+    }
+
+    #[test]
+    fn passes_through_items() {
+        let items: Vec<u32> = vec![1, 2, 3]
+            .into_iter()
+            .progress("Counting", "trees", false)
+            .collect();
+
+        assert_eq!(items, vec![1, 2, 3]);
+    }
+}
@@ -1,10 +1,13 @@
 use core::fmt;
+use std::collections::VecDeque;
 
 use nom::branch::alt;
-use nom::bytes::complete::{is_not, tag};
-use nom::character::complete::multispace0;
-use nom::multi::many1;
-use nom::sequence::delimited;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, multispace0};
+use nom::combinator::{all_consuming, cut, peek};
+use nom::error::ErrorKind;
+use nom::multi::{many0, many1};
+use nom::sequence::{delimited, preceded, terminated};
 use nom::{IResult, Parser};
 
 #[derive(Debug, PartialEq, Clone)]
@@ -13,21 +16,319 @@ pub struct ParseTree<T> {
     pub descendants: Descendants<T>,
 }
 
+impl<T> ParseTree<T> {
+    /// The subtrees below the root, which are empty for a preterminal
+    fn children(&self) -> &[ParseTree<T>] {
+        match &self.descendants {
+            Descendants::Atom(_) => &[],
+            Descendants::Expressions(trees) => trees,
+        }
+    }
+
+    /// Visits every node before its subtrees, from left to right
+    pub fn iter_preorder(&self) -> Preorder<'_, T> {
+        Preorder { stack: vec![self] }
+    }
+
+    /// Visits every node after its subtrees, from left to right
+    pub fn iter_postorder(&self) -> Postorder<'_, T> {
+        Postorder {
+            stack: vec![(self, false)],
+        }
+    }
+
+    /// Visits every subtree including the tree itself level by level, i.e. breadth-first
+    pub fn iter_subtrees(&self) -> Subtrees<'_, T> {
+        Subtrees {
+            queue: VecDeque::from([self]),
+        }
+    }
+
+    /// Replaces every label (but no terminal) by f(label)
+    pub fn map_labels(self, mut f: impl FnMut(T) -> T) -> Self {
+        self.map_with(&mut f, &mut |terminal| terminal)
+    }
+
+    /// Replaces every terminal by f(terminal)
+    pub fn map_terminals(self, mut f: impl FnMut(T) -> T) -> Self {
+        self.map_with(&mut |label| label, &mut f)
+    }
+
+    fn map_with(self, label: &mut impl FnMut(T) -> T, terminal: &mut impl FnMut(T) -> T) -> Self {
+        ParseTree {
+            root: label(self.root),
+            descendants: match self.descendants {
+                Descendants::Atom(atom) => Descendants::Atom(terminal(atom)),
+                Descendants::Expressions(trees) => Descendants::Expressions(
+                    trees
+                        .into_iter()
+                        .map(|tree| tree.map_with(label, terminal))
+                        .collect(),
+                ),
+            },
+        }
+    }
+
+    /// Calls f on every node before descending into its subtrees, so f may restructure the node
+    /// (e.g. insert or remove levels) and the resulting subtrees are visited afterwards
+    pub fn transform(&mut self, mut f: impl FnMut(&mut ParseTree<T>)) {
+        self.transform_with(&mut f);
+    }
+
+    fn transform_with(&mut self, f: &mut impl FnMut(&mut ParseTree<T>)) {
+        f(self);
+
+        if let Descendants::Expressions(trees) = &mut self.descendants {
+            for tree in trees {
+                tree.transform_with(f);
+            }
+        }
+    }
+}
+
+pub struct Preorder<'a, T> {
+    stack: Vec<&'a ParseTree<T>>,
+}
+
+impl<'a, T> Iterator for Preorder<'a, T> {
+    type Item = &'a ParseTree<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.stack.pop()?;
+        self.stack.extend(tree.children().iter().rev());
+
+        Some(tree)
+    }
+}
+
+pub struct Postorder<'a, T> {
+    /// Each node is pushed unexpanded first and yielded once its subtrees were pushed above it
+    stack: Vec<(&'a ParseTree<T>, bool)>,
+}
+
+impl<'a, T> Iterator for Postorder<'a, T> {
+    type Item = &'a ParseTree<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (tree, expanded) = self.stack.pop()?;
+            if expanded || tree.children().is_empty() {
+                return Some(tree);
+            }
+
+            self.stack.push((tree, true));
+            self.stack
+                .extend(tree.children().iter().rev().map(|tree| (tree, false)));
+        }
+    }
+}
+
+pub struct Subtrees<'a, T> {
+    queue: VecDeque<&'a ParseTree<T>>,
+}
+
+impl<'a, T> Iterator for Subtrees<'a, T> {
+    type Item = &'a ParseTree<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tree = self.queue.pop_front()?;
+        self.queue.extend(tree.children());
+
+        Some(tree)
+    }
+}
+
 impl ParseTree<String> {
     fn print(&self) -> String {
         match &self.descendants {
-            Descendants::Atom(atom) => format!("({} {})", self.root, atom),
+            Descendants::Atom(atom) => {
+                format!("({} {})", EscapedLabel(&self.root), EscapedLabel(atom))
+            }
             Descendants::Expressions(trees) => {
                 let tree_list = trees
                     .iter()
-                    .map(|tree| Self::print(tree))
+                    .map(Self::print)
                     .collect::<Vec<String>>()
                     .join(" ");
 
-                format!("({} {})", self.root, tree_list)
+                format!("({} {})", EscapedLabel(&self.root), tree_list)
+            }
+        }
+    }
+
+    /// Prints the tree with one subtree per line, where each level is indented by indent spaces.
+    /// Preterminals are kept on one line together with their word
+    pub fn pretty(&self, indent: usize) -> String {
+        let mut lines = Vec::new();
+        self.pretty_lines(indent, 0, &mut lines);
+
+        lines.join("\n")
+    }
+
+    fn pretty_lines(&self, indent: usize, depth: usize, lines: &mut Vec<String>) {
+        let padding = " ".repeat(indent * depth);
+
+        match &self.descendants {
+            Descendants::Atom(atom) => lines.push(format!(
+                "{}({} {})",
+                padding,
+                EscapedLabel(&self.root),
+                EscapedLabel(atom)
+            )),
+            Descendants::Expressions(trees) => {
+                lines.push(format!("{}({}", padding, EscapedLabel(&self.root)));
+                for tree in trees {
+                    tree.pretty_lines(indent, depth + 1, lines);
+                }
+
+                // The closing bracket belongs to the last subtree's line
+                if let Some(last) = lines.last_mut() {
+                    last.push(')');
+                }
+            }
+        }
+    }
+
+    /// Draws the tree top-down with one node per line, connected by branches like `|-- ` (see ASCII_BRANCHES),
+    /// e.g. to inspect trees in the terminal. Preterminals are kept on one line together with their word
+    pub fn render(&self, branches: &Branches) -> String {
+        let mut lines = Vec::new();
+        self.render_lines(branches, "", "", &mut lines);
+
+        lines.join("\n")
+    }
+
+    fn render_lines(
+        &self,
+        branches: &Branches,
+        branch: &str,
+        prefix: &str,
+        lines: &mut Vec<String>,
+    ) {
+        match &self.descendants {
+            Descendants::Atom(atom) => {
+                lines.push(format!("{}{}{} {}", prefix, branch, self.root, atom))
+            }
+            Descendants::Expressions(trees) => {
+                lines.push(format!("{}{}{}", prefix, branch, self.root));
+
+                // The subtrees are indented below the branch leading to this node
+                let prefix = match branch {
+                    "" => prefix.to_string(),
+                    branch if branch == branches.last => format!("{}{}", prefix, branches.space),
+                    _ => format!("{}{}", prefix, branches.line),
+                };
+                for (i, tree) in trees.iter().enumerate() {
+                    let branch = match i + 1 == trees.len() {
+                        true => branches.last,
+                        false => branches.branch,
+                    };
+                    tree.render_lines(branches, branch, &prefix, lines);
+                }
             }
         }
     }
+
+    /// Returns the (label, start, end) triples of all constituents in preorder,
+    /// where start and end are the positions of the first and behind the last word
+    pub fn spans(&self) -> Vec<(String, usize, usize)> {
+        let mut spans = Vec::new();
+        self.collect_spans(0, &mut spans);
+
+        spans
+    }
+
+    fn collect_spans(&self, start: usize, spans: &mut Vec<(String, usize, usize)>) -> usize {
+        let index = spans.len();
+        spans.push((self.root.clone(), start, start));
+
+        let end = match &self.descendants {
+            Descendants::Atom(_) => start + 1,
+            Descendants::Expressions(trees) => trees
+                .iter()
+                .fold(start, |end, tree| tree.collect_spans(end, spans)),
+        };
+
+        spans[index].2 = end;
+        end
+    }
+
+    /// Prints the spans() as space separated (label,start,end) triples
+    pub fn print_spans(&self) -> String {
+        self.spans()
+            .into_iter()
+            .map(|(label, start, end)| format!("({},{},{})", label, start, end))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// The number of labelled nodes on the longest path from the root to a word, i.e. 1 for a preterminal
+    pub fn depth(&self) -> usize {
+        match &self.descendants {
+            Descendants::Atom(_) => 1,
+            Descendants::Expressions(trees) => 1 + trees.iter().map(Self::depth).max().unwrap_or(0),
+        }
+    }
+
+    /// The number of labelled nodes, i.e. without the words
+    pub fn node_count(&self) -> usize {
+        match &self.descendants {
+            Descendants::Atom(_) => 1,
+            Descendants::Expressions(trees) => {
+                1 + trees.iter().map(Self::node_count).sum::<usize>()
+            }
+        }
+    }
+
+    /// The words of the tree from left to right
+    pub fn leaves(&self) -> Vec<&str> {
+        self.preterminals()
+            .into_iter()
+            .filter_map(|tree| match &tree.descendants {
+                Descendants::Atom(atom) => Some(atom.as_str()),
+                Descendants::Expressions(_) => None,
+            })
+            .collect()
+    }
+
+    /// The subtrees that directly dominate a word, from left to right
+    pub fn preterminals(&self) -> Vec<&ParseTree<String>> {
+        let mut preterminals = Vec::new();
+        self.collect_preterminals(&mut preterminals);
+
+        preterminals
+    }
+
+    fn collect_preterminals<'a>(&'a self, preterminals: &mut Vec<&'a ParseTree<String>>) {
+        match &self.descendants {
+            Descendants::Atom(_) => preterminals.push(self),
+            Descendants::Expressions(trees) => {
+                for tree in trees {
+                    tree.collect_preterminals(preterminals);
+                }
+            }
+        }
+    }
+
+    /// The labels of the preterminals from left to right, i.e. the POS tag of each word
+    pub fn pos_tags(&self) -> Vec<&str> {
+        self.preterminals()
+            .into_iter()
+            .map(|tree| tree.root.as_str())
+            .collect()
+    }
+
+    /// Prints the words of the tree as word/TAG sequence
+    pub fn tagged(&self) -> String {
+        match &self.descendants {
+            Descendants::Atom(atom) => format!("{}/{}", atom, self.root),
+            Descendants::Expressions(trees) => trees
+                .iter()
+                .map(Self::tagged)
+                .collect::<Vec<String>>()
+                .join(" "),
+        }
+    }
 }
 
 impl fmt::Display for ParseTree<String> {
@@ -36,26 +337,172 @@ impl fmt::Display for ParseTree<String> {
     }
 }
 
+/// The pieces that render() draws the branches with, which must all have the same width
+pub struct Branches {
+    /// Leads to a subtree that has siblings below it
+    pub branch: &'static str,
+    /// Leads to the last subtree
+    pub last: &'static str,
+    /// Continues the branches of a node past its subtree
+    pub line: &'static str,
+    /// Fills the space below the last subtree
+    pub space: &'static str,
+}
+
+pub const ASCII_BRANCHES: Branches = Branches {
+    branch: "|-- ",
+    last: "`-- ",
+    line: "|   ",
+    space: "    ",
+};
+
+pub const BOX_BRANCHES: Branches = Branches {
+    branch: "├── ",
+    last: "└── ",
+    line: "│   ",
+    space: "    ",
+};
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Descendants<T> {
     Atom(T),
     Expressions(Vec<ParseTree<T>>),
 }
 
+/// Labels and words end at whitespace or brackets
+const LABEL_DELIMITERS: &str = " \t\r\n()";
+
+/// Escapes a label or word so that it can contain brackets and whitespace:
+/// brackets become `\(` and `\)`, whitespace `\s`, `\t`, `\n` or `\r`.
+/// Backslashes are only doubled where they would start such an escape, so that the PTB's own `\/` and `\*` stay as they are
+pub struct EscapedLabel<'a>(pub &'a str);
+
+impl fmt::Display for EscapedLabel<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut chars = self.0.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' => f.write_str("\\(")?,
+                ')' => f.write_str("\\)")?,
+                ' ' => f.write_str("\\s")?,
+                '\t' => f.write_str("\\t")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\\' if chars
+                    .peek()
+                    .is_none_or(|next| next.is_whitespace() || unescape_char(*next).is_some()) =>
+                {
+                    f.write_str("\\\\")?
+                }
+                c => fmt::Write::write_char(f, c)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The character escaped by a backslash followed by c, if that is an escape at all
+fn unescape_char(c: char) -> Option<char> {
+    match c {
+        's' => Some(' '),
+        't' => Some('\t'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        '\\' | '(' | ')' => Some(c),
+        _ => None,
+    }
+}
+
+/// Parses a label or word up to the next unescaped whitespace or bracket and reverts EscapedLabel.
+/// Backslashes that don't start an escape are kept
+fn label(input: &str) -> IResult<&str, String> {
+    let mut label = String::new();
+    let mut end = input.len();
+
+    let mut chars = input.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => match chars.peek().and_then(|(_, next)| unescape_char(*next)) {
+                Some(unescaped) => {
+                    label.push(unescaped);
+                    chars.next();
+                }
+                None => label.push(c),
+            },
+            c if LABEL_DELIMITERS.contains(c) => {
+                end = i;
+                break;
+            }
+            c => label.push(c),
+        }
+    }
+
+    if end == 0 {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::IsNot,
+        )));
+    }
+
+    Ok((&input[end..], label))
+}
+
+/// A malformed tree with the position where parsing failed
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// Line of the input, starting at 1
+    pub line: usize,
+    /// Character in the line, starting at 1
+    pub column: usize,
+    /// What should have been at the position, e.g. ')'
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    /// Locates a nom error in the parsed input. The brackets are parsed with different
+    /// parsers, so that the kind of the error tells which one is missing
+    fn new(e: nom::Err<nom::error::Error<&str>>, s: &str) -> Self {
+        let (remaining, code) = match e {
+            nom::Err::Error(e) | nom::Err::Failure(e) => (e.input, e.code),
+            nom::Err::Incomplete(_) => ("", ErrorKind::Fail),
+        };
+
+        let expected = match code {
+            ErrorKind::Char => "'('",
+            ErrorKind::Tag => "')'",
+            ErrorKind::IsNot => "a label",
+            ErrorKind::Eof => "'(' or the end of the input",
+            _ => "a tree",
+        };
+
+        let parsed = &s[..s.len() - remaining.len()];
+        let line_start = parsed.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            line: parsed.matches('\n').count() + 1,
+            column: parsed[line_start..].chars().count() + 1,
+            expected,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: expected {}",
+            self.line, self.column, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
 trait PTBExpressionParser {
-    fn parse(s: &str) -> Result<ParseTree<String>, nom::error::Error<String>> {
+    fn parse(s: &str) -> Result<ParseTree<String>, ParseError> {
         match Self::expression.parse(s) {
             Ok((_input, tree)) => Ok(tree),
-            Err(e) => match e {
-                nom::Err::Error(e) | nom::Err::Failure(e) => Err(nom::error::Error {
-                    input: String::from(e.input),
-                    code: e.code,
-                }),
-                nom::Err::Incomplete(_e) => Err(nom::error::Error {
-                    input: String::from(s.to_string()),
-                    code: nom::error::ErrorKind::Fail,
-                }),
-            },
+            Err(e) => Err(ParseError::new(e, s)),
         }
     }
     fn atom(input: &str) -> IResult<&str, Descendants<String>>;
@@ -68,15 +515,13 @@ pub struct PTBParser;
 
 impl PTBExpressionParser for PTBParser {
     fn atom(input: &str) -> IResult<&str, Descendants<String>> {
-        let (input, atom) = delimited(multispace0, is_not(" ()"), multispace0).parse(input)?;
+        let (input, atom) = delimited(multispace0, label, multispace0).parse(input)?;
 
-        Ok((input, Descendants::Atom(String::from(atom))))
+        Ok((input, Descendants::Atom(atom)))
     }
 
     fn head(input: &str) -> IResult<&str, String> {
-        let (input, atom) = delimited(multispace0, is_not(" ()"), multispace0).parse(input)?;
-
-        Ok((input, String::from(atom)))
+        delimited(multispace0, label, multispace0).parse(input)
     }
 
     fn expression_list(input: &str) -> IResult<&str, Descendants<String>> {
@@ -87,13 +532,16 @@ impl PTBExpressionParser for PTBParser {
     }
 
     fn expression(input: &str) -> IResult<&str, ParseTree<String>> {
-        let lparen = delimited(multispace0, tag("("), multispace0);
+        let lparen = delimited(multispace0, char('('), multispace0);
         let rparen = delimited(multispace0, tag(")"), multispace0);
 
-        let (input, (head, tail)) = delimited(
+        // Everything after the bracket belongs to this tree, so errors aren't backtracked to an enclosing tree
+        let (input, (head, tail)) = preceded(
             lparen,
-            (Self::head, alt((Self::atom, Self::expression_list))),
-            rparen,
+            cut(terminated(
+                (Self::head, alt((Self::atom, Self::expression_list))),
+                rparen,
+            )),
         )
         .parse(input)?;
 
@@ -108,9 +556,67 @@ impl PTBExpressionParser for PTBParser {
 }
 
 impl PTBParser {
-    pub fn parse(s: &str) -> Result<ParseTree<String>, nom::error::Error<String>> {
+    pub fn parse(s: &str) -> Result<ParseTree<String>, ParseError> {
         <Self as PTBExpressionParser>::parse(s)
     }
+
+    /// Parses a tree that may be preceded by its weight and a tab (e.g. "2\t(S ...)"), i.e. how often the tree counts
+    /// when inducing. Trees without a weight count once
+    pub fn parse_weighted(s: &str) -> Result<(ParseTree<String>, f64), ParseError> {
+        let (weight, tree) = match s.split_once('\t') {
+            // Tabs may also separate the brackets of an unweighted tree
+            Some((weight, tree)) if !weight.trim_start().starts_with('(') => (weight, tree),
+            _ => return Self::parse(s).map(|tree| (tree, 1.0)),
+        };
+
+        let weight = match weight.trim().parse::<f64>() {
+            Ok(weight) if weight.is_finite() && weight > 0.0 => weight,
+            _ => {
+                return Err(ParseError {
+                    line: 1,
+                    column: 1,
+                    expected: "a positive weight",
+                })
+            }
+        };
+
+        let offset = s.len() - tree.len();
+        Self::parse(tree)
+            .map(|tree| (tree, weight))
+            .map_err(|e| ParseError {
+                column: e.column + s[..offset].chars().count(),
+                ..e
+            })
+    }
+
+    /// Parses all trees of a treebank, which may span multiple lines like in the .mrg files of the PTB.
+    /// Unlabelled outermost brackets (e.g. "( (S ...) )") are labelled with root
+    pub fn parse_treebank(s: &str, root: &str) -> Result<Vec<ParseTree<String>>, ParseError> {
+        let unlabelled_expression = |input| -> IResult<&str, ParseTree<String>> {
+            let lparen = delimited(multispace0, char('('), multispace0);
+            let rparen = delimited(multispace0, tag(")"), multispace0);
+
+            // Only a bracket that is directly followed by another one is unlabelled
+            let (input, descendants) = preceded(
+                (lparen, peek(char('('))),
+                cut(terminated(Self::expression_list, rparen)),
+            )
+            .parse(input)?;
+
+            Ok((
+                input,
+                ParseTree {
+                    root: root.to_string(),
+                    descendants,
+                },
+            ))
+        };
+
+        match all_consuming(many0(alt((unlabelled_expression, Self::expression)))).parse(s) {
+            Ok((_input, trees)) => Ok(trees),
+            Err(e) => Err(ParseError::new(e, s)),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -208,4 +714,192 @@ mod tests {
             }
         )
     }
+
+    #[test]
+    fn pretty_prints_tree() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams) (ADVP (RB loudly)))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(
+            tree.pretty(2),
+            "(ROOT\n  (S\n    (NP\n      (NNP A))\n    (VP\n      (VB screams)\n      (ADVP\n        (RB loudly)))))"
+        )
+    }
+
+    #[test]
+    fn computes_spans() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(
+            tree.print_spans(),
+            "(ROOT,0,2) (S,0,2) (NP,0,1) (NNP,0,1) (VP,1,2) (VB,1,2)"
+        )
+    }
+
+    #[test]
+    fn round_trips_escaped_labels() {
+        let input = r"(ROOT (-LRB- \() (CD 1\/2) (NN a\sb) (SYM \\) (SYM \\s) (-RRB- \)))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(tree.leaves(), vec!["(", "1\\/2", "a b", "\\", "\\s", ")"]);
+        assert_eq!(tree.to_string(), input);
+        assert_eq!(
+            PTBParser::parse(&tree.pretty(2)).expect("This should be parsable"),
+            tree
+        );
+    }
+
+    #[test]
+    fn renders_ascii_art() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams) (ADVP (RB loudly)))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(
+            tree.render(&ASCII_BRANCHES),
+            "ROOT\n`-- S\n    |-- NP\n    |   `-- NNP A\n    `-- VP\n        |-- VB screams\n        `-- ADVP\n            `-- RB loudly"
+        );
+    }
+
+    #[test]
+    fn prints_tagged_words() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(tree.tagged(), "A/NNP screams/VB")
+    }
+
+    #[test]
+    fn measures_and_collects_nodes() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams) (ADVP (RB loudly)))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert_eq!(tree.depth(), 5);
+        assert_eq!(tree.node_count(), 8);
+        assert_eq!(tree.leaves(), vec!["A", "screams", "loudly"]);
+        assert_eq!(tree.pos_tags(), vec!["NNP", "VB", "RB"]);
+        assert_eq!(
+            tree.preterminals()
+                .into_iter()
+                .map(|tree| tree.to_string())
+                .collect::<Vec<String>>(),
+            vec!["(NNP A)", "(VB screams)", "(RB loudly)"]
+        );
+    }
+
+    #[test]
+    fn iterates_over_nodes() {
+        let input = "(ROOT (S (NP (NNP A)) (VP (VB screams) (ADVP (RB loudly)))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+        fn labels<'a>(nodes: impl Iterator<Item = &'a ParseTree<String>>) -> Vec<&'a str> {
+            nodes.map(|tree| tree.root.as_str()).collect()
+        }
+
+        assert_eq!(
+            labels(tree.iter_preorder()),
+            vec!["ROOT", "S", "NP", "NNP", "VP", "VB", "ADVP", "RB"]
+        );
+        assert_eq!(
+            labels(tree.iter_postorder()),
+            vec!["NNP", "NP", "VB", "RB", "ADVP", "VP", "S", "ROOT"]
+        );
+        assert_eq!(
+            labels(tree.iter_subtrees()),
+            vec!["ROOT", "S", "NP", "VP", "NNP", "VB", "ADVP", "RB"]
+        );
+    }
+
+    #[test]
+    fn maps_and_transforms_nodes() {
+        let input = "(ROOT (S (NP-SBJ (NNP A)) (VP (VB Screams))))";
+        let tree = PTBParser::parse(input).expect("This should be parsable");
+
+        let mapped = tree
+            .clone()
+            .map_labels(|label| label.replace("-SBJ", ""))
+            .map_terminals(|terminal| terminal.to_lowercase());
+        assert_eq!(
+            mapped.to_string(),
+            "(ROOT (S (NP (NNP a)) (VP (VB screams))))"
+        );
+
+        // Removes the unary NP and VP levels above the preterminals
+        let mut transformed = tree;
+        transformed.transform(|node| {
+            if let Descendants::Expressions(trees) = &mut node.descendants {
+                for tree in trees.iter_mut() {
+                    *tree = match &tree.descendants {
+                        Descendants::Expressions(children) if children.len() == 1 => {
+                            children[0].clone()
+                        }
+                        _ => continue,
+                    };
+                }
+            }
+        });
+        assert_eq!(transformed.to_string(), "(ROOT (S (NNP A) (VB Screams)))");
+    }
+
+    #[test]
+    fn parses_multiline_treebank() {
+        let input = "( (S \n    (NP-SBJ (NNP Pierre) (NNP Vinken) )\n    (VP (MD will) \n      (VP (VB join) ))\n    (. .) ))\n( (S\n\t(NP (PRP It))\n\t(VP (VBZ works) )))\n";
+        let trees = PTBParser::parse_treebank(input, "ROOT").expect("This should be parsable");
+
+        assert_eq!(
+            trees
+                .iter()
+                .map(|tree| tree.to_string())
+                .collect::<Vec<String>>(),
+            vec![
+                "(ROOT (S (NP-SBJ (NNP Pierre) (NNP Vinken)) (VP (MD will) (VP (VB join))) (. .)))",
+                "(ROOT (S (NP (PRP It)) (VP (VBZ works))))"
+            ]
+        )
+    }
+
+    #[test]
+    fn erroneous_treebank() {
+        let input = "(ROOT (A a))\n(ROOT (B b)\n";
+        let _err =
+            PTBParser::parse_treebank(input, "ROOT").expect_err("This should not be parsable");
+    }
+
+    #[test]
+    fn locates_parse_errors() {
+        let error = PTBParser::parse_treebank("(ROOT (A a))\n( (S (B b) c)\n)", "ROOT")
+            .expect_err("c is no tree");
+        assert_eq!(error.to_string(), "line 2, column 12: expected ')'");
+
+        let error = PTBParser::parse("(ROOT (A a) (B b)").expect_err("The root isn't closed");
+        assert_eq!(error.to_string(), "line 1, column 18: expected ')'");
+
+        let error = PTBParser::parse("(ROOT (A ()))").expect_err("The word is missing");
+        assert_eq!(error.to_string(), "line 1, column 11: expected a label");
+
+        let error = PTBParser::parse_treebank("(ROOT (A a))\nA a", "ROOT")
+            .expect_err("The second tree has no brackets");
+        assert_eq!(
+            error.to_string(),
+            "line 2, column 1: expected '(' or the end of the input"
+        );
+    }
+
+    #[test]
+    fn parses_weighted_trees() {
+        let tree = PTBParser::parse("(S (A a))").expect("This should be parsable");
+        assert_eq!(
+            PTBParser::parse_weighted("2.5\t(S (A a))"),
+            Ok((tree.clone(), 2.5))
+        );
+        assert_eq!(PTBParser::parse_weighted("(S\t(A a))"), Ok((tree, 1.0)));
+
+        let error = PTBParser::parse_weighted("0\t(S (A a))").expect_err("Weights are positive");
+        assert_eq!(
+            error.to_string(),
+            "line 1, column 1: expected a positive weight"
+        );
+
+        let error = PTBParser::parse_weighted("3\t(S (A a)").expect_err("S isn't closed");
+        assert_eq!(error.to_string(), "line 1, column 11: expected ')'");
+    }
 }
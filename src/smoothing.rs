@@ -0,0 +1,529 @@
+use std::collections::{HashMap, HashSet};
+
+use rustc_hash::FxHashMap;
+
+use crate::{
+    unknown::{signature, SignatureOptions},
+    Body, Nonterminal, Occurence, OccurenceRules, Probability, Rule, Terminal, WeightedRules,
+};
+
+/// Only lexical rules occuring at most this often are discounted by Good-Turing, since higher counts are reliable
+const GOOD_TURING_THRESHOLD: Occurence = 5;
+
+pub trait Smoothing {
+    /// Turns the occurences of rules into weights, which are normalised into probabilities afterwards
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules;
+}
+
+/// Adds k to the occurence of every rule.
+/// If unknown is given, the lexical rule PRETERMINAL -> unknown is added with weight k for every preterminal
+pub struct AddK {
+    pub k: Probability,
+    pub unknown: Option<Terminal>,
+}
+
+impl Smoothing for AddK {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        let mut weighted_rules: WeightedRules = FxHashMap::default();
+
+        for (rule, occurence) in occurence_rules {
+            if let (Some(unknown), Body::Lexical(_)) = (&self.unknown, &rule.body) {
+                // The head of a lexical rule is a preterminal, which may now also produce the unknown terminal
+                weighted_rules
+                    .entry(Rule {
+                        head: rule.head.clone(),
+                        body: Body::Lexical(unknown.to_string()),
+                    })
+                    .or_insert(self.k);
+            }
+
+            // The unknown terminal might have been observed as well, so the occurence is added on top of k
+            *weighted_rules.entry(rule).or_insert(self.k) += occurence as Probability;
+        }
+
+        weighted_rules
+    }
+}
+
+/// Discounts the occurences of rare lexical rules via simple Good-Turing estimation.
+/// The discounted mass of each preterminal is given to its lexical rule PRETERMINAL -> unknown
+pub struct GoodTuring {
+    pub unknown: Terminal,
+}
+
+impl Smoothing for GoodTuring {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        // N_r: How many distinct word/tag pairs occur exactly r times
+        let mut frequencies_of_frequencies: HashMap<Occurence, Occurence> = HashMap::new();
+        for (rule, occurence) in occurence_rules.iter() {
+            if rule.is_lexical_rule() {
+                *frequencies_of_frequencies.entry(*occurence).or_insert(0) += 1;
+            }
+        }
+
+        // r* = (r + 1) * N_(r+1) / N_r
+        let discount = |occurence: Occurence| -> Probability {
+            if occurence > GOOD_TURING_THRESHOLD {
+                return occurence as Probability;
+            }
+
+            match (
+                frequencies_of_frequencies.get(&occurence),
+                frequencies_of_frequencies.get(&(occurence + 1)),
+            ) {
+                (Some(n_r), Some(n_r_plus_one)) => {
+                    let discounted = (occurence + 1) as Probability * *n_r_plus_one as Probability
+                        / *n_r as Probability;
+
+                    // Sparse counts may yield estimates above r, which must not be turned into negative mass
+                    discounted.min(occurence as Probability)
+                }
+                _ => occurence as Probability,
+            }
+        };
+
+        let mut weighted_rules: WeightedRules = FxHashMap::default();
+        let mut unseen_mass: HashMap<Nonterminal, Probability> = HashMap::new();
+
+        for (rule, occurence) in occurence_rules {
+            match &rule.body {
+                Body::Lexical(_) => {
+                    let discounted = discount(occurence);
+                    *unseen_mass.entry(rule.head.clone()).or_insert(0.0) +=
+                        occurence as Probability - discounted;
+                    *weighted_rules.entry(rule).or_insert(0.0) += discounted;
+                }
+                Body::NonLexical(_) => {
+                    weighted_rules.insert(rule, occurence as Probability);
+                }
+            }
+        }
+
+        for (preterminal, mass) in unseen_mass {
+            if mass > 0.0 {
+                *weighted_rules
+                    .entry(Rule {
+                        head: preterminal,
+                        body: Body::Lexical(self.unknown.clone()),
+                    })
+                    .or_insert(0.0) += mass;
+            }
+        }
+
+        weighted_rules
+    }
+}
+
+/// Interpolates the tag specific word distributions with a tag independent word distribution via Witten-Bell smoothing:
+/// P(word|tag) = (c(tag, word) + T(tag) * P(word)) / (c(tag) + T(tag)), where T(tag) is the number of distinct words seen with tag.
+/// The tag independent distribution is itself Witten-Bell smoothed, so that unknown receives the mass of unseen words.
+/// Words that weren't seen with a tag are backed off to its unknown rule, which gets their interpolated mass,
+/// so that the lexicon only grows by one rule per tag instead of pairing every tag with every word (e.g. DT -> running)
+pub struct WittenBell {
+    pub unknown: Terminal,
+}
+
+impl Smoothing for WittenBell {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        let mut word_occurences: HashMap<&Terminal, Occurence> = HashMap::new();
+        for (rule, occurence) in occurence_rules.iter() {
+            if let Body::Lexical(word) = &rule.body {
+                *word_occurences.entry(word).or_insert(0) += occurence;
+            }
+        }
+
+        // T(tag) and how often the words seen with tag occur in total, i.e. their share of the global distribution
+        let mut tag_types: HashMap<&Nonterminal, (Occurence, Occurence)> = HashMap::new();
+        for rule in occurence_rules.keys() {
+            if let Body::Lexical(word) = &rule.body {
+                let (types, seen_tokens) = tag_types.entry(&rule.head).or_insert((0, 0));
+                *types += 1;
+                *seen_tokens += word_occurences[word];
+            }
+        }
+
+        // P(word) = c(word) / (N + V) and P(unknown) = V / (N + V)
+        let total_tokens: Occurence = word_occurences.values().sum();
+        let global_weight = (total_tokens + word_occurences.len() as Occurence) as Probability;
+
+        let mut weighted_rules: WeightedRules = FxHashMap::default();
+        for (rule, occurence) in occurence_rules.iter() {
+            let weight = match &rule.body {
+                Body::Lexical(word) => {
+                    let (types, _seen_tokens) = tag_types[&rule.head];
+                    *occurence as Probability
+                        + types as Probability * word_occurences[word] as Probability
+                            / global_weight
+                }
+                Body::NonLexical(_) => *occurence as Probability,
+            };
+            weighted_rules.insert(rule.clone(), weight);
+        }
+
+        // The weights of each tag sum up to c(tag) + T(tag), so the normalisation yields the interpolated probabilities
+        for (tag, (types, seen_tokens)) in tag_types {
+            let unseen = (global_weight - seen_tokens as Probability) / global_weight;
+            *weighted_rules
+                .entry(Rule {
+                    head: tag.clone(),
+                    body: Body::Lexical(self.unknown.clone()),
+                })
+                .or_insert(0.0) += types as Probability * unseen;
+        }
+
+        weighted_rules
+    }
+}
+
+/// Adds pseudo-counts (e.g. of hand-written rules) to the weights of another smoothing or to the plain occurences,
+/// so that rules of the prior are part of the grammar even if they weren't observed
+pub struct Prior {
+    pub smoothing: Option<Box<dyn Smoothing>>,
+    pub pseudo_counts: WeightedRules,
+}
+
+impl Smoothing for Prior {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        let mut weighted_rules = match &self.smoothing {
+            Some(smoothing) => smoothing.smooth(occurence_rules),
+            None => occurence_rules
+                .into_iter()
+                .map(|(rule, occurence)| (rule, occurence as Probability))
+                .collect(),
+        };
+
+        for (rule, pseudo_count) in self.pseudo_counts.iter() {
+            *weighted_rules.entry(rule.clone()).or_insert(0.0) += pseudo_count;
+        }
+
+        weighted_rules
+    }
+}
+
+/// Restricts the unknown rules introduced by another smoothing to open-class preterminals,
+/// so that unknown words cannot be tagged as e.g. determiners or prepositions
+pub struct OpenClassOnly {
+    pub smoothing: Box<dyn Smoothing>,
+    pub unknown: Terminal,
+}
+
+impl Smoothing for OpenClassOnly {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        let open_class = open_class_preterminals(&occurence_rules);
+        // An observed unknown terminal is kept, since it is regular training data
+        let observed_unknown: HashSet<Nonterminal> = occurence_rules
+            .keys()
+            .filter(|rule| rule.body == Body::Lexical(self.unknown.clone()))
+            .map(|rule| rule.head.clone())
+            .collect();
+
+        let mut weighted_rules = self.smoothing.smooth(occurence_rules);
+        weighted_rules.retain(|rule, _weight| match &rule.body {
+            Body::Lexical(terminal) if *terminal == self.unknown => {
+                open_class.contains(&rule.head) || observed_unknown.contains(&rule.head)
+            }
+            _ => true,
+        });
+
+        weighted_rules
+    }
+}
+
+/// Splits the weight of every PRETERMINAL -> unknown among the signature classes of the preterminal's hapax legomena
+/// (e.g. NN -> UNK-LC-s), in proportion to how many of them fall into each class.
+/// Preterminals without hapax legomena keep the plain unknown rule
+pub struct SignatureClasses {
+    pub smoothing: Box<dyn Smoothing>,
+    pub unknown: Terminal,
+    pub options: SignatureOptions,
+}
+
+impl Smoothing for SignatureClasses {
+    fn smooth(&self, occurence_rules: OccurenceRules) -> WeightedRules {
+        let class_occurences = self.class_occurences(&occurence_rules);
+
+        let mut unknown_weights: Vec<(Nonterminal, Probability)> = Vec::new();
+        let mut weighted_rules = self.smoothing.smooth(occurence_rules);
+        weighted_rules.retain(|rule, weight| match &rule.body {
+            Body::Lexical(terminal)
+                if *terminal == self.unknown && class_occurences.contains_key(&rule.head) =>
+            {
+                unknown_weights.push((rule.head.clone(), *weight));
+                false
+            }
+            _ => true,
+        });
+
+        for (preterminal, weight) in unknown_weights {
+            let classes = &class_occurences[&preterminal];
+            let total: Occurence = classes.values().sum();
+
+            for (class, occurence) in classes {
+                *weighted_rules
+                    .entry(Rule {
+                        head: preterminal.clone(),
+                        body: Body::Lexical(class.clone()),
+                    })
+                    .or_insert(0.0) += weight * *occurence as Probability / total as Probability;
+            }
+        }
+
+        weighted_rules
+    }
+}
+
+impl SignatureClasses {
+    /// Counts how many hapax legomena of each preterminal fall into each signature class
+    fn class_occurences(
+        &self,
+        occurence_rules: &OccurenceRules,
+    ) -> HashMap<Nonterminal, HashMap<Terminal, Occurence>> {
+        let mut word_occurences: HashMap<&Terminal, Occurence> = HashMap::new();
+        for (rule, occurence) in occurence_rules.iter() {
+            if let Body::Lexical(word) = &rule.body {
+                *word_occurences.entry(word).or_insert(0) += occurence;
+            }
+        }
+
+        let mut class_occurences: HashMap<Nonterminal, HashMap<Terminal, Occurence>> =
+            HashMap::new();
+        for rule in occurence_rules.keys() {
+            match &rule.body {
+                Body::Lexical(word) if word_occurences[word] == 1 && *word != self.unknown => {
+                    *class_occurences
+                        .entry(rule.head.clone())
+                        .or_default()
+                        .entry(signature(word, &self.options))
+                        .or_insert(0) += 1;
+                }
+                _ => {}
+            }
+        }
+
+        class_occurences
+    }
+}
+
+/// Returns the preterminals that occured with hapax legomena (words seen exactly once in the whole corpus).
+/// These are the open-class preterminals, which are likely to produce unknown words
+pub fn open_class_preterminals(occurence_rules: &OccurenceRules) -> HashSet<Nonterminal> {
+    let mut word_occurences: HashMap<&Terminal, Occurence> = HashMap::new();
+    for (rule, occurence) in occurence_rules.iter() {
+        if let Body::Lexical(word) = &rule.body {
+            *word_occurences.entry(word).or_insert(0) += occurence;
+        }
+    }
+
+    occurence_rules
+        .keys()
+        .filter_map(|rule| match &rule.body {
+            Body::Lexical(word) if word_occurences[word] == 1 => Some(rule.head.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexical(head: &str, terminal: &str) -> Rule {
+        Rule {
+            head: head.to_string(),
+            body: Body::Lexical(terminal.to_string()),
+        }
+    }
+
+    #[test]
+    fn good_turing_moves_discounted_mass_to_unknown() {
+        let occurence_rules: OccurenceRules = HashMap::from_iter(vec![
+            (
+                Rule {
+                    head: "S".to_string(),
+                    body: Body::NonLexical(vec!["A".to_string(), "B".to_string()]),
+                },
+                4,
+            ),
+            (lexical("A", "a1"), 1),
+            (lexical("A", "a2"), 1),
+            (lexical("A", "a3"), 2),
+            (lexical("B", "b1"), 1),
+            (lexical("B", "b2"), 3),
+        ]);
+
+        let good_turing = GoodTuring {
+            unknown: "UNK".to_string(),
+        };
+        let weighted_rules = good_turing.smooth(occurence_rules);
+
+        // N_1 = 3, N_2 = 1, N_3 = 1, N_4 = 0
+        // 1* = 2 * 1 / 3, 2* = 3 * 1 / 1 > 2 stays 2, 3* stays 3 since N_4 = 0
+        let expected = vec![
+            (lexical("A", "a1"), 2.0 / 3.0),
+            (lexical("A", "a2"), 2.0 / 3.0),
+            (lexical("A", "a3"), 2.0),
+            (lexical("A", "UNK"), 2.0 / 3.0),
+            (lexical("B", "b1"), 2.0 / 3.0),
+            (lexical("B", "b2"), 3.0),
+            (lexical("B", "UNK"), 1.0 / 3.0),
+        ];
+
+        // Subtracting the discounted counts introduces rounding errors
+        let approx_equal = |a: f64, b: f64| -> bool { (a - b).abs() <= 1e-12 };
+
+        assert_eq!(weighted_rules.len(), expected.len() + 1);
+        for (rule, weight) in expected {
+            assert!(approx_equal(weighted_rules[&rule], weight), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn witten_bell_interpolates_with_global_distribution() {
+        let occurence_rules: OccurenceRules = HashMap::from_iter(vec![
+            (lexical("A", "a1"), 2),
+            (lexical("A", "a2"), 1),
+            (lexical("B", "a1"), 1),
+        ]);
+
+        let witten_bell = WittenBell {
+            unknown: "UNK".to_string(),
+        };
+        let weighted_rules = witten_bell.smooth(occurence_rules);
+
+        // N = 4, V = 2: P(a1) = 3 / 6, P(a2) = 1 / 6, P(UNK) = 2 / 6
+        // T(A) = 2, T(B) = 1, and B's share of a2 is backed off to B -> UNK
+        let expected = vec![
+            (lexical("A", "a1"), 2.0 + 2.0 * 3.0 / 6.0),
+            (lexical("A", "a2"), 1.0 + 2.0 * 1.0 / 6.0),
+            (lexical("A", "UNK"), 2.0 * 2.0 / 6.0),
+            (lexical("B", "a1"), 1.0 + 3.0 / 6.0),
+            (lexical("B", "UNK"), 1.0 / 6.0 + 2.0 / 6.0),
+        ];
+
+        let approx_equal = |a: f64, b: f64| -> bool { (a - b).abs() <= 1e-12 };
+
+        assert_eq!(weighted_rules.len(), expected.len());
+        for (rule, weight) in expected {
+            assert!(approx_equal(weighted_rules[&rule], weight), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn witten_bell_adds_one_unknown_rule_per_tag() {
+        let tags = ["DT", "NN", "VBG", "JJ", "IN"];
+        let occurence_rules: OccurenceRules = tags
+            .iter()
+            .flat_map(|tag| (0..20).map(move |i| (lexical(tag, &format!("{}{}", tag, i)), 1)))
+            .chain(std::iter::once((lexical("NN", "running"), 1)))
+            .chain(std::iter::once((lexical("VBG", "running"), 2)))
+            .collect();
+        let observed = occurence_rules.len();
+
+        let witten_bell = WittenBell {
+            unknown: "UNK".to_string(),
+        };
+        let weighted_rules = witten_bell.smooth(occurence_rules);
+
+        assert_eq!(weighted_rules.len(), observed + tags.len());
+        assert!(!weighted_rules.contains_key(&lexical("DT", "running")));
+        assert!(weighted_rules.contains_key(&lexical("DT", "UNK")));
+    }
+
+    #[test]
+    fn detects_open_class_preterminals() {
+        let occurence_rules: OccurenceRules = HashMap::from_iter(vec![
+            (lexical("DT", "the"), 3),
+            (lexical("NN", "dog"), 1),
+            (lexical("NN", "cat"), 2),
+            // Not a hapax, since "run" occurs twice in total
+            (lexical("VB", "run"), 1),
+            (lexical("NN", "run"), 1),
+        ]);
+
+        assert_eq!(
+            open_class_preterminals(&occurence_rules),
+            HashSet::from_iter(vec!["NN".to_string()])
+        );
+    }
+
+    #[test]
+    fn open_class_only_removes_closed_class_unknown_rules() {
+        let occurence_rules: OccurenceRules =
+            HashMap::from_iter(vec![(lexical("DT", "the"), 3), (lexical("NN", "dog"), 1)]);
+
+        let open_class_only = OpenClassOnly {
+            smoothing: Box::new(AddK {
+                k: 1.0,
+                unknown: Some("UNK".to_string()),
+            }),
+            unknown: "UNK".to_string(),
+        };
+        let weighted_rules = open_class_only.smooth(occurence_rules);
+
+        assert_eq!(
+            weighted_rules,
+            HashMap::from_iter(vec![
+                (lexical("DT", "the"), 4.0),
+                (lexical("NN", "dog"), 2.0),
+                (lexical("NN", "UNK"), 1.0),
+            ])
+        );
+    }
+
+    #[test]
+    fn splits_unknown_among_signature_classes() {
+        let occurence_rules: OccurenceRules = HashMap::from_iter(vec![
+            (lexical("DT", "the"), 3),
+            (lexical("NNS", "dogs"), 1),
+            (lexical("NNS", "cats"), 1),
+            (lexical("NNS", "Dogs"), 1),
+            (lexical("NNS", "people"), 2),
+        ]);
+
+        let signature_classes = SignatureClasses {
+            smoothing: Box::new(AddK {
+                k: 3.0,
+                unknown: Some("UNK".to_string()),
+            }),
+            unknown: "UNK".to_string(),
+            options: SignatureOptions::default(),
+        };
+        let weighted_rules = signature_classes.smooth(occurence_rules);
+
+        assert_eq!(weighted_rules[&lexical("NNS", "UNK-LC-s")], 2.0);
+        assert_eq!(weighted_rules[&lexical("NNS", "UNK-INITC-s")], 1.0);
+        assert!(!weighted_rules.contains_key(&lexical("NNS", "UNK")));
+        // DT has no hapax legomena to learn classes from
+        assert_eq!(weighted_rules[&lexical("DT", "UNK")], 3.0);
+    }
+
+    #[test]
+    fn prior_adds_pseudo_counts() {
+        let occurence_rules: OccurenceRules =
+            HashMap::from_iter(vec![(lexical("NN", "dog"), 3), (lexical("NN", "cat"), 1)]);
+        let pseudo_counts: WeightedRules = HashMap::from_iter(vec![
+            (lexical("NN", "cat"), 0.5),
+            (lexical("NN", "gnu"), 0.5),
+        ]);
+
+        let prior = Prior {
+            smoothing: None,
+            pseudo_counts: pseudo_counts.clone(),
+        };
+        let weighted_rules = prior.smooth(occurence_rules.clone());
+        assert_eq!(weighted_rules[&lexical("NN", "dog")], 3.0);
+        assert_eq!(weighted_rules[&lexical("NN", "cat")], 1.5);
+        assert_eq!(weighted_rules[&lexical("NN", "gnu")], 0.5);
+
+        let smoothed_prior = Prior {
+            smoothing: Some(Box::new(AddK {
+                k: 1.0,
+                unknown: None,
+            })),
+            pseudo_counts,
+        };
+        let weighted_rules = smoothed_prior.smooth(occurence_rules);
+        assert_eq!(weighted_rules[&lexical("NN", "cat")], 2.5);
+        assert_eq!(weighted_rules[&lexical("NN", "gnu")], 0.5);
+    }
+}
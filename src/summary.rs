@@ -0,0 +1,88 @@
+use std::{fmt, time::Duration};
+
+use crate::{induce::PCFGGrammar, Grammar};
+
+/// Statistics of an induce run, which are printed as one block on the stderr as a sanity check
+pub struct InduceSummary {
+    pub trees_read: usize,
+    /// Malformed trees and trees dropped by the preprocessing
+    pub trees_skipped: usize,
+    pub nonlexical_rules: usize,
+    pub lexical_rules: usize,
+    pub nonterminals: usize,
+    pub terminals: usize,
+    /// Wall-clock time of each phase, e.g. reading and counting
+    pub phases: Vec<(&'static str, Duration)>,
+}
+
+impl InduceSummary {
+    pub fn new(trees_read: usize, trees_skipped: usize, grammar: &Grammar) -> Self {
+        InduceSummary {
+            trees_read,
+            trees_skipped,
+            nonlexical_rules: grammar.nonlexical_rules().count(),
+            lexical_rules: grammar.lexical_rules().count(),
+            nonterminals: grammar.nonterminals().len(),
+            terminals: grammar.terminals().len(),
+            phases: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Display for InduceSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Induction summary")?;
+        writeln!(
+            f,
+            "  Trees:         {} read, {} skipped",
+            self.trees_read, self.trees_skipped
+        )?;
+        writeln!(
+            f,
+            "  Rules:         {} ({} nonlexical, {} lexical)",
+            self.nonlexical_rules + self.lexical_rules,
+            self.nonlexical_rules,
+            self.lexical_rules
+        )?;
+        writeln!(f, "  Nonterminals:  {}", self.nonterminals)?;
+        writeln!(f, "  Terminals:     {}", self.terminals)?;
+        for (phase, duration) in self.phases.iter() {
+            writeln!(
+                f,
+                "  {:<15}{:.3}s",
+                format!("{}:", phase),
+                duration.as_secs_f64()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn summarises_induced_grammar() {
+        let trees = [
+            "(ROOT (S (NP (NNP John)) (VP (VBZ sleeps))))",
+            "(ROOT (S (NP (NNP Mary)) (VP (VBZ sleeps))))",
+        ]
+        .iter()
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+        let grammar = Grammar::from_parse_trees(String::from("ROOT"), trees)
+            .expect("The trees have the initial as root");
+
+        let mut summary = InduceSummary::new(2, 1, &grammar);
+        summary
+            .phases
+            .push(("Reading", Duration::from_millis(1500)));
+
+        assert_eq!(
+            summary.to_string(),
+            "Induction summary\n  Trees:         2 read, 1 skipped\n  Rules:         7 (4 nonlexical, 3 lexical)\n  Nonterminals:  6\n  Terminals:     3\n  Reading:       1.500s\n"
+        );
+    }
+}
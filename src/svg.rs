@@ -0,0 +1,173 @@
+use std::fmt::Write;
+
+use crate::ptb::{Descendants, ParseTree};
+
+/// Appearance of the trees drawn by to_svg()
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    pub font_family: String,
+    /// Font size in pixels, which all distances are derived from
+    pub font_size: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            font_family: String::from("sans-serif"),
+            font_size: 14.0,
+        }
+    }
+}
+
+impl SvgOptions {
+    /// Rough width of a text, since the actual font metrics are unknown
+    fn text_width(&self, text: &str) -> f64 {
+        text.chars().count() as f64 * self.font_size * 0.6
+    }
+
+    fn level_height(&self) -> f64 {
+        self.font_size * 3.0
+    }
+
+    fn margin(&self) -> f64 {
+        self.font_size
+    }
+}
+
+impl ParseTree<String> {
+    /// Draws the tree as a standalone SVG document, e.g. for papers and slides.
+    /// Every level of nodes gets a row, the words are aligned in the bottom row,
+    /// and each node is centered above its first and last subtree
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        // Each word gets a column wide enough for itself and its preterminal
+        let mut columns = Vec::new();
+        let mut width = options.margin();
+        for preterminal in self.preterminals() {
+            let word = match &preterminal.descendants {
+                Descendants::Atom(word) => word.as_str(),
+                Descendants::Expressions(_) => "",
+            };
+            let column = options
+                .text_width(word)
+                .max(options.text_width(&preterminal.root))
+                + options.font_size;
+            columns.push(width + column / 2.0);
+            width += column;
+        }
+        width += options.margin();
+
+        // The words get the row below the deepest preterminal
+        let words_depth = self.depth();
+        let height = 2.0 * options.margin() + (words_depth + 1) as f64 * options.level_height();
+
+        let mut elements = String::new();
+        self.draw(
+            options,
+            0,
+            words_depth,
+            &mut columns.into_iter(),
+            &mut elements,
+        );
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.1}\" height=\"{:.1}\" viewBox=\"0 0 {:.1} {:.1}\">\n<g font-family=\"{}\" font-size=\"{:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" stroke=\"black\" stroke-width=\"1\">\n{}</g>\n</svg>\n",
+            width,
+            height,
+            width,
+            height,
+            escape_xml(&options.font_family),
+            options.font_size,
+            elements
+        )
+    }
+
+    /// Draws the node and its subtrees and returns the horizontal center of the node
+    fn draw(
+        &self,
+        options: &SvgOptions,
+        depth: usize,
+        words_depth: usize,
+        columns: &mut impl Iterator<Item = f64>,
+        elements: &mut String,
+    ) -> f64 {
+        let y = |depth: usize| options.margin() + (depth as f64 + 0.5) * options.level_height();
+
+        let (x, children) = match &self.descendants {
+            Descendants::Atom(word) => {
+                let x = columns.next().unwrap_or(0.0);
+                text(elements, x, y(words_depth), word);
+                (x, vec![(x, words_depth)])
+            }
+            Descendants::Expressions(trees) => {
+                let children: Vec<(f64, usize)> = trees
+                    .iter()
+                    .map(|tree| {
+                        let x = tree.draw(options, depth + 1, words_depth, columns, elements);
+                        (x, depth + 1)
+                    })
+                    .collect();
+                let first = children.first().map_or(0.0, |(x, _)| *x);
+                let last = children.last().map_or(0.0, |(x, _)| *x);
+                ((first + last) / 2.0, children)
+            }
+        };
+
+        text(elements, x, y(depth), &self.root);
+        // The lines end short of the labels, which are centered on their row
+        let gap = options.font_size * 0.8;
+        for (child_x, child_depth) in children {
+            writeln!(
+                elements,
+                "<line x1=\"{:.1}\" y1=\"{:.1}\" x2=\"{:.1}\" y2=\"{:.1}\"/>",
+                x,
+                y(depth) + gap,
+                child_x,
+                y(child_depth) - gap
+            )
+            .expect("Writing to a String works");
+        }
+
+        x
+    }
+}
+
+fn text(elements: &mut String, x: f64, y: f64, content: &str) {
+    writeln!(
+        elements,
+        "<text x=\"{:.1}\" y=\"{:.1}\" stroke=\"none\">{}</text>",
+        x,
+        y,
+        escape_xml(content)
+    )
+    .expect("Writing to a String works");
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn aligns_words_in_bottom_row() {
+        let tree = PTBParser::parse("(ROOT (S (NP (NNP Pierre)) (VP (VBD slept) (. <&>))))")
+            .expect("This should be parsable");
+        let svg = tree.to_svg(&SvgOptions::default());
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("font-family=\"sans-serif\""));
+        assert!(svg.contains("width=\"187.6\" height=\"238.0\""));
+        // Pierre is one level higher than slept in the tree, but both are in the bottom row
+        assert!(svg.contains("<text x=\"46.2\" y=\"203.0\" stroke=\"none\">Pierre</text>"));
+        assert!(svg.contains("<text x=\"46.2\" y=\"119.0\" stroke=\"none\">NP</text>"));
+        assert!(svg.contains("y=\"203.0\" stroke=\"none\">slept</text>"));
+        assert!(svg.contains("y=\"203.0\" stroke=\"none\">&lt;&amp;&gt;</text>"));
+        assert_eq!(svg.matches("<line").count(), 9);
+    }
+}
@@ -0,0 +1,431 @@
+use std::{collections::HashMap, error::Error};
+
+use crate::{
+    cli::CrossingBranches,
+    ptb::{Descendants, ParseTree},
+};
+
+/// Label of the virtual root that TIGER-XML corpora use for the whole sentence
+const VIRTUAL_ROOT: &str = "VROOT";
+
+/// Edge label of the head of a phrase
+const HEAD_EDGE: &str = "HD";
+
+/// A node of a TIGER graph, where only the primary edges are kept
+struct Node {
+    label: String,
+    /// Label of the edge from the parent, e.g. HD or NK
+    edge: String,
+    kind: Kind,
+}
+
+enum Kind {
+    /// A word with its position in the sentence
+    Word(usize, String),
+    Phrase(Vec<Node>),
+}
+
+impl Node {
+    fn positions(&self, positions: &mut Vec<usize>) {
+        match &self.kind {
+            Kind::Word(position, _) => positions.push(*position),
+            Kind::Phrase(children) => children.iter().for_each(|child| child.positions(positions)),
+        }
+    }
+
+    /// First and last position of the words below the node
+    fn span(&self) -> (usize, usize) {
+        let mut positions = Vec::new();
+        self.positions(&mut positions);
+        let first = positions.iter().min().copied().unwrap_or(0);
+        let last = positions.iter().max().copied().unwrap_or(0);
+        (first, last)
+    }
+}
+
+/// Reads the sentences of a TIGER-XML corpus as trees, e.g. to induce grammars from German treebanks.
+/// Secondary edges are ignored, and the virtual root VROOT becomes ROOT (other roots are wrapped in ROOT).
+/// Words that aren't attached to the graph, like the punctuation of some corpora, are attached to ROOT.
+/// Since PTB trees can't cross, the children of discontinuous phrases are either ordered by their first word,
+/// so that words may appear out of order, or raised until every phrase is continuous
+pub fn read_tiger(
+    xml: &str,
+    crossing_branches: CrossingBranches,
+) -> Result<Vec<ParseTree<String>>, Box<dyn Error>> {
+    let mut trees = Vec::new();
+
+    let mut sentence = String::new();
+    let mut root: Option<String> = None;
+    let mut words: Vec<(String, Node)> = Vec::new();
+    let mut phrases: HashMap<String, (String, Vec<(String, String)>)> = HashMap::new();
+    let mut phrase: Option<String> = None;
+
+    for tag in Tags(xml) {
+        let tag = tag?;
+        let attribute = |name: &str| -> Result<String, Box<dyn Error>> {
+            tag.attribute(name).ok_or_else(|| {
+                format!(
+                    "Sentence {} has a <{}> without {} attribute",
+                    sentence, tag.name, name
+                )
+                .into()
+            })
+        };
+
+        match (tag.name, tag.closing) {
+            ("s", false) => {
+                sentence = attribute("id")?;
+                root = None;
+                words.clear();
+                phrases.clear();
+            }
+            ("graph", false) => root = Some(attribute("root")?),
+            ("t", false) => {
+                let id = attribute("id")?;
+                let word = Node {
+                    label: attribute("pos")?,
+                    edge: String::new(),
+                    kind: Kind::Word(words.len(), attribute("word")?),
+                };
+                words.push((id, word));
+            }
+            ("nt", false) => {
+                let id = attribute("id")?;
+                phrases.insert(id.clone(), (attribute("cat")?, Vec::new()));
+                phrase = Some(id);
+            }
+            ("nt", true) => phrase = None,
+            ("edge", false) => match phrase.as_ref().and_then(|id| phrases.get_mut(id)) {
+                Some((_cat, edges)) => edges.push((attribute("idref")?, attribute("label")?)),
+                None => {
+                    return Err(
+                        format!("Sentence {} has an <edge> outside of <nt>", sentence).into(),
+                    );
+                }
+            },
+            ("s", true) => {
+                let root = root
+                    .take()
+                    .ok_or_else(|| format!("Sentence {} has no <graph root>", sentence))?;
+                let graph = Graph {
+                    sentence: &sentence,
+                    words: std::mem::take(&mut words).into_iter().collect(),
+                    phrases: std::mem::take(&mut phrases),
+                };
+                trees.push(graph.into_tree(&root, crossing_branches)?);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(trees)
+}
+
+/// The nodes of a sentence by id, which are removed while building the tree so that each is attached only once
+struct Graph<'a> {
+    sentence: &'a str,
+    words: HashMap<String, Node>,
+    phrases: HashMap<String, (String, Vec<(String, String)>)>,
+}
+
+impl Graph<'_> {
+    fn into_tree(
+        mut self,
+        root: &str,
+        crossing_branches: CrossingBranches,
+    ) -> Result<ParseTree<String>, Box<dyn Error>> {
+        let tree = self.node(root, String::new())?;
+        if let Some(id) = self.phrases.keys().min() {
+            return Err(format!(
+                "Sentence {} has the phrase {} outside of the graph",
+                self.sentence, id
+            )
+            .into());
+        }
+        let mut tree = match tree.kind {
+            Kind::Phrase(_) if tree.label == VIRTUAL_ROOT => Node {
+                label: String::from("ROOT"),
+                ..tree
+            },
+            _ => Node {
+                label: String::from("ROOT"),
+                edge: String::new(),
+                kind: Kind::Phrase(vec![tree]),
+            },
+        };
+
+        if let Kind::Phrase(children) = &mut tree.kind {
+            children.extend(self.words.into_values());
+        }
+
+        if crossing_branches == CrossingBranches::Raise {
+            let raised = raise_discontinuities(&mut tree);
+            if let Kind::Phrase(children) = &mut tree.kind {
+                children.extend(raised);
+            }
+        }
+
+        Ok(to_parse_tree(tree))
+    }
+
+    fn node(&mut self, id: &str, edge: String) -> Result<Node, Box<dyn Error>> {
+        if let Some(word) = self.words.remove(id) {
+            return Ok(Node { edge, ..word });
+        }
+
+        let (label, edges) = self.phrases.remove(id).ok_or_else(|| {
+            format!(
+                "Sentence {} refers to the unknown or already attached node {}",
+                self.sentence, id
+            )
+        })?;
+        let children = edges
+            .into_iter()
+            .map(|(id, edge)| self.node(&id, edge))
+            .collect::<Result<Vec<Node>, Box<dyn Error>>>()?;
+
+        if children.is_empty() {
+            return Err(format!("Sentence {} has the empty phrase {}", self.sentence, id).into());
+        }
+
+        Ok(Node {
+            label,
+            edge,
+            kind: Kind::Phrase(children),
+        })
+    }
+}
+
+/// Keeps the continuous run of children around the head (HD, or the first child) and returns the other children,
+/// which are attached to the parent instead. Called on the subtrees first, so that every child is continuous
+fn raise_discontinuities(node: &mut Node) -> Vec<Node> {
+    let children = match &mut node.kind {
+        Kind::Word(..) => return Vec::new(),
+        Kind::Phrase(children) => children,
+    };
+
+    let raised: Vec<Node> = children
+        .iter_mut()
+        .flat_map(raise_discontinuities)
+        // Their edges belonged to the child, so they can't be the head here
+        .map(|raised| Node {
+            edge: String::new(),
+            ..raised
+        })
+        .collect();
+    children.extend(raised);
+    children.sort_by_key(|child| child.span().0);
+
+    let spans: Vec<(usize, usize)> = children.iter().map(Node::span).collect();
+    let head = children
+        .iter()
+        .position(|child| child.edge == HEAD_EDGE)
+        .unwrap_or(0);
+    let mut start = head;
+    while start > 0 && spans[start - 1].1 + 1 == spans[start].0 {
+        start -= 1;
+    }
+    let mut end = head + 1;
+    while end < spans.len() && spans[end - 1].1 + 1 == spans[end].0 {
+        end += 1;
+    }
+
+    let mut raised: Vec<Node> = children.drain(end..).collect();
+    raised.extend(children.drain(..start));
+    raised
+}
+
+fn to_parse_tree(node: Node) -> ParseTree<String> {
+    let descendants = match node.kind {
+        Kind::Word(_, word) => Descendants::Atom(word),
+        Kind::Phrase(mut children) => {
+            children.sort_by_key(|child| child.span().0);
+            Descendants::Expressions(children.into_iter().map(to_parse_tree).collect())
+        }
+    };
+
+    ParseTree {
+        root: node.label,
+        descendants,
+    }
+}
+
+/// An opening, closing or empty element tag
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+    attributes: &'a str,
+}
+
+impl Tag<'_> {
+    fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes;
+
+        while let Some(equals) = rest.find('=') {
+            let key = rest[..equals].trim();
+            let value = rest[equals + 1..].trim_start();
+            let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let end = value[1..].find(quote)? + 1;
+
+            if key == name {
+                return Some(unescape_xml(&value[1..end]));
+            }
+            rest = &value[end + 1..];
+        }
+
+        None
+    }
+}
+
+/// The element tags of an XML document, skipping the declaration, comments, CDATA and the text between tags
+struct Tags<'a>(&'a str);
+
+impl<'a> Iterator for Tags<'a> {
+    type Item = Result<Tag<'a>, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.0.find('<')?;
+            let rest = &self.0[start..];
+
+            let end_marker = match rest {
+                _ if rest.starts_with("<!--") => "-->",
+                _ if rest.starts_with("<![CDATA[") => "]]>",
+                _ if rest.starts_with("<?") => "?>",
+                _ => ">",
+            };
+            let end = match rest.find(end_marker) {
+                Some(end) => end,
+                None => {
+                    self.0 = "";
+                    return Some(Err("Unterminated XML tag at the end of the input".into()));
+                }
+            };
+            self.0 = &rest[end + end_marker.len()..];
+
+            if end_marker != ">" || rest.starts_with("<!") {
+                continue;
+            }
+
+            let content = rest[1..end].trim_end_matches('/');
+            let (closing, content) = match content.strip_prefix('/') {
+                Some(content) => (true, content),
+                None => (false, content),
+            };
+            let name_end = content.find(char::is_whitespace).unwrap_or(content.len());
+
+            return Some(Ok(Tag {
+                name: &content[..name_end],
+                closing,
+                attributes: &content[name_end..],
+            }));
+        }
+    }
+}
+
+/// Replaces the predefined entities and character references of XML
+fn unescape_xml(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let end = rest.find(';');
+        let character = end.and_then(|end| match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => {
+                let code = match reference.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => reference.strip_prefix('#')?.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            }
+        });
+
+        match (character, end) {
+            (Some(character), Some(end)) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            // A stray ampersand is kept as it is
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "Darauf hat er nicht gewartet ." with the discontinuous VP "Darauf ... gewartet"
+    const CORPUS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<corpus id="test">
+  <body>
+    <s id="s1">
+      <graph root="s1_VROOT">
+        <terminals>
+          <t id="s1_1" word="Darauf" pos="PROAV"/>
+          <t id="s1_2" word="hat" pos="VAFIN"/>
+          <t id="s1_3" word="er" pos="PPER"/>
+          <t id="s1_4" word="nicht" pos="PTKNEG"/>
+          <t id="s1_5" word="gewartet" pos="VVPP"/>
+          <t id="s1_6" word="&quot;" pos="$("/>
+        </terminals>
+        <nonterminals>
+          <nt id="s1_500" cat="VP">
+            <edge label="MO" idref="s1_1"/>
+            <edge label="HD" idref="s1_5"/>
+          </nt>
+          <nt id="s1_501" cat="S">
+            <edge label="HD" idref="s1_2"/>
+            <edge label="SB" idref="s1_3"/>
+            <edge label="NG" idref="s1_4"/>
+            <edge label="OC" idref="s1_500"/>
+            <secedge label="SB" idref="s1_3"/>
+          </nt>
+          <nt id="s1_VROOT" cat="VROOT">
+            <edge label="--" idref="s1_501"/>
+          </nt>
+        </nonterminals>
+      </graph>
+    </s>
+  </body>
+</corpus>"#;
+
+    #[test]
+    fn reads_tiger_graphs() {
+        let trees = read_tiger(CORPUS, CrossingBranches::Reorder).expect("This is valid TIGER-XML");
+        assert_eq!(
+            trees[0].to_string(),
+            "(ROOT (S (VP (PROAV Darauf) (VVPP gewartet)) (VAFIN hat) (PPER er) (PTKNEG nicht)) ($\\( \"))"
+        );
+
+        let trees = read_tiger(CORPUS, CrossingBranches::Raise).expect("This is valid TIGER-XML");
+        assert_eq!(
+            trees[0].to_string(),
+            "(ROOT (S (PROAV Darauf) (VAFIN hat) (PPER er) (PTKNEG nicht) (VP (VVPP gewartet))) ($\\( \"))"
+        );
+
+        let err = read_tiger(
+            &CORPUS.replace(r#"idref="s1_4""#, r#"idref="s1_1""#),
+            CrossingBranches::Reorder,
+        )
+        .expect_err("Darauf is attached twice");
+        assert_eq!(
+            err.to_string(),
+            "Sentence s1 refers to the unknown or already attached node s1_1"
+        );
+    }
+}
@@ -0,0 +1,323 @@
+/// Brackets and their PTB escapes, e.g. ( is written -LRB- in the treebank
+const BRACKETS: [(&str, &str); 6] = [
+    ("(", "-LRB-"),
+    (")", "-RRB-"),
+    ("[", "-LSB-"),
+    ("]", "-RSB-"),
+    ("{", "-LCB-"),
+    ("}", "-RCB-"),
+];
+
+/// Punctuation that is always a token of its own
+const SEPARATE: &str = ";@#$%&?!()[]{}";
+
+/// Clitics that are split off the end of a word, e.g. I'm -> I 'm
+const CLITICS: [&str; 7] = ["n't", "'s", "'m", "'d", "'re", "'ve", "'ll"];
+
+/// Words that are split although they contain no apostrophe, e.g. cannot -> can not
+const SPLIT_WORDS: [(&str, usize); 4] = [("cannot", 3), ("gonna", 3), ("gotta", 3), ("wanna", 3)];
+
+/// Abbreviations whose period doesn't end a sentence
+const ABBREVIATIONS: [&str; 16] = [
+    "mr", "mrs", "ms", "dr", "prof", "jr", "sr", "st", "inc", "corp", "co", "ltd", "vs", "etc",
+    "e.g", "i.e",
+];
+
+/// Splits text into sentences, which end at ., ! or ? (possibly followed by closing quotes and brackets)
+/// if the next word starts with an uppercase letter, a digit or an opening quote or bracket.
+/// Abbreviations (Mr., Inc. etc.) and initials (J. Smith) don't end sentences, and neither do sentences span blank lines,
+/// so that the paragraphs of a document stay apart
+pub fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+
+    for paragraph in text.split("\n\n") {
+        let mut start = 0;
+        let chars: Vec<(usize, char)> = paragraph.char_indices().collect();
+
+        for (i, (index, c)) in chars.iter().enumerate() {
+            // Punctuation up to start closed the previous sentence already
+            if !".!?".contains(*c) || *index < start {
+                continue;
+            }
+
+            // The end of the sentence includes closing punctuation
+            let end = chars[i + 1..]
+                .iter()
+                .take_while(|(_, c)| ".!?\"')]}".contains(*c))
+                .count()
+                + i
+                + 1;
+            let next_word = chars[end..]
+                .iter()
+                .skip_while(|(_, c)| c.is_whitespace())
+                .map(|(_, c)| *c)
+                .next();
+            let boundary = end == chars.len()
+                || (chars[end].1.is_whitespace()
+                    && next_word.is_none_or(|c| {
+                        c.is_uppercase() || c.is_ascii_digit() || "\"'`([{".contains(c)
+                    }));
+
+            if boundary && !(*c == '.' && is_abbreviation(&paragraph[start..*index])) {
+                let end = chars.get(end).map_or(paragraph.len(), |(index, _)| *index);
+                push_trimmed(&paragraph[start..end], &mut sentences);
+                start = end;
+            }
+        }
+
+        push_trimmed(&paragraph[start..], &mut sentences);
+    }
+
+    sentences
+}
+
+/// Whether the text before a period ends with an abbreviation or an initial
+fn is_abbreviation(before_period: &str) -> bool {
+    let word = before_period
+        .rsplit(|c: char| c.is_whitespace() || "\"'([{".contains(c))
+        .next()
+        .unwrap_or("");
+    let is_initial = word.chars().count() == 1 && word.chars().all(char::is_uppercase);
+
+    is_initial || ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+fn push_trimmed<'a>(sentence: &'a str, sentences: &mut Vec<&'a str>) {
+    let sentence = sentence.trim();
+    if !sentence.is_empty() {
+        sentences.push(sentence);
+    }
+}
+
+/// Splits raw text into tokens following the Penn Treebank conventions:
+/// punctuation and clitics become tokens of their own (don't -> do n't), double quotes become `` and '',
+/// and brackets their escapes (-LRB- etc.). Periods are only split off at the end of the text
+pub fn tokenize(text: &str) -> Vec<String> {
+    let spaced = space_punctuation(text);
+    let mut tokens: Vec<String> = Vec::new();
+
+    for token in spaced.split_whitespace() {
+        split_word(token, &mut tokens);
+    }
+
+    split_final_period(&mut tokens);
+
+    tokens
+        .into_iter()
+        .map(
+            |token| match BRACKETS.iter().find(|(bracket, _)| token == *bracket) {
+                Some((_, escape)) => escape.to_string(),
+                None => token,
+            },
+        )
+        .collect()
+}
+
+/// Surrounds punctuation with spaces and replaces double quotes by `` or '' depending on their position
+fn space_punctuation(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spaced = String::with_capacity(text.len() * 2);
+
+    for (i, c) in chars.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|i| chars[i]);
+        let next = chars.get(i + 1).copied();
+        let is_digit = |c: Option<char>| c.is_some_and(|c| c.is_ascii_digit());
+
+        match c {
+            '"' if previous.is_none_or(|c| c.is_whitespace() || "([{<".contains(c)) => {
+                spaced.push_str(" `` ")
+            }
+            '"' => spaced.push_str(" '' "),
+            // Commas and colons in numbers like 1,000 or 10:30 stay
+            ',' | ':' if is_digit(previous) && is_digit(next) => spaced.push(*c),
+            ',' | ':' => {
+                spaced.push(' ');
+                spaced.push(*c);
+                spaced.push(' ');
+            }
+            '.' if next == Some('.') || previous == Some('.') => {
+                // Ellipses are kept together as one token
+                if previous != Some('.') {
+                    spaced.push(' ');
+                }
+                spaced.push('.');
+                if next != Some('.') {
+                    spaced.push(' ');
+                }
+            }
+            '-' if next == Some('-') && previous != Some('-') => spaced.push_str(" -"),
+            '-' if previous == Some('-') && next != Some('-') => spaced.push_str("- "),
+            c if SEPARATE.contains(*c) => {
+                spaced.push(' ');
+                spaced.push(*c);
+                spaced.push(' ');
+            }
+            c => spaced.push(*c),
+        }
+    }
+
+    spaced
+}
+
+/// Splits clitics and quotes off a whitespace separated word
+fn split_word(word: &str, tokens: &mut Vec<String>) {
+    // Quotes made by space_punctuation()
+    if word == "``" || word == "''" {
+        tokens.push(word.to_string());
+        return;
+    }
+
+    let lowercase = word.to_lowercase();
+
+    if let Some((_, index)) = SPLIT_WORDS.iter().find(|(split, _)| lowercase == *split) {
+        tokens.push(word[..*index].to_string());
+        tokens.push(word[*index..].to_string());
+        return;
+    }
+
+    // An opening single quote like in 'Hello' is written `
+    let (word, lowercase) = match word.strip_prefix('\'') {
+        Some(rest) if !rest.is_empty() && !CLITICS.iter().any(|clitic| lowercase == *clitic) => {
+            tokens.push(String::from("`"));
+            (rest, lowercase[1..].to_string())
+        }
+        _ => (word, lowercase),
+    };
+
+    let clitic = CLITICS
+        .iter()
+        .find(|clitic| lowercase.len() > clitic.len() && lowercase.ends_with(*clitic));
+    match clitic {
+        Some(clitic) => {
+            let index = word.len() - clitic.len();
+            tokens.push(word[..index].to_string());
+            tokens.push(word[index..].to_string());
+        }
+        // A closing single quote like in dogs' or 'Hello'
+        None if word.len() > 1 && word.ends_with('\'') => {
+            tokens.push(word[..word.len() - 1].to_string());
+            tokens.push(String::from("'"));
+        }
+        None => tokens.push(word.to_string()),
+    }
+}
+
+/// Joins tokens into readable text by reverting the conventions of tokenize():
+/// punctuation and clitics are attached again, quotes and bracket escapes become the original characters
+pub fn detokenize<S: AsRef<str>>(tokens: &[S]) -> String {
+    let mut text = String::new();
+    // Whether the previous token attaches to the next one, like an opening bracket
+    let mut attach_next = true;
+    let mut previous: Option<&str> = None;
+
+    for token in tokens.iter().map(AsRef::as_ref) {
+        let joined = previous.is_some_and(|previous| {
+            SPLIT_WORDS.iter().any(|(word, index)| {
+                previous.len() == *index && (previous.to_string() + token).to_lowercase() == *word
+            })
+        });
+
+        let (word, attach_previous, attaches_next) = match token {
+            "``" => ("\"", false, true),
+            "''" => ("\"", true, false),
+            "`" => ("'", false, true),
+            "'" => ("'", true, false),
+            "$" | "#" => (token, false, true),
+            "..." | "%" => (token, true, false),
+            token if token.len() == 1 && ",.;:?!".contains(token) => (token, true, false),
+            token
+                if CLITICS
+                    .iter()
+                    .any(|clitic| token.eq_ignore_ascii_case(clitic)) =>
+            {
+                (token, true, false)
+            }
+            token => match BRACKETS.iter().position(|(_, escape)| *escape == token) {
+                // Opening and closing brackets alternate in BRACKETS
+                Some(index) => (BRACKETS[index].0, index % 2 == 1, index % 2 == 0),
+                None => (token, joined, false),
+            },
+        };
+
+        if !(attach_next || attach_previous) {
+            text.push(' ');
+        }
+        text.push_str(word);
+
+        attach_next = attaches_next;
+        previous = Some(token);
+    }
+
+    text
+}
+
+/// Splits the period off the last word, which may only be followed by closing quotes and brackets
+fn split_final_period(tokens: &mut Vec<String>) {
+    let closing = tokens
+        .iter()
+        .rev()
+        .take_while(|token| ["''", "'", ")", "]", "}"].contains(&token.as_str()))
+        .count();
+    let index = match tokens.len().checked_sub(closing + 1) {
+        Some(index) => index,
+        None => return,
+    };
+
+    let word = &tokens[index];
+    if word.len() > 1 && word.ends_with('.') && !word.ends_with("..") {
+        let word = word[..word.len() - 1].to_string();
+        tokens[index] = String::from(".");
+        tokens.insert(index, word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_sentences() {
+        let text = "Mr. J. Smith arrived at 10 a.m. on Monday. \"Why?\" he asked!\nNobody knew... Then silence.\n\nA new paragraph... starts here";
+
+        assert_eq!(
+            split_sentences(text),
+            vec![
+                "Mr. J. Smith arrived at 10 a.m. on Monday.",
+                "\"Why?\" he asked!",
+                "Nobody knew...",
+                "Then silence.",
+                "A new paragraph... starts here"
+            ]
+        );
+    }
+
+    #[test]
+    fn detokenizes_tokenized_text() {
+        for text in [
+            "\"I can't believe it,\" she said (again) -- it costs $1,000.50.",
+            "Mr. Smith's dogs' toys cannot wait...",
+            "He said 'I'm done.'",
+        ] {
+            assert_eq!(detokenize(&tokenize(text)), text);
+        }
+    }
+
+    #[test]
+    fn tokenizes_like_the_treebank() {
+        assert_eq!(
+            tokenize("\"I can't believe it,\" she said (again) -- it costs $1,000.50."),
+            vec![
+                "``", "I", "ca", "n't", "believe", "it", ",", "''", "she", "said", "-LRB-",
+                "again", "-RRB-", "--", "it", "costs", "$", "1,000.50", "."
+            ]
+        );
+        assert_eq!(
+            tokenize("Mr. Smith's dogs' toys cannot wait..."),
+            vec!["Mr.", "Smith", "'s", "dogs", "'", "toys", "can", "not", "wait", "..."]
+        );
+        assert_eq!(
+            tokenize("He said 'I'm done.'"),
+            vec!["He", "said", "`", "I", "'m", "done", ".", "'"]
+        );
+    }
+}
@@ -0,0 +1,435 @@
+use crate::ptb::{Descendants, ParseTree};
+
+/// Joins the labels of a collapsed unary chain (e.g. S+VP+VB)
+pub const UNARY_DELIMITER: char = '+';
+
+/// Preterminals that are ignored by evalb and thus removed or raised as punctuation
+pub const PUNCTUATION_TAGS: [&str; 5] = ["``", "''", ",", ".", ":"];
+
+/// Separates the root of a left-corner chain from the node it continues, e.g. S/NP is an S whose left part NP is complete
+pub const LEFT_CORNER_DELIMITER: char = '/';
+
+/// Placeholder for numbers, see normalize_numbers()
+pub const NUMBER: &str = "<NUM>";
+
+fn is_punctuation(tree: &ParseTree<String>) -> bool {
+    matches!(tree.descendants, Descendants::Atom(_))
+        && PUNCTUATION_TAGS.contains(&tree.root.as_str())
+}
+
+/// Removes all punctuation preterminals and the nodes that are left without words.
+/// Returns false if the whole tree consisted of punctuation
+pub fn remove_punctuation(tree: &mut ParseTree<String>) -> bool {
+    match &mut tree.descendants {
+        Descendants::Atom(_) => !is_punctuation(tree),
+        Descendants::Expressions(trees) => {
+            trees.retain_mut(remove_punctuation);
+            !trees.is_empty()
+        }
+    }
+}
+
+/// Moves punctuation at the edges of constituents as high up as possible below the root,
+/// e.g. (S (NP (NNP Pierre) (, ,)) (VP (VBD slept) (. .))) becomes (S (NP (NNP Pierre)) (, ,) (VP (VBD slept)) (. .))
+pub fn raise_punctuation(tree: &mut ParseTree<String>) {
+    if let Descendants::Expressions(trees) = &mut tree.descendants {
+        trees.iter_mut().for_each(raise_into);
+    }
+}
+
+/// Raises the punctuation of the subtrees first and then takes the punctuation at their edges as own children
+fn raise_into(node: &mut ParseTree<String>) {
+    let trees = match &mut node.descendants {
+        Descendants::Atom(_) => return,
+        Descendants::Expressions(trees) => trees,
+    };
+    trees.iter_mut().for_each(raise_into);
+
+    let mut raised = Vec::with_capacity(trees.len());
+    for mut tree in trees.drain(..) {
+        let (leading, trailing) = match &mut tree.descendants {
+            Descendants::Expressions(children) => split_edge_punctuation(children),
+            Descendants::Atom(_) => (vec![], vec![]),
+        };
+
+        raised.extend(leading);
+        raised.push(tree);
+        raised.extend(trailing);
+    }
+    *trees = raised;
+}
+
+/// Takes the punctuation from the start and the end of the children, but keeps at least one child
+fn split_edge_punctuation(
+    children: &mut Vec<ParseTree<String>>,
+) -> (Vec<ParseTree<String>>, Vec<ParseTree<String>>) {
+    let leading = children
+        .iter()
+        .take(children.len().saturating_sub(1))
+        .take_while(|child| is_punctuation(child))
+        .count();
+    let leading: Vec<ParseTree<String>> = children.drain(..leading).collect();
+
+    let trailing = children
+        .iter()
+        .skip(1)
+        .rev()
+        .take_while(|child| is_punctuation(child))
+        .count();
+    let trailing = children.split_off(children.len() - trailing);
+
+    (leading, trailing)
+}
+
+/// Merges every unary chain below the root into a single node, e.g. (S (VP (VB stop))) becomes (S+VP+VB stop).
+/// The root itself is kept, so that the initial nonterminal doesn't change
+pub fn collapse_unaries(tree: &mut ParseTree<String>) {
+    if let Descendants::Expressions(trees) = &mut tree.descendants {
+        for tree in trees {
+            tree.transform(collapse_chain);
+        }
+    }
+}
+
+fn collapse_chain(node: &mut ParseTree<String>) {
+    loop {
+        let child = match &mut node.descendants {
+            Descendants::Expressions(trees) if trees.len() == 1 => trees.remove(0),
+            _ => return,
+        };
+
+        node.root.push(UNARY_DELIMITER);
+        node.root.push_str(&child.root);
+        node.descendants = child.descendants;
+    }
+}
+
+/// Case-folds all terminals, so that e.g. sentence initial words share their lexicon entries with the others
+pub fn lowercase_terminals(tree: &mut ParseTree<String>) {
+    tree.transform(|node| {
+        if let Descendants::Atom(word) = &mut node.descendants {
+            *word = word.to_lowercase();
+        }
+    });
+}
+
+/// Whether the word only consists of digits, commas and periods with at least one digit (e.g. 1,000 or 3.14)
+pub fn is_number(word: &str) -> bool {
+    word.chars().any(|c| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ',' || c == '.')
+}
+
+/// Replaces all numbers (see is_number()) by NUMBER
+pub fn normalize_numbers(tree: &mut ParseTree<String>) {
+    tree.transform(|node| match &mut node.descendants {
+        Descendants::Atom(word) if is_number(word) => *word = String::from(NUMBER),
+        _ => {}
+    });
+}
+
+/// Removes the inner node of every unary whose parent and child share their label, e.g. (NP (NP (DT the) (NN dog))) becomes (NP (DT the) (NN dog))
+pub fn remove_x_over_x(tree: &mut ParseTree<String>) {
+    tree.transform(|node| loop {
+        let child = match &mut node.descendants {
+            Descendants::Expressions(trees) if trees.len() == 1 && trees[0].root == node.root => {
+                trees.remove(0)
+            }
+            _ => return,
+        };
+
+        node.descendants = child.descendants;
+    });
+}
+
+/// Reverts collapse_unaries() by splitting every collapsed label into its unary chain again,
+/// e.g. for the trees of a parser whose grammar was induced from collapsed trees
+pub fn expand_unaries(tree: &mut ParseTree<String>) {
+    tree.transform(expand_chain);
+}
+
+fn expand_chain(node: &mut ParseTree<String>) {
+    let labels: Vec<&str> = node.root.split(UNARY_DELIMITER).collect();
+
+    // Labels like + or A+ weren't made by collapse_unaries()
+    if labels.len() < 2 || labels.iter().any(|label| label.is_empty()) {
+        return;
+    }
+
+    let labels: Vec<String> = labels.into_iter().map(String::from).collect();
+    let descendants = std::mem::replace(&mut node.descendants, Descendants::Expressions(vec![]));
+    let chain = labels[1..]
+        .iter()
+        .rev()
+        .fold(descendants, |descendants, label| {
+            Descendants::Expressions(vec![ParseTree {
+                root: label.clone(),
+                descendants,
+            }])
+        });
+
+    node.root = labels[0].clone();
+    node.descendants = chain;
+}
+
+/// Applies the left-corner transform (Johnson 1998) to every constituent, which removes left recursion from the induced grammar:
+/// a constituent A whose left spine descends A -> X1 ... -> Xn -> P down to the preterminal P becomes (A P (A/P ...)),
+/// where A/Xk holds the right siblings of Xk followed by A/Xk-1, e.g. (S (NP (DT the) (NN dog)) (VP (VBD slept)))
+/// becomes (S (DT the) (S/DT (NN dog) (S/NP (VP (VBD slept))))). The empty A/A at the end of each chain is left out,
+/// and unary chains at the top of constituents are collapsed (see collapse_unaries()) so that no other node ends up empty.
+/// A unary root like ROOT is kept, so that the initial nonterminal doesn't change
+pub fn left_corner(tree: &mut ParseTree<String>) {
+    let root = take_tree(tree);
+
+    *tree = match root.descendants {
+        Descendants::Expressions(mut trees) if trees.len() == 1 => ParseTree {
+            root: root.root,
+            descendants: Descendants::Expressions(vec![left_corner_constituent(trees.remove(0))]),
+        },
+        descendants => left_corner_constituent(ParseTree {
+            root: root.root,
+            descendants,
+        }),
+    };
+}
+
+fn left_corner_constituent(mut tree: ParseTree<String>) -> ParseTree<String> {
+    collapse_chain(&mut tree);
+    let root = tree.root.clone();
+
+    // The labels of the spine below the root and the transformed right siblings of each spine node
+    let mut spine = Vec::new();
+    let mut siblings = Vec::new();
+    let mut node = tree;
+    let left_corner = loop {
+        let trees = match node.descendants {
+            Descendants::Atom(_) => break node,
+            Descendants::Expressions(trees) => trees,
+        };
+
+        let mut trees = trees.into_iter();
+        match trees.next() {
+            Some(first) => {
+                spine.push(first.root.clone());
+                siblings.push(trees.map(left_corner_constituent).collect::<Vec<_>>());
+                node = first;
+            }
+            None => {
+                break ParseTree {
+                    root: node.root,
+                    descendants: Descendants::Expressions(vec![]),
+                }
+            }
+        }
+    };
+
+    // Xk is completed by its right siblings into Xk-1, so A/Xk holds them followed by A/Xk-1
+    let chain = spine
+        .into_iter()
+        .zip(siblings)
+        .fold(None, |chain, (label, mut siblings)| {
+            siblings.extend(chain);
+            Some(ParseTree {
+                root: format!("{}{}{}", root, LEFT_CORNER_DELIMITER, label),
+                descendants: Descendants::Expressions(siblings),
+            })
+        });
+
+    match chain {
+        Some(chain) => ParseTree {
+            root,
+            descendants: Descendants::Expressions(vec![left_corner, chain]),
+        },
+        None => left_corner,
+    }
+}
+
+/// Reverts left_corner(), e.g. for the trees of a parser whose grammar was induced from left-corner trees
+pub fn undo_left_corner(tree: &mut ParseTree<String>) {
+    let root = take_tree(tree);
+
+    *tree = match root.descendants {
+        Descendants::Expressions(mut trees) if trees.len() == 1 => ParseTree {
+            root: root.root,
+            descendants: Descendants::Expressions(vec![undo_left_corner_constituent(
+                trees.remove(0),
+            )]),
+        },
+        descendants => undo_left_corner_constituent(ParseTree {
+            root: root.root,
+            descendants,
+        }),
+    };
+}
+
+fn undo_left_corner_constituent(tree: ParseTree<String>) -> ParseTree<String> {
+    let (mut node, mut chain) = match tree.descendants {
+        Descendants::Expressions(trees) if trees.len() == 2 => {
+            let mut trees = trees.into_iter();
+            (trees.next(), trees.next())
+        }
+        descendants => {
+            let mut tree = ParseTree {
+                root: tree.root,
+                descendants,
+            };
+            expand_chain(&mut tree);
+            return tree;
+        }
+    };
+
+    let prefix = format!("{}{}", tree.root, LEFT_CORNER_DELIMITER);
+    while let Some(link) = chain.take() {
+        let mut siblings = match link.descendants {
+            Descendants::Expressions(trees) => trees,
+            Descendants::Atom(_) => vec![],
+        };
+        if siblings
+            .last()
+            .is_some_and(|last| last.root.starts_with(&prefix))
+        {
+            chain = siblings.pop();
+        }
+
+        // The next link names the parent, and the last one completes the root
+        let label = match &chain {
+            Some(next) => next.root[prefix.len()..].to_string(),
+            None => tree.root.clone(),
+        };
+        let mut children: Vec<ParseTree<String>> = node.into_iter().collect();
+        children.extend(siblings.into_iter().map(undo_left_corner_constituent));
+        node = Some(ParseTree {
+            root: label,
+            descendants: Descendants::Expressions(children),
+        });
+    }
+
+    let mut tree = node.expect("The left corner or the chain forms a node");
+    expand_chain(&mut tree);
+    tree
+}
+
+/// Moves the tree out of the reference, leaving an empty node behind
+fn take_tree(tree: &mut ParseTree<String>) -> ParseTree<String> {
+    std::mem::replace(
+        tree,
+        ParseTree {
+            root: String::new(),
+            descendants: Descendants::Expressions(vec![]),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn removes_punctuation() {
+        let input = "(ROOT (S (`` ``) (NP (NNP Pierre) (, ,)) (VP (VBD slept)) (. .)))";
+        let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+        assert!(remove_punctuation(&mut tree));
+        assert_eq!(
+            tree.to_string(),
+            "(ROOT (S (NP (NNP Pierre)) (VP (VBD slept))))"
+        );
+
+        let mut tree = PTBParser::parse("(ROOT (S (. .)))").expect("This should be parsable");
+        assert!(!remove_punctuation(&mut tree));
+    }
+
+    #[test]
+    fn raises_punctuation() {
+        let input =
+            "(ROOT (S (NP (NP (NNP Pierre) (, ,)) (: :)) (VP (VBD slept) (. .)) (NP (. .))))";
+        let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+        raise_punctuation(&mut tree);
+        assert_eq!(
+            tree.to_string(),
+            "(ROOT (S (NP (NP (NNP Pierre))) (, ,) (: :) (VP (VBD slept)) (. .) (NP (. .))))"
+        );
+    }
+
+    #[test]
+    fn lowercases_terminals() {
+        let input = "(ROOT (S (NP (NNP The) (NNP EU)) (VP (VB Rules))))";
+        let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+        lowercase_terminals(&mut tree);
+        assert_eq!(
+            tree.to_string(),
+            "(ROOT (S (NP (NNP the) (NNP eu)) (VP (VB rules))))"
+        );
+    }
+
+    #[test]
+    fn normalizes_numbers() {
+        let input = "(ROOT (NP (CD 1,000.50) (CD 3) (NNS 1980s) (. .)))";
+        let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+        normalize_numbers(&mut tree);
+        assert_eq!(
+            tree.to_string(),
+            "(ROOT (NP (CD <NUM>) (CD <NUM>) (NNS 1980s) (. .)))"
+        );
+    }
+
+    #[test]
+    fn removes_x_over_x_unaries() {
+        let input = "(ROOT (S (NP (NP (NP (DT the) (NN dog)))) (VP (VB (VB barks)))))";
+        let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+        remove_x_over_x(&mut tree);
+        assert_eq!(
+            tree.to_string(),
+            "(ROOT (S (NP (DT the) (NN dog)) (VP (VB barks))))"
+        );
+    }
+
+    #[test]
+    fn transforms_into_left_corner_trees_and_back() {
+        for (input, transformed) in [
+            (
+                "(ROOT (S (NP (DT the) (NN dog)) (VP (VBD slept))))",
+                "(ROOT (S (DT the) (S/DT (NN dog) (S/NP (VP+VBD slept)))))",
+            ),
+            (
+                "(ROOT (S (NP (NP (NNP Pierre)) (PP (IN of) (NP (NNP Paris)))) (VP (VBD slept) (ADVP (RB well)))))",
+                "(ROOT (S (NNP Pierre) (S/NNP (S/NP (PP (IN of) (PP/IN (NP+NNP Paris))) (S/NP (VP (VBD slept) (VP/VBD (ADVP+RB well))))))))",
+            ),
+            ("(ROOT (S (VP (VB stop))))", "(ROOT (S+VP+VB stop))"),
+            ("(S (NP (PRP I)) (VP (VBD left)))", "(S (PRP I) (S/PRP (S/NP (VP+VBD left))))"),
+        ] {
+            let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+            left_corner(&mut tree);
+            assert_eq!(tree.to_string(), transformed);
+
+            undo_left_corner(&mut tree);
+            assert_eq!(tree.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn collapses_and_expands_unary_chains() {
+        for (input, collapsed) in [
+            ("(ROOT (S (VP (VB stop))))", "(ROOT (S+VP+VB stop))"),
+            (
+                "(ROOT (S (VP (VB stop)) (NP (NP (NN it)) (PP (IN now)))))",
+                "(ROOT (S (VP+VB stop) (NP (NP+NN it) (PP+IN now))))",
+            ),
+        ] {
+            let mut tree = PTBParser::parse(input).expect("This should be parsable");
+
+            collapse_unaries(&mut tree);
+            assert_eq!(tree.to_string(), collapsed);
+
+            expand_unaries(&mut tree);
+            assert_eq!(tree.to_string(), input);
+        }
+    }
+}
@@ -0,0 +1,699 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    induce::PCFGGrammar,
+    ptb::{Descendants, ParseTree},
+    Body, Grammar, Nonterminal, Occurence, OccurenceRules, Probability, Rule, Terminal,
+};
+
+/// Base of every signature class, which on its own stands for any unknown word
+pub const UNKNOWN: &str = "UNK";
+
+/// Suffixes that are encoded into signatures, checked in this order
+const SIGNATURE_SUFFIXES: [&str; 5] = ["ing", "ion", "ed", "ly", "s"];
+
+/// Length of the longest suffix in SIGNATURE_SUFFIXES, which encodes all of them
+pub const MAX_SIGNATURE_SUFFIX_LENGTH: usize = 3;
+
+/// Length of the longest suffix of the SuffixModel if none is given, like in TnT
+pub const MAX_OOV_SUFFIX_LENGTH: usize = 10;
+
+/// Selects the features that are encoded into the signature of an unknown word
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureOptions {
+    /// Encodes all-caps (-CAPS), initial capital (-INITC) or lowercase (-LC) words
+    pub capitalisation: bool,
+    /// Encodes words containing a digit (-NUM)
+    pub digits: bool,
+    /// Encodes words containing a hyphen (-DASH)
+    pub hyphens: bool,
+    /// Encodes the common suffixes (-ing, -ion, -ed, -ly, -s) of at most this many letters, where 0 encodes none
+    pub suffix_length: usize,
+}
+
+impl Default for SignatureOptions {
+    fn default() -> Self {
+        Self {
+            capitalisation: true,
+            digits: true,
+            hyphens: true,
+            suffix_length: MAX_SIGNATURE_SUFFIX_LENGTH,
+        }
+    }
+}
+
+impl SignatureOptions {
+    /// Names the options that differ from the default like the flags of induce (e.g. no-signature-digits or signature-suffix-length=2),
+    /// so that they can be recorded in the #transforms of a grammar
+    pub fn transforms(&self) -> Vec<String> {
+        let default = Self::default();
+
+        let mut transforms = Vec::new();
+        if self.capitalisation != default.capitalisation {
+            transforms.push(String::from("no-signature-capitalisation"));
+        }
+        if self.digits != default.digits {
+            transforms.push(String::from("no-signature-digits"));
+        }
+        if self.hyphens != default.hyphens {
+            transforms.push(String::from("no-signature-dash"));
+        }
+        if self.suffix_length != default.suffix_length {
+            transforms.push(format!("signature-suffix-length={}", self.suffix_length));
+        }
+
+        transforms
+    }
+
+    /// Reverts transforms(), where other transforms are ignored and malformed ones keep the default
+    pub fn from_transforms<S: AsRef<str>>(transforms: &[S]) -> Self {
+        let mut options = Self::default();
+
+        for transform in transforms {
+            match transform.as_ref().split_once('=') {
+                Some(("signature-suffix-length", length)) => {
+                    options.suffix_length = length.parse().unwrap_or(options.suffix_length)
+                }
+                Some(_) => {}
+                None => match transform.as_ref() {
+                    "no-signature-capitalisation" => options.capitalisation = false,
+                    "no-signature-digits" => options.digits = false,
+                    "no-signature-dash" => options.hyphens = false,
+                    _ => {}
+                },
+            }
+        }
+
+        options
+    }
+}
+
+/// Maps a word to its signature class (e.g. Walking -> UNK-INITC-ing).
+/// Both the replacement of rare words before induction and the lookup of unknown words must use this function,
+/// so that their classes agree
+pub fn signature(word: &str, options: &SignatureOptions) -> Terminal {
+    let mut signature = String::from(UNKNOWN);
+
+    if options.capitalisation {
+        let mut letters = word.chars().filter(|c| c.is_alphabetic()).peekable();
+
+        if letters.peek().is_some() {
+            if letters.all(|c| c.is_uppercase()) {
+                signature.push_str("-CAPS");
+            } else if word.chars().next().is_some_and(|c| c.is_uppercase()) {
+                signature.push_str("-INITC");
+            } else if word.chars().all(|c| !c.is_uppercase()) {
+                signature.push_str("-LC");
+            }
+        }
+    }
+
+    if options.digits && word.chars().any(|c| c.is_ascii_digit()) {
+        signature.push_str("-NUM");
+    }
+
+    if options.hyphens && word.contains('-') {
+        signature.push_str("-DASH");
+    }
+
+    if options.suffix_length > 0 {
+        let lowercase = word.to_lowercase();
+        let suffix = SIGNATURE_SUFFIXES.iter().find(|suffix| {
+            // A plural -s must not be mistaken in words like "class"
+            suffix.len() <= options.suffix_length
+                && lowercase.len() > suffix.len()
+                && lowercase.ends_with(*suffix)
+                && !(**suffix == "s" && lowercase.ends_with("ss"))
+        });
+
+        if let Some(suffix) = suffix {
+            signature.push('-');
+            signature.push_str(suffix);
+        }
+    }
+
+    signature
+}
+
+/// Finds the lexicon terminal of a word: the word itself if it is known, and its signature class otherwise.
+/// The class is generalised by dropping its last feature until it is known (e.g. UNK-LC-NUM-s -> UNK-LC-NUM -> UNK-LC -> UNK),
+/// which yields None if not even UNKNOWN is known
+pub fn lexicon_terminal(
+    word: &str,
+    is_known: impl Fn(&str) -> bool,
+    options: &SignatureOptions,
+) -> Option<Terminal> {
+    if is_known(word) {
+        return Some(word.to_string());
+    }
+
+    let mut class = signature(word, options);
+    loop {
+        if is_known(&class) {
+            return Some(class);
+        }
+
+        match class.rfind('-') {
+            Some(index) => class.truncate(index),
+            None => return None,
+        }
+    }
+}
+
+/// The lexical rules of the grammar a word is scanned with, from the most to the least probable one: its own if it is known
+/// and those of its lexicon_terminal() otherwise, which is None if it has none. Given a suffix model, the rules of an unknown word
+/// are weighed by its P(tag|suffix) / P(tag) and rewritten to the word, where tags the model never saw with rare words are dropped
+pub fn scanned_rules(
+    grammar: &Grammar,
+    word: &str,
+    options: &SignatureOptions,
+    suffix_model: Option<&SuffixModel>,
+) -> Option<Vec<(Rule, Probability)>> {
+    let known: HashSet<&str> = grammar
+        .lexical_rules()
+        .filter_map(|(rule, _probability)| match &rule.body {
+            Body::Lexical(terminal) => Some(terminal.as_str()),
+            Body::NonLexical(_) => None,
+        })
+        .collect();
+    let terminal = lexicon_terminal(word, |terminal| known.contains(terminal), options)?;
+    let class_rules = grammar
+        .lexical_rules()
+        .filter(|(rule, _probability)| rule.body == Body::Lexical(terminal.clone()));
+
+    let mut rules: Vec<(Rule, Probability)> = match suffix_model.filter(|_| terminal != word) {
+        Some(model) => class_rules
+            .map(|(rule, probability)| {
+                let weight = probability * model.lexical_weight(word, &rule.head);
+                let rule = Rule {
+                    head: rule.head.clone(),
+                    body: Body::Lexical(word.to_string()),
+                };
+                (rule, weight)
+            })
+            .filter(|(_rule, weight)| *weight > 0.0)
+            .collect(),
+        None => class_rules
+            .map(|(rule, probability)| (rule.clone(), probability))
+            .collect(),
+    };
+    // Like rules_of(), equally probable rules are ordered by their text
+    rules.sort_by(|(rule, probability), (other_rule, other_probability)| {
+        other_probability
+            .total_cmp(probability)
+            .then_with(|| rule.to_string().cmp(&other_rule.to_string()))
+    });
+
+    Some(rules)
+}
+
+/// Replaces every word that occurs fewer than threshold times in the trees by its signature class,
+/// or by UNKNOWN if no signature options are given. This yields the same vocabulary as fold_rare_words()
+pub fn replace_rare_words(
+    trees: &mut [ParseTree<String>],
+    threshold: Occurence,
+    options: Option<&SignatureOptions>,
+) {
+    let mut word_occurences: HashMap<String, Occurence> = HashMap::new();
+    for word in trees.iter().flat_map(ParseTree::leaves) {
+        *word_occurences.entry(word.to_string()).or_insert(0) += 1;
+    }
+
+    let replace = |word: &mut String| {
+        if word_occurences[word.as_str()] < threshold {
+            *word = match options {
+                Some(options) => signature(word, options),
+                None => String::from(UNKNOWN),
+            };
+        }
+    };
+
+    for tree in trees.iter_mut() {
+        tree.transform(|node| {
+            if let Descendants::Atom(word) = &mut node.descendants {
+                replace(word);
+            }
+        });
+    }
+}
+
+/// Merges the counted lexical rules of every word that occurs fewer than threshold times into those of its signature class,
+/// or of UNKNOWN if no signature options are given. Unlike replace_rare_words(), this doesn't need another pass over the trees
+pub fn fold_rare_words(
+    occurence_rules: OccurenceRules,
+    threshold: Occurence,
+    options: Option<&SignatureOptions>,
+) -> OccurenceRules {
+    let mut word_occurences: HashMap<Terminal, Occurence> = HashMap::new();
+    for (rule, occurence) in occurence_rules.iter() {
+        if let Body::Lexical(word) = &rule.body {
+            *word_occurences.entry(word.clone()).or_insert(0) += occurence;
+        }
+    }
+
+    let mut folded = OccurenceRules::default();
+    for (mut rule, occurence) in occurence_rules {
+        match &mut rule.body {
+            Body::Lexical(word) if word_occurences[word.as_str()] < threshold => {
+                *word = match options {
+                    Some(options) => signature(word, options),
+                    None => String::from(UNKNOWN),
+                };
+            }
+            _ => {}
+        }
+
+        *folded.entry(rule).or_insert(0) += occurence;
+    }
+
+    folded
+}
+
+/// Counts the suffixes of up to max_suffix_length characters of every word that occurs fewer than threshold times,
+/// i.e. of the words fold_rare_words() merges, as lexical rules of their tags (e.g. VBG ing), which SuffixModel::estimate() reads
+pub fn count_suffixes(
+    occurence_rules: &OccurenceRules,
+    max_suffix_length: usize,
+    threshold: Occurence,
+) -> OccurenceRules {
+    let mut word_occurences: HashMap<&str, Occurence> = HashMap::new();
+    for (rule, occurence) in occurence_rules.iter() {
+        if let Body::Lexical(word) = &rule.body {
+            *word_occurences.entry(word).or_insert(0) += occurence;
+        }
+    }
+
+    let mut suffix_counts = OccurenceRules::default();
+    for (rule, occurence) in occurence_rules.iter() {
+        let word = match &rule.body {
+            Body::Lexical(word) if word_occurences[word.as_str()] < threshold => word,
+            _ => continue,
+        };
+
+        for suffix in suffixes(word, max_suffix_length) {
+            let suffix_rule = Rule {
+                head: rule.head.clone(),
+                body: Body::Lexical(suffix.to_string()),
+            };
+            *suffix_counts.entry(suffix_rule).or_insert(0) += occurence;
+        }
+    }
+
+    suffix_counts
+}
+
+/// Suffix based model for unknown words (Brants, 2000: TnT).
+/// P(tag|suffix) is estimated from the rare words of the training data for all suffixes up to a maximum length
+/// and smoothed by successive abstraction towards shorter suffixes
+pub struct SuffixModel {
+    max_suffix_length: usize,
+    theta: Probability,
+    tag_probabilities: HashMap<Nonterminal, Probability>,
+    suffix_tag_probabilities: HashMap<String, HashMap<Nonterminal, Probability>>,
+}
+
+impl SuffixModel {
+    /// Estimates the model from the suffix counts of count_suffixes(). Every rare word has a suffix of one character,
+    /// so those are the occurences of the tags among the rare words
+    pub fn estimate(suffix_counts: &OccurenceRules) -> Self {
+        let mut max_suffix_length = 0;
+        let mut tag_occurences: HashMap<Nonterminal, Occurence> = HashMap::new();
+        let mut suffix_tag_occurences: HashMap<String, HashMap<Nonterminal, Occurence>> =
+            HashMap::new();
+
+        for (rule, occurence) in suffix_counts.iter() {
+            let suffix = match &rule.body {
+                Body::Lexical(suffix) => suffix,
+                Body::NonLexical(_) => continue,
+            };
+
+            let length = suffix.chars().count();
+            max_suffix_length = max_suffix_length.max(length);
+            if length == 1 {
+                *tag_occurences.entry(rule.head.clone()).or_insert(0) += occurence;
+            }
+            *suffix_tag_occurences
+                .entry(suffix.clone())
+                .or_default()
+                .entry(rule.head.clone())
+                .or_insert(0) += occurence;
+        }
+
+        let tag_probabilities = relative_frequencies(tag_occurences);
+        let suffix_tag_probabilities = suffix_tag_occurences
+            .into_iter()
+            .map(|(suffix, tag_occurences)| (suffix, relative_frequencies(tag_occurences)))
+            .collect();
+
+        // TnT uses the standard deviation of the unconditioned tag probabilities as interpolation weight
+        let tags = tag_probabilities.len() as Probability;
+        let theta = if tags > 1.0 {
+            let mean = 1.0 / tags;
+            (tag_probabilities
+                .values()
+                .map(|probability| (probability - mean).powi(2))
+                .sum::<Probability>()
+                / (tags - 1.0))
+                .sqrt()
+        } else {
+            0.0
+        };
+
+        Self {
+            max_suffix_length,
+            theta,
+            tag_probabilities,
+            suffix_tag_probabilities,
+        }
+    }
+
+    /// P(tag|suffix) for the longest known suffix of word
+    pub fn tag_probability(&self, word: &str, tag: &str) -> Probability {
+        let mut probability = self.tag_probabilities.get(tag).copied().unwrap_or(0.0);
+
+        // P(t|l_n-i+1, ..., l_n) = (P'(t|l_n-i+1, ..., l_n) + theta * P(t|l_n-i+2, ..., l_n)) / (1 + theta)
+        for suffix in suffixes(word, self.max_suffix_length) {
+            match self.suffix_tag_probabilities.get(suffix) {
+                Some(tag_probabilities) => {
+                    let suffix_probability = tag_probabilities.get(tag).copied().unwrap_or(0.0);
+                    probability =
+                        (suffix_probability + self.theta * probability) / (1.0 + self.theta);
+                }
+                None => break,
+            }
+        }
+
+        probability
+    }
+
+    /// P(word|tag) up to the factor P(word), which is the same for all tags, via Bayes inversion:
+    /// P(word|tag) = P(tag|suffix) * P(word) / P(tag)
+    pub fn lexical_weight(&self, word: &str, tag: &str) -> Probability {
+        match self.tag_probabilities.get(tag) {
+            Some(tag_probability) => self.tag_probability(word, tag) / tag_probability,
+            None => 0.0,
+        }
+    }
+
+    /// All tags that were seen with rare words
+    pub fn tags(&self) -> impl Iterator<Item = &Nonterminal> {
+        self.tag_probabilities.keys()
+    }
+}
+
+/// Returns the non-empty suffixes of word from the shortest up to max_suffix_length characters
+fn suffixes(word: &str, max_suffix_length: usize) -> impl Iterator<Item = &str> {
+    word.char_indices()
+        .rev()
+        .take(max_suffix_length)
+        .map(move |(index, _char)| &word[index..])
+}
+
+fn relative_frequencies(
+    occurences: HashMap<Nonterminal, Occurence>,
+) -> HashMap<Nonterminal, Probability> {
+    let total: Occurence = occurences.values().sum();
+
+    occurences
+        .into_iter()
+        .map(|(tag, occurence)| (tag, occurence as Probability / total as Probability))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{induce::count_rules, ptb::PTBParser};
+
+    #[test]
+    fn generates_signatures() {
+        let options = SignatureOptions::default();
+
+        assert_eq!(signature("walking", &options), "UNK-LC-ing");
+        assert_eq!(signature("Walking", &options), "UNK-INITC-ing");
+        assert_eq!(signature("NASA", &options), "UNK-CAPS");
+        assert_eq!(signature("mid-1990s", &options), "UNK-LC-NUM-DASH-s");
+        assert_eq!(signature("1,000", &options), "UNK-NUM");
+        assert_eq!(signature("class", &options), "UNK-LC");
+        assert_eq!(signature("iPod", &options), "UNK");
+    }
+
+    #[test]
+    fn replaces_rare_words() {
+        let mut trees = vec![
+            PTBParser::parse("(S (NP (DT the) (NN dog)) (VP (VBZ barks)))").expect("parsable"),
+            PTBParser::parse("(S (NP (DT the) (NNS Cats)) (VP (VBD purred)))").expect("parsable"),
+        ];
+
+        replace_rare_words(&mut trees, 2, None);
+        assert_eq!(
+            trees[0].to_string(),
+            "(S (NP (DT the) (NN UNK)) (VP (VBZ UNK)))"
+        );
+
+        let mut trees = vec![
+            PTBParser::parse("(S (NP (DT the) (NNS Cats)) (VP (VBD purred)))").expect("parsable"),
+            PTBParser::parse("(S (NP (DT the) (NN dog)))").expect("parsable"),
+        ];
+
+        replace_rare_words(&mut trees, 2, Some(&SignatureOptions::default()));
+        assert_eq!(
+            trees[0].to_string(),
+            "(S (NP (DT the) (NNS UNK-INITC-s)) (VP (VBD UNK-LC-ed)))"
+        );
+    }
+
+    #[test]
+    fn folds_rare_words_into_unknown() {
+        let lexical = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let occurence_rules = OccurenceRules::from_iter(vec![
+            (lexical("DT", "the"), 2),
+            (lexical("NN", "dog"), 1),
+            (lexical("NN", "cat"), 1),
+            (lexical("NNS", "Cats"), 1),
+        ]);
+
+        let folded = fold_rare_words(occurence_rules.clone(), 2, None);
+        assert_eq!(
+            folded,
+            OccurenceRules::from_iter(vec![
+                (lexical("DT", "the"), 2),
+                (lexical("NN", "UNK"), 2),
+                (lexical("NNS", "UNK"), 1),
+            ])
+        );
+
+        let folded = fold_rare_words(occurence_rules, 2, Some(&SignatureOptions::default()));
+        assert_eq!(folded[&lexical("NN", "UNK-LC")], 2);
+        assert_eq!(folded[&lexical("NNS", "UNK-INITC-s")], 1);
+    }
+
+    #[test]
+    fn folds_and_replaces_the_same_words() {
+        let trees = [
+            "(S (NP (DT the) (NN dog)) (VP (VBZ barks)))",
+            "(S (NP (DT the) (NN dog)) (VP (VBD barked)))",
+            "(S (NP (DT a) (NNS Cats)) (VP (VBD purred)))",
+        ]
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+        let options = SignatureOptions::default();
+
+        for threshold in 1..=3 {
+            let counts =
+                count_rules("S", trees.clone()).expect("The trees have the initial as root");
+            let folded = fold_rare_words(counts, threshold, Some(&options));
+
+            let mut replaced = trees.clone();
+            replace_rare_words(&mut replaced, threshold, Some(&options));
+            let replaced = count_rules("S", replaced).expect("The trees have the initial as root");
+
+            assert_eq!(folded, replaced);
+        }
+    }
+
+    #[test]
+    fn yields_suffixes_shortest_first() {
+        assert_eq!(
+            suffixes("süß", 5).collect::<Vec<&str>>(),
+            vec!["ß", "üß", "süß"]
+        );
+        assert_eq!(
+            suffixes("running", 3).collect::<Vec<&str>>(),
+            vec!["g", "ng", "ing"]
+        );
+    }
+
+    #[test]
+    fn counts_suffixes_of_rare_words() {
+        let lexical = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let occurence_rules = OccurenceRules::from_iter(vec![
+            (lexical("DT", "the"), 2),
+            (lexical("NN", "dog"), 1),
+            (lexical("NN", "log"), 1),
+        ]);
+
+        assert_eq!(
+            count_suffixes(&occurence_rules, 2, 2),
+            OccurenceRules::from_iter(vec![(lexical("NN", "g"), 2), (lexical("NN", "og"), 2),])
+        );
+    }
+
+    #[test]
+    fn suffix_model_prefers_tags_of_matching_suffixes() {
+        let lexical = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let occurence_rules = OccurenceRules::from_iter(vec![
+            (lexical("VBG", "running"), 1),
+            (lexical("VBG", "eating"), 1),
+            (lexical("NN", "ring"), 1),
+            (lexical("NN", "table"), 1),
+            // Frequent words are not used for estimation
+            (lexical("DT", "the"), 20),
+        ]);
+
+        let model = SuffixModel::estimate(&count_suffixes(&occurence_rules, 3, 10));
+
+        assert!(model.tag_probability("singing", "VBG") > model.tag_probability("singing", "NN"));
+        assert!(model.tag_probability("cable", "NN") > model.tag_probability("cable", "VBG"));
+        assert_eq!(model.tag_probability("singing", "DT"), 0.0);
+        assert_eq!(model.lexical_weight("singing", "DT"), 0.0);
+
+        // Successive abstraction interpolates distributions, so they still sum up to one
+        let total: Probability = model
+            .tags()
+            .map(|tag| model.tag_probability("singing", tag))
+            .sum();
+        assert!((total - 1.0).abs() <= 1e-12);
+
+        // Both tags are equally likely among the rare words, so the Bayes inversion keeps their ratio
+        let ratio = model.lexical_weight("singing", "VBG") / model.lexical_weight("singing", "NN");
+        assert!(
+            (ratio
+                - model.tag_probability("singing", "VBG") / model.tag_probability("singing", "NN"))
+            .abs()
+                <= 1e-12
+        );
+    }
+
+    #[test]
+    fn weighs_unknown_words_by_their_suffix() {
+        let lexical = |head: &str, word: &str| Rule {
+            head: head.to_string(),
+            body: Body::Lexical(word.to_string()),
+        };
+        let occurence_rules = OccurenceRules::from_iter(vec![
+            (lexical("VBG", "running"), 1),
+            (lexical("VBG", "eating"), 1),
+            (lexical("NN", "ring"), 1),
+            (lexical("NN", "table"), 1),
+            (lexical("NN", "dog"), 2),
+        ]);
+        let model = SuffixModel::estimate(&count_suffixes(&occurence_rules, 3, 2));
+        let grammar = Grammar::from_occurence_rules(
+            String::from("ROOT"),
+            fold_rare_words(occurence_rules, 2, None),
+            None,
+        );
+        let options = SignatureOptions::default();
+
+        // Known words keep their rules
+        assert_eq!(
+            scanned_rules(&grammar, "dog", &options, Some(&model)),
+            Some(vec![(lexical("NN", "dog"), 0.5)])
+        );
+        // Without the model, the rules of UNK are scanned
+        assert_eq!(
+            scanned_rules(&grammar, "singing", &options, None),
+            Some(vec![
+                (lexical("VBG", "UNK"), 1.0),
+                (lexical("NN", "UNK"), 0.5)
+            ])
+        );
+
+        let rules = scanned_rules(&grammar, "singing", &options, Some(&model))
+            .expect("UNK is part of the lexicon");
+        let heads: Vec<&str> = rules
+            .iter()
+            .map(|(rule, _weight)| rule.head.as_str())
+            .collect();
+        assert_eq!(heads, vec!["VBG", "NN"]);
+        assert_eq!(rules[0].0, lexical("VBG", "singing"));
+        assert!(rules[0].1 > 1.0 && rules[1].1 < 0.5);
+
+        let empty = Grammar::from_occurence_rules(
+            String::from("ROOT"),
+            OccurenceRules::from_iter(vec![(lexical("NN", "dog"), 1)]),
+            None,
+        );
+        assert_eq!(scanned_rules(&empty, "singing", &options, None), None);
+    }
+
+    #[test]
+    fn looks_up_generalised_signature_classes() {
+        let lexicon = ["walk", "UNK-LC", "UNK"];
+        let is_known = |terminal: &str| lexicon.contains(&terminal);
+        let options = SignatureOptions::default();
+
+        assert_eq!(
+            lexicon_terminal("walk", is_known, &options).as_deref(),
+            Some("walk")
+        );
+        assert_eq!(
+            lexicon_terminal("walking", is_known, &options).as_deref(),
+            Some("UNK-LC")
+        );
+        assert_eq!(
+            lexicon_terminal("Walking", is_known, &options).as_deref(),
+            Some("UNK")
+        );
+        assert_eq!(lexicon_terminal("Walking", |_| false, &options), None);
+    }
+
+    #[test]
+    fn generates_signatures_for_selected_features() {
+        let options = SignatureOptions {
+            capitalisation: false,
+            digits: true,
+            hyphens: false,
+            suffix_length: 0,
+        };
+
+        assert_eq!(signature("Walking", &options), "UNK");
+        assert_eq!(signature("mid-1990s", &options), "UNK-NUM");
+
+        let options = SignatureOptions {
+            suffix_length: 2,
+            ..SignatureOptions::default()
+        };
+        assert_eq!(signature("walking", &options), "UNK-LC");
+        assert_eq!(signature("walked", &options), "UNK-LC-ed");
+    }
+
+    #[test]
+    fn records_signature_options_as_transforms() {
+        assert!(SignatureOptions::default().transforms().is_empty());
+
+        let options = SignatureOptions {
+            digits: false,
+            suffix_length: 1,
+            ..SignatureOptions::default()
+        };
+        let transforms = options.transforms();
+        assert_eq!(
+            transforms,
+            vec!["no-signature-digits", "signature-suffix-length=1"]
+        );
+
+        let mut recorded = vec![String::from("lowercase")];
+        recorded.extend(transforms);
+        assert_eq!(SignatureOptions::from_transforms(&recorded), options);
+    }
+}
@@ -0,0 +1,143 @@
+use std::{
+    collections::HashMap,
+    io::{BufWriter, Write},
+};
+
+use crate::{
+    berkeley::Escaped,
+    ptb::{Descendants, ParseTree},
+    Nonterminal, Terminal,
+};
+
+/// Frequencies of the words of a corpus, e.g. to choose the threshold below which words are replaced by UNK
+#[derive(Debug, Default)]
+pub struct Vocabulary {
+    /// Counts each word per preterminal instead of overall
+    by_preterminal: bool,
+    /// The preterminal is None unless by_preterminal is set
+    frequencies: HashMap<(Terminal, Option<Nonterminal>), u64>,
+}
+
+impl Vocabulary {
+    pub fn new(by_preterminal: bool) -> Self {
+        Vocabulary {
+            by_preterminal,
+            frequencies: HashMap::new(),
+        }
+    }
+
+    /// Counts the words of the tree, together with their preterminal if the vocabulary is split by preterminal
+    pub fn add_tree(&mut self, tree: &ParseTree<String>) {
+        for preterminal in tree.preterminals() {
+            if let Descendants::Atom(word) = &preterminal.descendants {
+                let head = self.by_preterminal.then(|| preterminal.root.clone());
+                *self.frequencies.entry((word.clone(), head)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// Counts words without preterminals, e.g. those of a tokenized sentence
+    pub fn add_words<S: AsRef<str>>(&mut self, words: &[S]) {
+        for word in words {
+            *self
+                .frequencies
+                .entry((word.as_ref().to_string(), None))
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// Number of distinct entries, i.e. words or pairs of word and preterminal
+    pub fn types(&self) -> usize {
+        self.frequencies.len()
+    }
+
+    pub fn tokens(&self) -> u64 {
+        self.frequencies.values().sum()
+    }
+
+    /// Number of entries that occur exactly once (hapax legomena)
+    pub fn singletons(&self) -> usize {
+        self.frequencies
+            .values()
+            .filter(|count| **count == 1)
+            .count()
+    }
+
+    /// The entries from the most to the least frequent one, where ties are sorted by word and preterminal
+    pub fn entries(&self) -> Vec<(&str, Option<&str>, u64)> {
+        let mut entries: Vec<(&str, Option<&str>, u64)> = self
+            .frequencies
+            .iter()
+            .map(|((word, head), count)| (word.as_str(), head.as_deref(), *count))
+            .collect();
+        entries.sort_unstable_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+
+        entries
+    }
+
+    /// Writes a header followed by one WORD\tCOUNT (or WORD\tPRETERMINAL\tCOUNT) line per entry occuring at least min_count times.
+    /// Symbols are escaped like in the grammar files
+    pub fn write_tsv<W: Write>(&self, w: &mut W, min_count: u64) -> std::io::Result<()> {
+        let mut w = BufWriter::new(w);
+
+        match self.by_preterminal {
+            true => writeln!(w, "word\tpreterminal\tcount")?,
+            false => writeln!(w, "word\tcount")?,
+        }
+        for (word, head, count) in self.entries() {
+            if count < min_count {
+                break;
+            }
+
+            write!(w, "{}", Escaped(word))?;
+            if let Some(head) = head {
+                write!(w, "\t{}", Escaped(head))?;
+            }
+            writeln!(w, "\t{}", count)?;
+        }
+
+        w.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ptb::PTBParser;
+
+    #[test]
+    fn counts_words_by_preterminal() {
+        let trees = [
+            "(S (NP (NN run)) (VP (VB run)))",
+            "(S (NP (NN dogs)) (VP (VB run)))",
+        ]
+        .map(|tree| PTBParser::parse(tree).expect("This should be parsable"));
+
+        let mut vocabulary = Vocabulary::new(false);
+        let mut by_preterminal = Vocabulary::new(true);
+        for tree in trees.iter() {
+            vocabulary.add_tree(tree);
+            by_preterminal.add_tree(tree);
+        }
+
+        let mut tsv = Vec::new();
+        vocabulary
+            .write_tsv(&mut tsv, 1)
+            .expect("Writing to a Vec works");
+        assert_eq!(
+            std::str::from_utf8(&tsv),
+            Ok("word\tcount\nrun\t3\ndogs\t1\n")
+        );
+        assert_eq!((vocabulary.types(), vocabulary.tokens()), (2, 4));
+        assert_eq!(vocabulary.singletons(), 1);
+
+        let mut tsv = Vec::new();
+        by_preterminal
+            .write_tsv(&mut tsv, 2)
+            .expect("Writing to a Vec works");
+        assert_eq!(
+            std::str::from_utf8(&tsv),
+            Ok("word\tpreterminal\tcount\nrun\tVB\t2\n")
+        );
+    }
+}
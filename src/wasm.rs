@@ -0,0 +1,53 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    berkeley::{BerkeleyFormatWriter, BerkeleyWriter},
+    induce::PCFGGrammar,
+    ptb::PTBParser,
+    Grammar,
+};
+
+/// An induced grammar, which JavaScript can only read in the Berkeley format
+#[wasm_bindgen]
+pub struct WasmGrammar {
+    grammar: Grammar,
+}
+
+#[wasm_bindgen]
+impl WasmGrammar {
+    /// The nonlexical rules as HEAD -> BODY PROBABILITY lines
+    pub fn rules(&self) -> Result<String, JsError> {
+        let mut rules = Vec::new();
+        BerkeleyWriter::from_grammar(&self.grammar).rules_io(&mut rules)?;
+
+        Ok(String::from_utf8(rules)?)
+    }
+
+    /// The lexical rules as HEAD TERMINAL PROBABILITY lines
+    pub fn lexicon(&self) -> Result<String, JsError> {
+        let mut lexicon = Vec::new();
+        BerkeleyWriter::from_grammar(&self.grammar).lexicon_io(&mut lexicon)?;
+
+        Ok(String::from_utf8(lexicon)?)
+    }
+
+    /// The terminals, one per line
+    pub fn words(&self) -> Result<String, JsError> {
+        let mut words = Vec::new();
+        BerkeleyWriter::from_grammar(&self.grammar).words_io(&mut words)?;
+
+        Ok(String::from_utf8(words)?)
+    }
+}
+
+/// Induces a grammar with initial ROOT from a treebank, where trees may span multiple lines
+#[wasm_bindgen]
+pub fn induce_from_ptb(treebank: &str) -> Result<WasmGrammar, JsError> {
+    let trees = PTBParser::parse_treebank(treebank, "ROOT")
+        .map_err(|e| JsError::new(&format!("Malformed treebank at {}", e)))?;
+
+    let grammar = Grammar::from_parse_trees(String::from("ROOT"), trees)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+
+    Ok(WasmGrammar { grammar })
+}
@@ -0,0 +1,30 @@
+//! Runs the pcfg_tool binary on a small treebank in a directory of its own.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+pub const TREEBANK: &str = "(ROOT (S (NP (DT the) (NN dog)) (VP (VBZ barks))))
+(ROOT (S (NP (DT the) (NN cat)) (VP (VBZ sleeps))))
+(ROOT (S (NP (DT a) (NN dog)) (VP (VBZ sleeps))))
+";
+
+/// Creates an empty directory for the files of one test below the temporary directory
+pub fn workdir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("pcfg_tool-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("Temporary directory is writable");
+    dir
+}
+
+pub fn pcfg_tool(dir: &Path, args: &[&str]) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_pcfg_tool"))
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .expect("pcfg_tool runs");
+    assert!(output.status.success(), "pcfg_tool {:?} failed", args);
+    output
+}
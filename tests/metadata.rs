@@ -0,0 +1,82 @@
+//! Runs the pcfg_tool binary on a small treebank and checks the metadata written with the grammars.
+
+mod common;
+
+use std::{fs, path::Path};
+
+use pcfg_tool::Grammar;
+
+use common::{pcfg_tool, workdir, TREEBANK};
+
+fn recorded_transforms(dir: &Path, grammar: &str) -> Vec<String> {
+    let path = dir.join(grammar);
+    let metadata = Grammar::load_metadata(path.to_str().expect("Path is UTF-8"))
+        .expect("Metadata is readable")
+        .expect("Metadata is written");
+    metadata.transforms
+}
+
+#[test]
+fn projecting_keeps_the_signature_options() {
+    let dir = workdir("project");
+    fs::write(dir.join("treebank"), TREEBANK).expect("Treebank is writable");
+
+    pcfg_tool(
+        &dir,
+        &[
+            "induce",
+            "--vocab-threshold",
+            "2",
+            "--signatures",
+            "--signature-suffix-length",
+            "1",
+            "g",
+            "treebank",
+        ],
+    );
+    pcfg_tool(&dir, &["project", "g", "p"]);
+
+    let transforms = recorded_transforms(&dir, "p");
+    assert_eq!(transforms, recorded_transforms(&dir, "g"));
+    assert!(transforms.contains(&String::from("signatures")));
+    assert!(transforms.contains(&String::from("signature-suffix-length=1")));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn rewriting_a_container_writes_a_container() {
+    let dir = workdir("container");
+    fs::write(dir.join("treebank"), TREEBANK).expect("Treebank is writable");
+
+    pcfg_tool(
+        &dir,
+        &[
+            "induce",
+            "--vocab-threshold",
+            "2",
+            "--signatures",
+            "--pcfg",
+            "g",
+            "treebank",
+        ],
+    );
+    pcfg_tool(&dir, &["project", "g.pcfg", "p"]);
+    pcfg_tool(&dir, &["prune", "--top-k", "1", "g.pcfg", "q"]);
+    pcfg_tool(&dir, &["renormalize", "g.pcfg", "r"]);
+
+    for grammar in ["p", "q", "r"] {
+        let container = format!("{}.pcfg", grammar);
+        assert!(dir.join(&container).exists(), "{} is missing", container);
+        assert!(!dir.join(format!("{}.rules", grammar)).exists());
+        assert_eq!(
+            recorded_transforms(&dir, &container),
+            vec![
+                String::from("vocab-threshold=2"),
+                String::from("signatures")
+            ]
+        );
+    }
+
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,43 @@
+//! Runs the pcfg_tool binary on a small treebank and checks the rules printed by query.
+
+mod common;
+
+use std::fs;
+
+use common::{pcfg_tool, workdir, TREEBANK};
+
+#[test]
+fn prints_counts_of_induced_grammars() {
+    let dir = workdir("query-counts");
+    fs::write(dir.join("treebank"), TREEBANK).expect("Treebank is writable");
+
+    pcfg_tool(&dir, &["induce", "--counts", "g", "treebank"]);
+    let output = pcfg_tool(&dir, &["query", "g", "--head", "NN"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "NN dog 0.6666666666666666 2\nNN cat 0.3333333333333333 1\n"
+    );
+
+    // Counts of containers are written beside them as well
+    pcfg_tool(&dir, &["induce", "--counts", "--pcfg", "c", "treebank"]);
+    let output = pcfg_tool(&dir, &["query", "c.pcfg", "NP -> DT NN"]);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "NP -> DT NN 1 3\n");
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn says_that_counts_are_unavailable() {
+    let dir = workdir("query-no-counts");
+    fs::write(dir.join("treebank"), TREEBANK).expect("Treebank is writable");
+
+    pcfg_tool(&dir, &["induce", "g", "treebank"]);
+    let output = pcfg_tool(&dir, &["query", "g", "NN dog"]);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "NN dog 0.6666666666666666\n"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Counts are unavailable"));
+
+    let _ = fs::remove_dir_all(&dir);
+}